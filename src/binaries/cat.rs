@@ -1,26 +1,254 @@
-use std::fs;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write},
+    path::Path,
+};
 
-use colored::Colorize;
+use crate::cmd::{
+    args::{Arg, Parser},
+    color::{self, Stream},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::highlight::{Highlighter, Language};
+use crate::theme::Role;
 
-use crate::cmd::CMD;
+use super::{man::Page, Runnable};
 
-use super::Runnable;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Default)]
+struct Options {
+    number: bool,
+    number_nonblank: bool,
+    show_ends: bool,
+    show_tabs: bool,
+    show_nonprinting: bool,
+    squeeze_blank: bool,
+    /// Print files that look binary even to a terminal.
+    binary: bool,
+    /// Never highlight, even when colors are enabled.
+    plain: bool,
+    /// Overrides the language detected from the extension or `#!` line.
+    language: Option<Language>,
+}
+
+impl Options {
+    fn is_plain(&self) -> bool {
+        !(self.number
+            || self.number_nonblank
+            || self.show_ends
+            || self.show_tabs
+            || self.show_nonprinting
+            || self.squeeze_blank)
+    }
+}
+
+/// Formatting state carried across chunks, and across files like GNU cat does.
+struct Formatter<'o> {
+    options: &'o Options,
+    line_number: u64,
+    at_line_start: bool,
+    previous_blank: bool,
+}
+
+impl<'o> Formatter<'o> {
+    fn new(options: &'o Options) -> Self {
+        Self {
+            options,
+            line_number: 0,
+            at_line_start: true,
+            previous_blank: false,
+        }
+    }
+
+    fn write_chunk(&mut self, chunk: &[u8], out: &mut impl Write) -> io::Result<()> {
+        for &byte in chunk {
+            if self.at_line_start {
+                let blank = byte == b'\n';
+                if blank && self.options.squeeze_blank && self.previous_blank {
+                    continue;
+                }
+                let numbered = if self.options.number_nonblank {
+                    !blank
+                } else {
+                    self.options.number
+                };
+                if numbered {
+                    self.line_number += 1;
+                    write!(out, "{:>6}\t", self.line_number)?;
+                }
+                self.previous_blank = blank;
+                self.at_line_start = false;
+            }
+
+            match byte {
+                b'\n' => {
+                    if self.options.show_ends {
+                        out.write_all(b"$")?;
+                    }
+                    out.write_all(b"\n")?;
+                    self.at_line_start = true;
+                }
+                b'\t' if self.options.show_tabs => out.write_all(b"^I")?,
+                b'\t' => out.write_all(b"\t")?,
+                _ if self.options.show_nonprinting => Self::write_visible(byte, out)?,
+                _ => out.write_all(&[byte])?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Caret and `M-` notation, as used by `cat -v`.
+    fn write_visible(byte: u8, out: &mut impl Write) -> io::Result<()> {
+        let mut byte = byte;
+        if byte >= 128 {
+            out.write_all(b"M-")?;
+            byte -= 128;
+        }
+        match byte {
+            0..=31 => out.write_all(&[b'^', byte + 64]),
+            127 => out.write_all(b"^?"),
+            _ => out.write_all(&[byte]),
+        }
+    }
+}
+
+/// Where the bytes being printed come from and how to treat them.
+struct Source<'s> {
+    name: &'s str,
+    path: Option<&'s Path>,
+    check_binary: bool,
+    highlight: bool,
+}
+
+pub const SPEC: Spec = Spec::new("cat", "concatenate files and print them")
+    .usage(&["cat [-nbsAvETp] [--binary] [-l language] [file...]"])
+    .options(&[
+        Opt::new("n", &["number"], "Number all output lines."),
+        Opt::new(
+            "b",
+            &["number-nonblank"],
+            "Number nonempty output lines only.",
+        ),
+        Opt::new("s", &["squeeze-blank"], "Print repeated empty lines once."),
+        Opt::new("A", &["show-all"], "The same as -vET."),
+        Opt::new(
+            "v",
+            &["show-nonprinting"],
+            "Show control characters with ^ and M- notation.",
+        ),
+        Opt::new("E", &["show-ends"], "Print $ at the end of every line."),
+        Opt::new("T", &["show-tabs"], "Print tabs as ^I."),
+        Opt::new("", &["binary"], "Print files even when they look binary."),
+        Opt::new("p", &["plain"], "Do not highlight."),
+        Opt::new(
+            "l",
+            &["language"],
+            "Highlight as language, e.g. rust or json.",
+        )
+        .value("language"),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the files one after the other, '-' or no operand reading the standard \
+                  input. Files that look binary are skipped with a warning.\n\n\
+                  Rust, TOML, JSON, Markdown, shell and Python are highlighted whenever colors \
+                  are enabled, the language being guessed from the file name.",
+    examples: &[
+        ("cat -n src/main.rs", "Print main.rs with line numbers."),
+        ("cat a - b", "Print a, then the standard input, then b."),
+    ],
+    see_also: &["less", "head", "tail"],
+};
 
 pub struct Cat<'a> {
     vars: &'a CMD,
 }
 
 impl<'a> Runnable for Cat<'a> {
-    fn run(&mut self) -> Result<(), String> {
-        for i in 1..self.vars.get_tokens_length() {
-            let mut fpath = self.vars.get_current_dir_path().clone();
-            fpath.push(self.vars.get_token(i));
-            match fs::read_to_string(fpath) {
-                Ok(data) => println!("{}", data),
-                Err(err) => eprintln!("Error: {}", format!("{}", err).red()),
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options::default();
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("number") => options.number = true,
+                Arg::Opt("number-nonblank") => options.number_nonblank = true,
+                Arg::Opt("show-ends") => options.show_ends = true,
+                Arg::Opt("show-tabs") => options.show_tabs = true,
+                Arg::Opt("show-nonprinting") => options.show_nonprinting = true,
+                Arg::Opt("show-all") => {
+                    options.show_nonprinting = true;
+                    options.show_ends = true;
+                    options.show_tabs = true;
+                }
+                Arg::Opt("squeeze-blank") => options.squeeze_blank = true,
+                Arg::Opt("binary") => options.binary = true,
+                Arg::Opt("plain") => options.plain = true,
+                Arg::Opt("language") => {
+                    let name = parser.value();
+                    match Language::from_name(name) {
+                        Some(language) => options.language = Some(language),
+                        None => {
+                            return Err(Error::usage("cat", format!("unknown language '{}'", name)))
+                        }
+                    }
+                }
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
             }
         }
-        Ok(())
+        if operands.is_empty() {
+            operands.push("-");
+        }
+
+        let to_terminal = io::stdout().is_terminal();
+        // Highlighting replaces the other formatting flags rather than combining with them.
+        let highlight = options.is_plain() && !options.plain && color::enabled(Stream::Stdout);
+        let mut out = BufWriter::new(io::stdout().lock());
+        let mut formatter = Formatter::new(&options);
+        let mut failures = Failures::default();
+        for operand in operands {
+            let mut path = self.vars.get_current_dir_path().clone();
+            path.push(operand);
+            let input: Box<dyn Read + '_> = if operand == "-" {
+                self.vars.stdin()
+            } else {
+                if path.is_dir() {
+                    failures.report(Error::io(
+                        "cat",
+                        operand,
+                        io::Error::other("Is a directory"),
+                    ));
+                    continue;
+                }
+                match File::open(&path) {
+                    Ok(file) => Box::new(file),
+                    Err(err) => {
+                        failures.report(Error::io("cat", operand, err));
+                        continue;
+                    }
+                }
+            };
+
+            let check_binary = to_terminal && !options.binary && !options.show_nonprinting;
+            let source = Source {
+                name: operand,
+                path: if operand == "-" { None } else { Some(&path) },
+                check_binary,
+                highlight,
+            };
+            if let Err(err) = Self::stream(input, &source, &mut formatter, &mut out) {
+                failures.report(Error::io("cat", operand, err));
+            }
+        }
+        out.flush()
+            .map_err(|err| Error::io("cat", "standard output", err))?;
+        failures.finish("cat")
     }
 }
 
@@ -28,4 +256,99 @@ impl<'a> Cat<'a> {
     pub fn new(cmd: &'a CMD) -> Self {
         Self { vars: cmd }
     }
+
+    /// NUL bytes or invalid UTF-8 (other than a character cut off at the end of the chunk).
+    fn looks_binary(chunk: &[u8]) -> bool {
+        if chunk.contains(&0) {
+            return true;
+        }
+        match std::str::from_utf8(chunk) {
+            Ok(_) => false,
+            Err(err) => err.error_len().is_some(),
+        }
+    }
+
+    /// Copies `input` chunk by chunk, so memory use does not depend on the file size.
+    /// The first chunk is used to detect binary files, which are skipped with a
+    /// warning rather than dumped to the terminal, and the `#!` line.
+    fn stream(
+        input: Box<dyn Read + '_>,
+        source: &Source,
+        formatter: &mut Formatter,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let mut reader = BufReader::with_capacity(CHUNK_SIZE, input);
+        let first_chunk = reader.fill_buf()?;
+
+        if source.check_binary && Self::looks_binary(first_chunk) {
+            out.flush()?;
+            color::on_stderr(|| {
+                eprintln!(
+                    "{}",
+                    Role::Warning.paint(&format!(
+                        "cat: {}: binary file not shown, use 'cat -v' or 'cat --binary' \
+                         to print it",
+                        source.name
+                    ))
+                )
+            });
+            return Ok(());
+        }
+
+        if source.highlight {
+            let first_line = first_chunk
+                .split(|&b| b == b'\n')
+                .next()
+                .unwrap_or_default();
+            let language = formatter
+                .options
+                .language
+                .or_else(|| Language::detect(source.path, &String::from_utf8_lossy(first_line)));
+            if let Some(language) = language {
+                return Self::highlight(reader, language, out);
+            }
+        }
+
+        loop {
+            let chunk = reader.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+            if formatter.options.is_plain() {
+                out.write_all(chunk)?;
+            } else {
+                formatter.write_chunk(chunk, out)?;
+            }
+            let len = chunk.len();
+            reader.consume(len);
+        }
+        Ok(())
+    }
+
+    /// Prints the input colorized line by line behind a line number gutter.
+    fn highlight(
+        mut reader: impl BufRead,
+        language: Language,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let mut highlighter = Highlighter::new(language);
+        let mut line = Vec::new();
+        let mut number = 0;
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            number += 1;
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\n', '\r']);
+            writeln!(
+                out,
+                "{} {}",
+                Role::LineNumber.paint(&format!("{:>5} │", number)),
+                highlighter.highlight_line(text)
+            )?;
+        }
+        Ok(())
+    }
 }
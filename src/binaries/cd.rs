@@ -1,76 +1,88 @@
-use std::{fs, path::PathBuf};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
 
-use crate::cmd::CMD;
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::fs::path::{canonicalize, expand_tilde, normalize, Missing};
 
-use super::Runnable;
+use super::{man::Page, z, Runnable};
+
+pub const SPEC: Spec = Spec::new("cd", "change the current directory")
+    .usage(&["cd [-L | -P] [dir]"])
+    .options(&[
+        Opt::new(
+            "L",
+            &[],
+            "Keep symlinks in the new path and treat '..' textually, removing the last \
+             component. This is the default.",
+        ),
+        Opt::new("P", &[], "Resolve every symlink of the new path."),
+    ])
+    .operands(Operands::at_most(1));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Change the current directory to dir, or to $HOME without it. 'cd -' returns to \
+                  the previous directory, ~ and ~user expand to home directories and relative \
+                  names not found in the current directory are also searched in the directories \
+                  of $CDPATH.\n\n\
+                  The shell exports $PWD and $OLDPWD, the new and the previous directory, and \
+                  takes the previous directory from $OLDPWD when it starts. Every directory \
+                  visited is recorded for z.",
+    examples: &[
+        ("cd -", "Go back to the previous directory."),
+        ("cd ~/src", "Go to the src directory in the home directory."),
+    ],
+    see_also: &["pwd", "pushd", "z"],
+};
 
 pub struct Cd<'a> {
     vars: &'a mut CMD,
 }
 
 impl<'a> Runnable for Cd<'a> {
-    fn run(&mut self) -> Result<(), String> {
-        match self.vars.get_tokens_length() {
-            len if len > 2 => Err("Too many arguments".to_string()),
-            len if len == 2 => {
-                let dest = self.vars.get_token(1);
+    fn run(&mut self) -> Result<(), Error> {
+        let mut physical = false;
+        let mut operands = Vec::new();
 
-                match dest {
-                    "." => Ok(()),
-                    ".." => match self.vars.get_current_dir_path().parent() {
-                        Some(c) => {
-                            self.vars.set_current_dir_path(c.to_path_buf());
-                            Ok(())
-                        }
-                        None => Err("Can't move to parent directory".to_string()),
-                    },
-                    &_ => {
-                        let absolute_path = match dest {
-                            dest if dest.starts_with("/") => PathBuf::from(dest.to_string()),
-                            dest if dest.starts_with("./") => {
-                                let mut abs_path = self.vars.get_current_dir_path().clone();
-                                abs_path.push(&dest.to_string().leak()[2..]);
-                                abs_path
-                            }
-                            dest if dest.starts_with("../") => {
-                                let mut abs_path = self.vars.get_current_dir_path().clone();
-                                match abs_path.parent() {
-                                    Some(c) => {
-                                        abs_path = c.to_path_buf();
-                                    }
-                                    None => {
-                                        return Err("Can't move to parent directory".to_string());
-                                    }
-                                }
-                                abs_path.push(&dest.to_string().leak()[3..]);
-                                abs_path
-                            }
-                            &_ => {
-                                let mut abs_path = self.vars.get_current_dir_path().clone();
-                                abs_path.push(dest.to_string());
-                                abs_path
-                            }
-                        };
-                        match fs::symlink_metadata(&absolute_path) {
-                            Ok(metadata) => {
-                                if metadata.is_file() {
-                                    println!(
-                                        "File exists, but not a directory. Can't change directory"
-                                    );
-                                } else if metadata.is_dir() {
-                                    self.vars.set_current_dir_path(absolute_path);
-                                } else {
-                                    println!("Not a file nor a directory");
-                                }
-                                Ok(())
-                            }
-                            Err(err) => Err(err.to_string()),
-                        }
-                    }
-                }
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("P") => physical = true,
+                Arg::Opt("L") => physical = false,
+                Arg::Value(operand) => operands.push(operand.to_string()),
+                _ => return Err(parser.unexpected(arg)),
             }
-            _ => Ok(()),
         }
+
+        // The new directory is printed when it is not obvious from the argument.
+        let (target, announce) = match operands.first().map(String::as_str) {
+            None => (
+                env::var("HOME").map_err(|_| Error::failed("cd", "HOME not set"))?,
+                false,
+            ),
+            Some("-") => match self.vars.get_previous_dir_path() {
+                Some(previous) => (previous.display().to_string(), true),
+                None => return Err(Error::failed("cd", "OLDPWD not set")),
+            },
+            Some(dest) => (expand_tilde(dest), false),
+        };
+
+        let (path, found_in_cdpath) = locate_dir(self.vars, "cd", &target, physical)?;
+        if announce || found_in_cdpath {
+            println!("{}", path.display());
+        }
+        self.vars
+            .set_current_dir_path(path.clone())
+            .map_err(|err| Error::io("cd", &path, err))?;
+        z::record_visit(&path);
+        Ok(())
     }
 }
 
@@ -79,3 +91,67 @@ impl<'a> Cd<'a> {
         Self { vars: cmd }
     }
 }
+
+/// Turns `target` into the new directory: textually with `-L`, or with
+/// every symlink resolved with `-P`.
+fn resolve(command: &str, base: &Path, target: &str, physical: bool) -> Result<PathBuf, Error> {
+    let joined = base.join(target);
+    if physical {
+        canonicalize(&joined, Missing::None).map_err(|err| Error::io(command, target, err))
+    } else {
+        Ok(normalize(&joined))
+    }
+}
+
+/// Finds the directory `cd target` moves to, searching the directories of
+/// `CDPATH` for relative targets before the current directory. Also
+/// returns whether `CDPATH` supplied it. Shared with `pushd` and `z`,
+/// `command` naming the caller in error messages.
+pub(crate) fn locate_dir(
+    vars: &CMD,
+    command: &str,
+    target: &str,
+    physical: bool,
+) -> Result<(PathBuf, bool), Error> {
+    let current = vars.get_current_dir_path().clone();
+    let explicit = target.starts_with('/')
+        || target == "."
+        || target == ".."
+        || target.starts_with("./")
+        || target.starts_with("../");
+
+    let mut found = None;
+    if !explicit {
+        if let Ok(cdpath) = env::var("CDPATH") {
+            for entry in cdpath.split(':') {
+                // An empty entry stands for the current directory.
+                let base = if entry.is_empty() {
+                    current.clone()
+                } else {
+                    current.join(expand_tilde(entry))
+                };
+                if base.join(target).is_dir() {
+                    found = Some((
+                        resolve(command, &base, target, physical)?,
+                        !entry.is_empty(),
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+    let (path, from_cdpath) = match found {
+        Some(found) => found,
+        None => (resolve(command, &current, target, physical)?, false),
+    };
+
+    let metadata = fs::metadata(&path).map_err(|err| Error::io(command, target, err))?;
+    if !metadata.is_dir() {
+        return Err(Error::io(
+            command,
+            target,
+            io::Error::other("Not a directory"),
+        ));
+    }
+    Ok((path, from_cdpath))
+}
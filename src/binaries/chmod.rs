@@ -0,0 +1,290 @@
+use std::{
+    fs::{self, Permissions},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::{format::mode_string, walk::Walker};
+
+use super::{man::Page, Runnable};
+
+const USER_BITS: u32 = 0o4700;
+const GROUP_BITS: u32 = 0o2070;
+const OTHER_BITS: u32 = 0o1007;
+
+/// One `who op perms` part of a symbolic mode such as `go-w`.
+struct Clause {
+    /// Bits the clause may touch, or `None` when no `ugoa` was given.
+    who: Option<u32>,
+    op: char,
+    perms: Vec<char>,
+}
+
+enum Mode {
+    Octal(u32),
+    Symbolic(Vec<Clause>),
+}
+
+impl Mode {
+    /// Parses an octal mode like `755` or a symbolic one like `u+x,go=r`.
+    fn parse(text: &str) -> Option<Self> {
+        if !text.is_empty() && text.chars().all(|c| c.is_digit(8)) {
+            return u32::from_str_radix(text, 8)
+                .ok()
+                .filter(|mode| *mode <= 0o7777)
+                .map(Mode::Octal);
+        }
+
+        let mut clauses = Vec::new();
+        for part in text.split(',') {
+            let mut chars = part.chars().peekable();
+            let mut who = None;
+            while let Some(&c) = chars.peek() {
+                let bits = match c {
+                    'u' => USER_BITS,
+                    'g' => GROUP_BITS,
+                    'o' => OTHER_BITS,
+                    'a' => 0o7777,
+                    _ => break,
+                };
+                who = Some(who.unwrap_or(0) | bits);
+                chars.next();
+            }
+            // Several operations may follow one `who`, as in `u+r-w`.
+            let mut parsed_op = false;
+            while let Some(op) = chars.next_if(|c| matches!(c, '+' | '-' | '=')) {
+                let mut perms = Vec::new();
+                while let Some(c) = chars.next_if(|c| "rwxXstugo".contains(*c)) {
+                    perms.push(c);
+                }
+                clauses.push(Clause { who, op, perms });
+                parsed_op = true;
+            }
+            if !parsed_op || chars.next().is_some() {
+                return None;
+            }
+        }
+        Some(Mode::Symbolic(clauses))
+    }
+
+    fn apply(&self, mode: u32, is_dir: bool, umask: u32) -> u32 {
+        let clauses = match self {
+            Mode::Octal(bits) => return *bits,
+            Mode::Symbolic(clauses) => clauses,
+        };
+
+        let mut mode = mode & 0o7777;
+        for clause in clauses {
+            let mut bits = 0;
+            for perm in &clause.perms {
+                bits |= match perm {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' => 0o111,
+                    'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                    's' => 0o6000,
+                    't' => 0o1000,
+                    'u' => ((mode >> 6) & 0o7) * 0o111,
+                    'g' => ((mode >> 3) & 0o7) * 0o111,
+                    'o' => (mode & 0o7) * 0o111,
+                    _ => 0,
+                };
+            }
+            // Without `ugoa` the clause applies to everyone, minus the umask.
+            let who = clause.who.unwrap_or(0o7777);
+            bits &= who;
+            if clause.who.is_none() {
+                bits &= !umask;
+            }
+            mode = match clause.op {
+                '+' => mode | bits,
+                '-' => mode & !bits,
+                _ => {
+                    // Directories keep their set-id bits unless named explicitly.
+                    let cleared = if is_dir { who & !0o6000 } else { who };
+                    (mode & !cleared) | bits
+                }
+            };
+        }
+        mode
+    }
+}
+
+/// The process umask. Reading it requires setting it, so it is restored right away.
+fn umask() -> u32 {
+    // SAFETY: umask cannot fail and the original value is put back immediately.
+    unsafe {
+        let mask = libc::umask(0);
+        libc::umask(mask);
+        mask as u32
+    }
+}
+
+struct Options {
+    recursive: bool,
+    verbose: bool,
+    changes: bool,
+    silent: bool,
+}
+
+pub const SPEC: Spec = Spec::new("chmod", "change file mode bits")
+    .usage(&["chmod [-R] [-v] [-c] [-f] mode file..."])
+    .options(&[
+        Opt::new("R", &["recursive"], "Descend into directories."),
+        Opt::new("v", &["verbose"], "Report every file."),
+        Opt::new("c", &["changes"], "Report only the files that changed."),
+        Opt::new("f", &["silent", "quiet"], "Do not report errors."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Change the permissions of the files. The mode is octal like 755, or symbolic \
+                  like u+x,go-w, a=rX or g=u.",
+    examples: &[("chmod -R go-w src", "Let only the owner write in src.")],
+    see_also: &["chown", "stat", "ls"],
+};
+
+pub struct Chmod<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Chmod<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options {
+            recursive: false,
+            verbose: false,
+            changes: false,
+            silent: false,
+        };
+
+        // Modes like `-w` look like flags, so the mode is picked out before
+        // the remaining tokens are parsed.
+        let args = self.vars.get_args();
+        let mode_idx = args
+            .iter()
+            .take_while(|arg| *arg != "--")
+            .position(|arg| !arg.starts_with('-') || Mode::parse(arg).is_some())
+            .or_else(|| {
+                let dashes = args.iter().position(|arg| arg == "--")?;
+                (dashes + 1 < args.len()).then_some(dashes + 1)
+            })
+            .ok_or_else(|| Error::usage("chmod", "missing operand"))?;
+        let mode_text = &args[mode_idx];
+        let rest: Vec<String> = args
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != mode_idx)
+            .map(|(_, arg)| arg.clone())
+            .collect();
+
+        let mut operands = Vec::new();
+        let mut parser = Parser::new(&SPEC, &rest);
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("recursive") => options.recursive = true,
+                Arg::Opt("verbose") => options.verbose = true,
+                Arg::Opt("changes") => options.changes = true,
+                Arg::Opt("silent") => options.silent = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let mode = Mode::parse(mode_text)
+            .ok_or_else(|| Error::usage("chmod", format!("invalid mode: '{}'", mode_text)))?;
+        if operands.is_empty() {
+            return Err(Error::usage(
+                "chmod",
+                format!("missing operand after '{}'", mode_text),
+            ));
+        }
+
+        let umask = umask();
+        let mut failures = Failures::default();
+        let mut report = |err: Error| {
+            if options.silent {
+                failures.count(&err);
+            } else {
+                failures.report(err);
+            }
+        };
+        for operand in operands {
+            let path = self.vars.resolve_path(operand);
+            if let Err(err) = Self::change(&options, &mode, umask, &path, operand) {
+                report(err);
+                continue;
+            }
+            if !options.recursive || !path.is_dir() {
+                continue;
+            }
+            for entry in Walker::new(&path).hidden(true).walk() {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        report(Error::failed("chmod", err));
+                        continue;
+                    }
+                };
+                // Links met during the walk are skipped, as their permissions are not used.
+                if entry.metadata.file_type().is_symlink() {
+                    continue;
+                }
+                let label =
+                    Path::new(operand).join(entry.path.strip_prefix(&path).unwrap_or(&entry.path));
+                if let Err(err) = Self::change(
+                    &options,
+                    &mode,
+                    umask,
+                    &entry.path,
+                    &label.to_string_lossy(),
+                ) {
+                    report(err);
+                }
+            }
+        }
+        failures.finish("chmod")
+    }
+}
+
+impl<'a> Chmod<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    fn change(
+        options: &Options,
+        mode: &Mode,
+        umask: u32,
+        path: &Path,
+        label: &str,
+    ) -> Result<(), Error> {
+        let metadata = fs::metadata(path).map_err(|err| Error::io("chmod", label, err))?;
+        let old = metadata.permissions().mode();
+        let new = mode.apply(old, metadata.is_dir(), umask);
+        if new != old & 0o7777 {
+            fs::set_permissions(path, Permissions::from_mode(new))
+                .map_err(|err| Error::io("chmod", label, err))?;
+        }
+
+        let file_type = old & 0o170000;
+        let describe =
+            |mode: u32| format!("{:04o} ({})", mode, &mode_string(file_type | mode)[1..]);
+        if new != old & 0o7777 && (options.verbose || options.changes) {
+            println!(
+                "mode of '{}' changed from {} to {}",
+                label,
+                describe(old & 0o7777),
+                describe(new)
+            );
+        } else if options.verbose {
+            println!("mode of '{}' retained as {}", label, describe(new));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    fs,
+    os::unix::fs::{chown, lchown, MetadataExt},
+    path::Path,
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::{users::Users, walk::Walker};
+
+use super::{man::Page, Runnable};
+
+struct Options {
+    recursive: bool,
+    no_dereference: bool,
+    verbose: bool,
+}
+
+/// The new owner and group, `None` leaving that id unchanged.
+#[derive(Clone, Copy)]
+struct Owner {
+    uid: Option<u32>,
+    gid: Option<u32>,
+}
+
+impl Owner {
+    /// Parses `user`, `user:group`, `user:` (the user's login group) or `:group`.
+    fn parse(spec: &str, users: &Users) -> Result<Self, Error> {
+        let (user, group) = match spec.split_once(':') {
+            Some((user, group)) => (user, Some(group)),
+            None => (spec, None),
+        };
+        let uid =
+            match user {
+                "" => None,
+                user => Some(users.user_id(user).ok_or_else(|| {
+                    Error::not_found("chown", format!("invalid user: '{}'", spec))
+                })?),
+            };
+        let gid =
+            match group {
+                None => None,
+                Some("") => match uid {
+                    Some(uid) => Some(users.login_group(uid).ok_or_else(|| {
+                        Error::usage("chown", format!("invalid spec: '{}'", spec))
+                    })?),
+                    None => None,
+                },
+                Some(group) => Some(users.group_id(group).ok_or_else(|| {
+                    Error::not_found("chown", format!("invalid group: '{}'", spec))
+                })?),
+            };
+        if uid.is_none() && gid.is_none() && !spec.contains(':') {
+            return Err(Error::usage("chown", format!("invalid spec: '{}'", spec)));
+        }
+        Ok(Self { uid, gid })
+    }
+}
+
+pub const SPEC: Spec = Spec::new("chown", "change file owner and group")
+    .usage(&["chown [-R] [-h] [-v] owner[:group] file..."])
+    .options(&[
+        Opt::new("R", &["recursive"], "Descend into directories."),
+        Opt::new(
+            "h",
+            &["no-dereference"],
+            "Change symlinks themselves rather than their targets.",
+        ),
+        Opt::new("v", &["verbose"], "Report every file."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Change the owner and group of the files. Names are looked up in /etc/passwd \
+                  and /etc/group, 'user:' picks the login group of the user and ':group' changes \
+                  only the group.",
+    examples: &[(
+        "chown -R www: site",
+        "Give site to www and its login group.",
+    )],
+    see_also: &["chmod", "stat"],
+};
+
+pub struct Chown<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Chown<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options {
+            recursive: false,
+            no_dereference: false,
+            verbose: false,
+        };
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("recursive") => options.recursive = true,
+                Arg::Opt("no-dereference") => options.no_dereference = true,
+                Arg::Opt("verbose") => options.verbose = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        let Some((spec, operands)) = operands.split_first() else {
+            return Err(Error::usage("chown", "missing operand"));
+        };
+        if operands.is_empty() {
+            return Err(Error::usage(
+                "chown",
+                format!("missing operand after '{}'", spec),
+            ));
+        }
+
+        let users = Users::load();
+        let owner = Owner::parse(spec, &users)?;
+        let mut failures = Failures::default();
+        for operand in operands {
+            let path = self.vars.resolve_path(operand);
+            if let Err(err) = Self::change(
+                &options,
+                &users,
+                owner,
+                &path,
+                operand,
+                !options.no_dereference,
+            ) {
+                failures.report(err);
+                continue;
+            }
+            let is_dir = fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir());
+            if !options.recursive || !is_dir {
+                continue;
+            }
+            for entry in Walker::new(&path).hidden(true).walk() {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        failures.report(Error::failed("chown", err));
+                        continue;
+                    }
+                };
+                let label = Path::new(operand)
+                    .join(entry.path.strip_prefix(&path).unwrap_or(&entry.path))
+                    .to_string_lossy()
+                    .into_owned();
+                // Links found while recursing are changed themselves, never their targets.
+                if let Err(err) = Self::change(&options, &users, owner, &entry.path, &label, false)
+                {
+                    failures.report(err);
+                }
+            }
+        }
+        failures.finish("chown")
+    }
+}
+
+impl<'a> Chown<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    fn change(
+        options: &Options,
+        users: &Users,
+        owner: Owner,
+        path: &Path,
+        label: &str,
+        dereference: bool,
+    ) -> Result<(), Error> {
+        let metadata = if dereference {
+            fs::metadata(path)
+        } else {
+            fs::symlink_metadata(path)
+        }
+        .map_err(|err| Error::io("chown", label, err))?;
+
+        let result = if dereference {
+            chown(path, owner.uid, owner.gid)
+        } else {
+            lchown(path, owner.uid, owner.gid)
+        };
+        result.map_err(|err| Error::io("chown", label, err))?;
+
+        if options.verbose {
+            let describe =
+                |uid: u32, gid: u32| format!("{}:{}", users.user_name(uid), users.group_name(gid));
+            let (old_uid, old_gid) = (metadata.uid(), metadata.gid());
+            let (new_uid, new_gid) = (owner.uid.unwrap_or(old_uid), owner.gid.unwrap_or(old_gid));
+            if (old_uid, old_gid) == (new_uid, new_gid) {
+                println!(
+                    "ownership of '{}' retained as {}",
+                    label,
+                    describe(new_uid, new_gid)
+                );
+            } else {
+                println!(
+                    "changed ownership of '{}' from {} to {}",
+                    label,
+                    describe(old_uid, old_gid),
+                    describe(new_uid, new_gid)
+                );
+            }
+        }
+        Ok(())
+    }
+}
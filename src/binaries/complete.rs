@@ -0,0 +1,93 @@
+use std::fs;
+
+use crate::cmd::{
+    error::Error,
+    spec::{Completion, Operands, Spec},
+    BUILTINS, CMD,
+};
+
+use super::{man, man::Page, Runnable, COMMANDS};
+
+pub const SPEC: Spec = Spec::new("complete", "list the completions of a command line")
+    .usage(&["complete word..."])
+    .operands(Operands::at_least(1));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the candidates for the last word of a command line, one per line: \
+                  command names for the first word, then the subcommands and options the command \
+                  declares, or the files of the directory the word is in. Nothing is printed for \
+                  the value of an option.\n\n\
+                  The words are taken as they are, so 'complete ls --a' completes the options of \
+                  ls rather than being one of complete. Give '' to complete an empty word.",
+    examples: &[
+        ("complete ls --g", "Print --group-directories-first."),
+        ("complete todo ''", "Print the commands of todo."),
+        ("complete cat src/", "Print the entries of src."),
+    ],
+    see_also: &["man"],
+};
+
+pub struct Complete<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Complete<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        // The words are not parsed, since they are mostly options of the
+        // command being completed.
+        let words: Vec<&str> = self.vars.get_args().iter().map(String::as_str).collect();
+        let mut candidates = match words.as_slice() {
+            [] => return Err(Error::usage("complete", "missing operand")),
+            ["--help"] => return Err(Error::help(SPEC.help(None))),
+            [word] => COMMANDS
+                .iter()
+                .chain(BUILTINS.iter())
+                .filter(|command| command.starts_with(word))
+                .map(|command| command.to_string())
+                .collect(),
+            [command, rest @ ..] => match man::find(command).map(|page| page.spec.complete(rest)) {
+                Some(Completion::Words(words)) => words,
+                Some(Completion::Value) => Vec::new(),
+                Some(Completion::Operand) | None => self.files(rest.last().copied().unwrap_or("")),
+            },
+        };
+        candidates.sort();
+        candidates.dedup();
+        for candidate in candidates {
+            println!("{}", candidate);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Complete<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    /// The entries of the directory `word` is in whose names start like its
+    /// last component, directories ending with `/`. Hidden entries are only
+    /// offered once the word starts with a dot.
+    fn files(&self, word: &str) -> Vec<String> {
+        let (dir, prefix) = match word.rfind('/') {
+            Some(idx) => word.split_at(idx + 1),
+            None => ("", word),
+        };
+        let Ok(entries) = fs::read_dir(self.vars.resolve_path(dir)) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let hidden = name.starts_with('.') && !prefix.starts_with('.');
+                if hidden || !name.starts_with(prefix) {
+                    return None;
+                }
+                let slash = if entry.path().is_dir() { "/" } else { "" };
+                Some(format!("{}{}{}", dir, name, slash))
+            })
+            .collect()
+    }
+}
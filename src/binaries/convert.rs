@@ -0,0 +1,323 @@
+use std::io::Read;
+
+use serde_json::Value;
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    output::Format,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::table::{self, csv, Record};
+
+use super::{man::Page, Runnable};
+
+/// The text to convert: the file operand, or the output of the previous stage.
+fn input_text(command: &str, vars: &CMD, operand: Option<&str>) -> Result<String, Error> {
+    let Some(operand) = operand else {
+        return table::read_text(command, vars);
+    };
+    let mut text = String::new();
+    vars.open_input(operand)
+        .and_then(|mut input| input.read_to_string(&mut text))
+        .map_err(|err| Error::io(command, operand, err))?;
+    Ok(text)
+}
+
+/// Parses the value of `-s`, where `\t` stands for a tab.
+fn separator(command: &str, text: &str) -> Result<char, Error> {
+    if text == "\\t" {
+        return Ok('\t');
+    }
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(Error::usage(
+            command,
+            format!("the separator must be a single character: '{}'", text),
+        )),
+    }
+}
+
+/// Reads numbers and booleans of a CSV field as such, so `where` can
+/// compare them. Numbers with leading zeros such as `0644` stay strings.
+fn infer(field: &str) -> Value {
+    let digits = field.trim_start_matches(['-', '+']);
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    let numeric = !digits.is_empty()
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+'));
+    if numeric && !leading_zero {
+        if let Ok(number) = field.parse::<i64>() {
+            return Value::from(number);
+        }
+        if let Ok(number) = field.parse::<f64>() {
+            return Value::from(number);
+        }
+    }
+    match field {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::from(field),
+    }
+}
+
+pub const FROM_JSON_SPEC: Spec = Spec::new("from-json", "read records from JSON")
+    .usage(&["from-json [file]"])
+    .options(&[
+        Opt::new("", &["json"], "Print the records as JSON."),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ])
+    .operands(Operands::at_most(1));
+
+pub const FROM_JSON_MANUAL: Page = Page {
+    spec: &FROM_JSON_SPEC,
+    description: "Read a JSON array, JSON objects or one object per line from file or the \
+                  previous pipeline stage, and draw them as a table or pass them on to the next \
+                  stage.",
+    examples: &[(
+        "from-json users.json | where age >= 18",
+        "Show the adults of users.json.",
+    )],
+    see_also: &["to-json", "from-csv", "where"],
+};
+
+pub struct FromJson<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for FromJson<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut format = self.vars.output_format();
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&FROM_JSON_SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            if let Some(chosen) = Format::from_arg(arg) {
+                format = chosen;
+                continue;
+            }
+            match arg {
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let text = input_text("from-json", self.vars, operands.first().copied())?;
+        let records = table::parse("from-json", &text)?;
+        table::emit("from-json", format, &records)
+    }
+}
+
+impl<'a> FromJson<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+pub const FROM_CSV_SPEC: Spec = Spec::new("from-csv", "read records from CSV")
+    .usage(&["from-csv [-s separator] [-n] [file]"])
+    .options(&[
+        Opt::new(
+            "s",
+            &["separator"],
+            "Separate fields with separator, \\t for tabs.",
+        )
+        .value("separator"),
+        Opt::new(
+            "n",
+            &["noheaders"],
+            "Name the columns column0, column1 and so on.",
+        ),
+        Opt::new("", &["json"], "Print the records as JSON."),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ])
+    .operands(Operands::at_most(1));
+
+pub const FROM_CSV_MANUAL: Page = Page {
+    spec: &FROM_CSV_SPEC,
+    description: "Read CSV from file or the previous pipeline stage into records, the first row \
+                  naming the columns. Numbers and true/false become numbers and booleans, except \
+                  numbers with leading zeros.",
+    examples: &[(
+        "from-csv sales.csv | sort-by -r total",
+        "Show sales.csv by decreasing total.",
+    )],
+    see_also: &["to-csv", "from-json", "sort-by"],
+};
+
+pub struct FromCsv<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for FromCsv<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut format = self.vars.output_format();
+        let mut delimiter = ',';
+        let mut headers = true;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&FROM_CSV_SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            if let Some(chosen) = Format::from_arg(arg) {
+                format = chosen;
+                continue;
+            }
+            match arg {
+                Arg::Opt("separator") => delimiter = separator("from-csv", parser.value())?,
+                Arg::Opt("noheaders") => headers = false,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let text = input_text("from-csv", self.vars, operands.first().copied())?;
+        let mut rows = csv::parse(&text, delimiter)
+            .into_iter()
+            .filter(|row| !(row.len() == 1 && row[0].is_empty()));
+        let names = match headers {
+            true => rows.next().unwrap_or_default(),
+            false => Vec::new(),
+        };
+
+        let records: Vec<Record> = rows
+            .map(|row| {
+                let mut record = Record::default();
+                for (idx, field) in row.iter().enumerate() {
+                    let name = match names.get(idx) {
+                        Some(name) => name.clone(),
+                        None => format!("column{}", idx),
+                    };
+                    record.insert(&name, infer(field));
+                }
+                record
+            })
+            .collect();
+        table::emit("from-csv", format, &records)
+    }
+}
+
+impl<'a> FromCsv<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+pub const TO_JSON_SPEC: Spec = Spec::new("to-json", "write records as JSON")
+    .usage(&["to-json [--ndjson]"])
+    .options(&[Opt::new(
+        "",
+        &["ndjson"],
+        "Print one record per line instead.",
+    )])
+    .operands(Operands::NONE);
+
+pub const TO_JSON_MANUAL: Page = Page {
+    spec: &TO_JSON_SPEC,
+    description: "Print the records of the previous pipeline stage as a JSON array.",
+    examples: &[(
+        "ls | to-json",
+        "Print the entries of the directory as JSON.",
+    )],
+    see_also: &["from-json", "to-csv"],
+};
+
+pub struct ToJson<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for ToJson<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut format = Format::Json;
+        let mut parser = Parser::new(&TO_JSON_SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match Format::from_arg(arg) {
+                Some(chosen) => format = chosen,
+                None => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let records = table::read("to-json", self.vars)?;
+        format.print("to-json", &records)
+    }
+}
+
+impl<'a> ToJson<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+pub const TO_CSV_SPEC: Spec = Spec::new("to-csv", "write records as CSV")
+    .usage(&["to-csv [-s separator] [-n]"])
+    .options(&[
+        Opt::new(
+            "s",
+            &["separator"],
+            "Separate fields with separator, \\t for tabs.",
+        )
+        .value("separator"),
+        Opt::new("n", &["noheaders"], "Leave out the header row."),
+    ])
+    .operands(Operands::NONE);
+
+pub const TO_CSV_MANUAL: Page = Page {
+    spec: &TO_CSV_SPEC,
+    description: "Print the records of the previous pipeline stage as CSV, with a header row \
+                  naming the columns.",
+    examples: &[(
+        "ls | select name size | to-csv",
+        "Print names and sizes as CSV.",
+    )],
+    see_also: &["from-csv", "to-json"],
+};
+
+pub struct ToCsv<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for ToCsv<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut delimiter = ',';
+        let mut headers = true;
+
+        let mut parser = Parser::new(&TO_CSV_SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("separator") => delimiter = separator("to-csv", parser.value())?,
+                Arg::Opt("noheaders") => headers = false,
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let records = table::read("to-csv", self.vars)?;
+        let columns: Vec<String> = table::columns(&records)
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        if headers && !columns.is_empty() {
+            println!("{}", csv::format_row(&columns, delimiter));
+        }
+        for record in records.iter() {
+            let fields: Vec<String> = columns
+                .iter()
+                .map(|column| match record.get(column) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(text)) => text.clone(),
+                    Some(value) => value.to_string(),
+                })
+                .collect();
+            println!("{}", csv::format_row(&fields, delimiter));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ToCsv<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
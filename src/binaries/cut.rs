@@ -0,0 +1,312 @@
+use std::io::{self, BufRead, BufWriter, Write};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+
+use super::{man::Page, Runnable};
+
+/// Parsed `LIST` argument: 1-based inclusive ranges such as `1,3-5,7-`.
+struct List {
+    ranges: Vec<(usize, usize)>,
+    complement: bool,
+}
+
+impl List {
+    fn parse(list: &str) -> Result<Self, Error> {
+        let invalid = || Error::usage("cut", format!("invalid field value '{}'", list));
+        let mut ranges = Vec::new();
+        for part in list.split(',') {
+            let bound = |text: &str, default: usize| -> Result<usize, Error> {
+                if text.is_empty() {
+                    return Ok(default);
+                }
+                match text.parse() {
+                    Ok(0) => Err(Error::usage(
+                        "cut",
+                        "fields and positions are numbered from 1",
+                    )),
+                    Ok(value) => Ok(value),
+                    Err(_) => Err(invalid()),
+                }
+            };
+            let range = match part.split_once('-') {
+                Some(("", "")) => return Err(invalid()),
+                Some((start, end)) => (bound(start, 1)?, bound(end, usize::MAX)?),
+                None if part.is_empty() => return Err(invalid()),
+                None => {
+                    let position = bound(part, 0)?;
+                    (position, position)
+                }
+            };
+            if range.0 > range.1 {
+                return Err(Error::usage("cut", "invalid decreasing range"));
+            }
+            ranges.push(range);
+        }
+        Ok(Self {
+            ranges,
+            complement: false,
+        })
+    }
+
+    fn contains(&self, position: usize) -> bool {
+        let listed = self
+            .ranges
+            .iter()
+            .any(|&(start, end)| start <= position && position <= end);
+        listed != self.complement
+    }
+}
+
+enum Mode {
+    Bytes(List),
+    Chars(List),
+    Fields(List),
+}
+
+struct Options {
+    mode: Mode,
+    delimiter: char,
+    output_delimiter: Option<String>,
+    only_delimited: bool,
+}
+
+impl Options {
+    fn cut_line(&self, line: &[u8], out: &mut Vec<u8>) {
+        match &self.mode {
+            Mode::Bytes(list) => out.extend(
+                line.iter()
+                    .enumerate()
+                    .filter(|(idx, _)| list.contains(idx + 1))
+                    .map(|(_, &byte)| byte),
+            ),
+            Mode::Chars(list) => {
+                let text = String::from_utf8_lossy(line);
+                let selected: String = text
+                    .chars()
+                    .enumerate()
+                    .filter(|(idx, _)| list.contains(idx + 1))
+                    .map(|(_, c)| c)
+                    .collect();
+                out.extend(selected.as_bytes());
+            }
+            Mode::Fields(list) => {
+                let text = String::from_utf8_lossy(line);
+                if !text.contains(self.delimiter) {
+                    if !self.only_delimited {
+                        out.extend(line);
+                        out.push(b'\n');
+                    }
+                    return;
+                }
+                let separator = match &self.output_delimiter {
+                    Some(separator) => separator.clone(),
+                    None => self.delimiter.to_string(),
+                };
+                let selected = text
+                    .split(self.delimiter)
+                    .enumerate()
+                    .filter(|(idx, _)| list.contains(idx + 1))
+                    .map(|(_, field)| field)
+                    .collect::<Vec<_>>()
+                    .join(&separator);
+                out.extend(selected.as_bytes());
+            }
+        }
+        out.push(b'\n');
+    }
+}
+
+pub const SPEC: Spec = Spec::new("cut", "remove sections from each line")
+    .usage(&[
+        "cut (-b list | -c list | -f list) [-d delim] [-s] [--complement] \
+         [--output-delimiter str] [file...]",
+    ])
+    .options(&[
+        Opt::new("b", &["bytes"], "Select bytes.").value("list"),
+        Opt::new("c", &["characters"], "Select characters.").value("list"),
+        Opt::new(
+            "f",
+            &["fields"],
+            "Select fields, separated by tabs unless -d is given.",
+        )
+        .value("list"),
+        Opt::new("d", &["delimiter"], "Separate fields with delim.").value("delim"),
+        Opt::new(
+            "s",
+            &["only-delimited"],
+            "Skip lines without the delimiter.",
+        ),
+        Opt::new("", &["complement"], "Print everything but the selection."),
+        Opt::new("", &["output-delimiter"], "Join selected fields with str.").value("str"),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the selected bytes, characters or fields of every line. A list is made \
+                  of numbers and ranges separated by commas, such as 1,3-5 or 4-.",
+    examples: &[(
+        "cut -d : -f 1,7 /etc/passwd",
+        "Print the users and their shells.",
+    )],
+    see_also: &["tr", "select"],
+};
+
+pub struct Cut<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Cut<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut mode = None;
+        let mut delimiter = None;
+        let mut output_delimiter = None;
+        let mut only_delimited = false;
+        let mut complement = false;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            let selected = match arg {
+                Arg::Opt("bytes") => Mode::Bytes(List::parse(parser.value())?),
+                Arg::Opt("characters") => Mode::Chars(List::parse(parser.value())?),
+                Arg::Opt("fields") => Mode::Fields(List::parse(parser.value())?),
+                Arg::Opt("delimiter") => {
+                    let value = parser.value();
+                    let mut chars = value.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => delimiter = Some(c),
+                        _ => {
+                            return Err(Error::usage(
+                                "cut",
+                                "the delimiter must be a single character",
+                            ))
+                        }
+                    }
+                    continue;
+                }
+                Arg::Opt("output-delimiter") => {
+                    output_delimiter = Some(parser.value().to_string());
+                    continue;
+                }
+                Arg::Opt("only-delimited") => {
+                    only_delimited = true;
+                    continue;
+                }
+                Arg::Opt("complement") => {
+                    complement = true;
+                    continue;
+                }
+                Arg::Value(operand) => {
+                    operands.push(operand);
+                    continue;
+                }
+                _ => return Err(parser.unexpected(arg)),
+            };
+            if mode.is_some() {
+                return Err(Error::usage(
+                    "cut",
+                    "only one type of list may be specified",
+                ));
+            }
+            mode = Some(selected);
+        }
+
+        let Some(mut mode) = mode else {
+            return Err(Error::usage(
+                "cut",
+                "you must specify a list of bytes, characters, or fields",
+            ));
+        };
+        if !matches!(mode, Mode::Fields(_)) && (delimiter.is_some() || only_delimited) {
+            return Err(Error::usage(
+                "cut",
+                "an input delimiter may be specified only when operating on fields",
+            ));
+        }
+        let (Mode::Bytes(list) | Mode::Chars(list) | Mode::Fields(list)) = &mut mode;
+        list.complement = complement;
+        let options = Options {
+            mode,
+            delimiter: delimiter.unwrap_or('\t'),
+            output_delimiter,
+            only_delimited,
+        };
+        if operands.is_empty() {
+            operands.push("-");
+        }
+
+        let mut out = BufWriter::new(io::stdout().lock());
+        let mut line = Vec::new();
+        let mut cut = Vec::new();
+        let mut failures = Failures::default();
+        for operand in operands {
+            let mut input = match self.vars.open_input(operand) {
+                Ok(input) => input,
+                Err(err) => {
+                    failures.report(Error::io("cut", operand, err));
+                    continue;
+                }
+            };
+            loop {
+                line.clear();
+                match input.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(_) => {}
+                    Err(err) => {
+                        failures.report(Error::io("cut", operand, err));
+                        break;
+                    }
+                }
+                if line.ends_with(b"\n") {
+                    line.pop();
+                }
+                cut.clear();
+                options.cut_line(&line, &mut cut);
+                if out.write_all(&cut).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        let _ = out.flush();
+        failures.finish("cut")
+    }
+}
+
+impl<'a> Cut<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_are_parsed_into_ranges() {
+        let list = List::parse("1,3-4,6-").unwrap();
+        assert_eq!(list.ranges, [(1, 1), (3, 4), (6, usize::MAX)]);
+        let listed: Vec<usize> = (1..=8).filter(|&idx| list.contains(idx)).collect();
+        assert_eq!(listed, [1, 3, 4, 6, 7, 8]);
+        assert_eq!(List::parse("-2").unwrap().ranges, [(1, 2)]);
+
+        let complement = List {
+            complement: true,
+            ..List::parse("2-3").unwrap()
+        };
+        let listed: Vec<usize> = (1..=5).filter(|&idx| complement.contains(idx)).collect();
+        assert_eq!(listed, [1, 4, 5]);
+    }
+
+    #[test]
+    fn invalid_lists_are_rejected() {
+        for list in ["", "-", "0", "0-2", "1,,2", "a", "1-x", "3-1"] {
+            assert!(List::parse(list).is_err(), "'{}' was accepted", list);
+        }
+    }
+}
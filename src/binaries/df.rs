@@ -0,0 +1,356 @@
+use std::{
+    ffi::CString,
+    fs,
+    mem::MaybeUninit,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt},
+    path::Path,
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::format::human_size;
+use crate::theme::Role;
+
+use super::{man::Page, Runnable};
+
+const MOUNTS: &str = "/proc/mounts";
+
+struct Mount {
+    source: String,
+    target: String,
+    fs_type: String,
+}
+
+/// Sizes of a mounted file system as reported by `statvfs`, in bytes or inodes.
+struct Usage {
+    total: u64,
+    used: u64,
+    available: u64,
+}
+
+impl Usage {
+    /// Percentage of the space usable by ordinary users that is taken,
+    /// rounded up like `df` does.
+    fn percent(&self) -> Option<u64> {
+        let usable = self.used + self.available;
+        if usable == 0 {
+            None
+        } else {
+            Some((self.used * 100).div_ceil(usable))
+        }
+    }
+}
+
+/// Undoes the octal escapes the kernel uses for spaces, tabs, newlines and
+/// backslashes in mount table fields.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let octal = bytes.get(idx + 1..idx + 4);
+        if bytes[idx] == b'\\' && octal.is_some_and(|digits| digits.iter().all(u8::is_ascii_digit))
+        {
+            let code = octal
+                .unwrap_or_default()
+                .iter()
+                .fold(0u32, |code, digit| code * 8 + u32::from(digit - b'0'));
+            out.push(code as u8);
+            idx += 4;
+        } else {
+            out.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn read_mounts() -> Result<Vec<Mount>, Error> {
+    let table = fs::read_to_string(MOUNTS).map_err(|err| Error::io("df", MOUNTS, err))?;
+    Ok(table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(Mount {
+                source: unescape(fields.next()?),
+                target: unescape(fields.next()?),
+                fs_type: fields.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+fn statvfs(path: &Path) -> std::io::Result<libc::statvfs> {
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is a valid C string and `stat` is only read on success.
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(unsafe { stat.assume_init() })
+}
+
+fn usage(stat: &libc::statvfs, inodes: bool) -> Usage {
+    if inodes {
+        let total = stat.f_files;
+        let free = stat.f_ffree;
+        Usage {
+            total,
+            used: total.saturating_sub(free),
+            available: stat.f_favail,
+        }
+    } else {
+        let unit = stat.f_frsize;
+        let total = stat.f_blocks * unit;
+        let free = stat.f_bfree * unit;
+        Usage {
+            total,
+            used: total.saturating_sub(free),
+            available: stat.f_bavail * unit,
+        }
+    }
+}
+
+pub const SPEC: Spec = Spec::new("df", "report file system usage")
+    .usage(&["df [-a] [-h] [-i] [-T] [path...]"])
+    .options(&[
+        Opt::new("a", &["all"], "Include pseudo file systems."),
+        Opt::new("h", &["human-readable"], "Print sizes like 1K, 234M or 2G."),
+        Opt::new("i", &["inodes"], "Show inodes instead of blocks."),
+        Opt::new("T", &["print-type"], "Show the type of the file systems."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Show the size, used and available space of the mounted file systems, or of the \
+                  ones holding the paths.",
+    examples: &[("df -h .", "Show the space left on the current file system.")],
+    see_also: &["du"],
+};
+
+pub struct Df<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Df<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut all = false;
+        let mut human = false;
+        let mut inodes = false;
+        let mut show_type = false;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("all") => all = true,
+                Arg::Opt("human-readable") => human = true,
+                Arg::Opt("inodes") => inodes = true,
+                Arg::Opt("print-type") => show_type = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let mounts = read_mounts()?;
+        let explicit = !operands.is_empty();
+        let mut failures = Failures::default();
+        let selected: Vec<&Mount> = if operands.is_empty() {
+            // A later mount on the same directory hides the earlier ones.
+            mounts
+                .iter()
+                .enumerate()
+                .filter(|(idx, mount)| {
+                    !mounts[idx + 1..]
+                        .iter()
+                        .any(|later| later.target == mount.target)
+                })
+                .map(|(_, mount)| mount)
+                .collect()
+        } else {
+            let mut selected = Vec::new();
+            for operand in operands {
+                match Self::mount_of(&mounts, &self.vars.resolve_path(operand), operand) {
+                    Ok(mount) => selected.push(mount),
+                    Err(err) => failures.report(err),
+                }
+            }
+            selected
+        };
+
+        let size_header = match (inodes, human) {
+            (true, _) => "Inodes",
+            (false, true) => "Size",
+            (false, false) => "1K-blocks",
+        };
+        let (used_header, available_header, percent_header) = if inodes {
+            ("IUsed", "IFree", "IUse%")
+        } else {
+            ("Used", "Available", "Use%")
+        };
+        let format = |value: u64| match (inodes, human) {
+            (false, true) => human_size(value),
+            (false, false) => value.div_ceil(1024).to_string(),
+            (true, _) => value.to_string(),
+        };
+
+        let mut header = vec!["Filesystem"];
+        if show_type {
+            header.push("Type");
+        }
+        header.extend([
+            size_header,
+            used_header,
+            available_header,
+            percent_header,
+            "Mounted on",
+        ]);
+        let mut rows = Vec::new();
+        for mount in selected {
+            let stat = match statvfs(Path::new(&mount.target)) {
+                Ok(stat) => stat,
+                Err(err) => {
+                    if explicit {
+                        failures.report(Error::io("df", &mount.target, err));
+                    }
+                    continue;
+                }
+            };
+            // Pseudo file systems like proc and sysfs report no blocks at all.
+            if !all && !explicit && stat.f_blocks == 0 {
+                continue;
+            }
+            let usage = usage(&stat, inodes);
+            let mut row = vec![mount.source.clone()];
+            if show_type {
+                row.push(mount.fs_type.clone());
+            }
+            row.push(format(usage.total));
+            row.push(format(usage.used));
+            row.push(format(usage.available));
+            row.push(match usage.percent() {
+                Some(percent) => format!("{}%", percent),
+                None => "-".to_string(),
+            });
+            row.push(mount.target.clone());
+            rows.push(row);
+        }
+
+        // Nothing to show when none of the operands was resolved.
+        if !rows.is_empty() {
+            let mut lines = layout(&header, &rows, show_type).into_iter();
+            if let Some(header) = lines.next() {
+                println!("{}", Role::Header.paint(&header));
+            }
+            for line in lines {
+                println!("{}", line);
+            }
+        }
+        failures.finish("df")
+    }
+}
+
+impl<'a> Df<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    /// The last mount in the table on the same device as `path`, since later
+    /// mounts hide earlier ones.
+    fn mount_of<'m>(mounts: &'m [Mount], path: &Path, operand: &str) -> Result<&'m Mount, Error> {
+        let device = fs::metadata(path)
+            .map_err(|err| Error::io("df", operand, err))?
+            .dev();
+        mounts
+            .iter()
+            .rev()
+            .find(|mount| {
+                fs::metadata(&mount.target).is_ok_and(|metadata| metadata.dev() == device)
+            })
+            .ok_or_else(|| Error::not_found("df", format!("{}: no file system found", operand)))
+    }
+}
+
+/// The lines of the table, the header first, with every column as wide as
+/// its widest cell, header included. Text columns are aligned left and
+/// numbers right.
+fn layout(header: &[&str], rows: &[Vec<String>], show_type: bool) -> Vec<String> {
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(col, title)| {
+            rows.iter()
+                .map(|row| row[col].width())
+                .chain([title.width()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let columns = widths.len();
+    let text_columns = if show_type { 2 } else { 1 };
+
+    let header = header.iter().map(|title| title.to_string()).collect();
+    [header]
+        .iter()
+        .chain(rows)
+        .map(|row: &Vec<String>| {
+            let mut line = String::new();
+            for (col, cell) in row.iter().enumerate() {
+                let pad = " ".repeat(widths[col] - cell.width());
+                if col > 0 {
+                    line.push(' ');
+                }
+                if col < text_columns || col == columns - 1 {
+                    line.push_str(cell);
+                    if col != columns - 1 {
+                        line.push_str(&pad);
+                    }
+                } else {
+                    line.push_str(&pad);
+                    line.push_str(cell);
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_header_lines_up_with_the_rows() {
+        let header = [
+            "Filesystem",
+            "Type",
+            "Size",
+            "Used",
+            "Available",
+            "Use%",
+            "Mounted on",
+        ];
+        let rows: Vec<Vec<String>> = [
+            ["/dev/mapper/root", "ext4", "252G", "18G", "79G", "19%", "/"],
+            ["tmpfs", "tmpfs", "5.9G", "0", "5.9G", "0%", "/dev/shm"],
+        ]
+        .iter()
+        .map(|row| row.map(String::from).to_vec())
+        .collect();
+        assert_eq!(
+            layout(&header, &rows, true),
+            [
+                "Filesystem       Type  Size Used Available Use% Mounted on",
+                "/dev/mapper/root ext4  252G  18G       79G  19% /",
+                "tmpfs            tmpfs 5.9G    0      5.9G   0% /dev/shm",
+            ]
+        );
+    }
+}
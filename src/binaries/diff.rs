@@ -0,0 +1,732 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use colored::Colorize;
+use unicode_width::UnicodeWidthChar;
+
+use crate::binaries::ls::grid::terminal_width;
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::diff::{self, unified, Edit};
+use crate::fs::{format::full_time, ignore::Ignore, walk::Walker};
+use crate::theme::Role;
+
+use super::{man::Page, Runnable};
+
+const DEFAULT_CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Normal,
+    Unified(usize),
+    SideBySide,
+}
+
+/// A directory compared by `diff`, with the ignore rules that apply in it.
+struct Dir {
+    path: PathBuf,
+    label: String,
+    ignore: Arc<Ignore>,
+}
+
+struct Options {
+    format: Format,
+    width: Option<usize>,
+    suppress_common: bool,
+    brief: bool,
+    recursive: bool,
+    new_file: bool,
+    report_identical: bool,
+    ignore_case: bool,
+    ignore_space_change: bool,
+    ignore_all_space: bool,
+    gitignore: bool,
+}
+
+impl Options {
+    /// The form of a line that is compared, after applying the -i/-b/-w flags.
+    fn key(&self, line: &str) -> String {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let mut key = if self.ignore_all_space {
+            line.chars().filter(|c| !c.is_whitespace()).collect()
+        } else if self.ignore_space_change {
+            line.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            line.to_string()
+        };
+        if self.ignore_case {
+            key = key.to_lowercase();
+        }
+        key
+    }
+
+    fn ignores_anything(&self) -> bool {
+        self.ignore_case || self.ignore_space_change || self.ignore_all_space
+    }
+}
+
+/// One side of a comparison: what to call it and what it contains.
+struct Side {
+    label: String,
+    modified: Option<SystemTime>,
+    bytes: Vec<u8>,
+}
+
+pub const SPEC: Spec = Spec::new("diff", "compare files line by line")
+    .usage(&[
+        "diff [-u | -U lines | -y [-W width] [--suppress-common-lines]] [-qrNsibw] \
+         [--gitignore] old new",
+    ])
+    .options(&[
+        Opt::new("u", &[], "Print unified hunks with 3 lines of context."),
+        Opt::new("U", &[], "Print unified hunks with lines lines of context.").value("lines"),
+        Opt::new(
+            "",
+            &["unified"],
+            "Print unified hunks with lines lines of context, 3 by default.",
+        )
+        .optional_value("lines"),
+        Opt::new(
+            "y",
+            &["side-by-side"],
+            "Show both files side by side, fitted to the terminal.",
+        ),
+        Opt::new("W", &["width"], "Use width columns for -y.").value("width"),
+        Opt::new(
+            "",
+            &["suppress-common-lines"],
+            "Leave out equal lines with -y.",
+        ),
+        Opt::new("q", &["brief"], "Only tell whether the files differ."),
+        Opt::new("r", &["recursive"], "Compare subdirectories recursively."),
+        Opt::new("N", &["new-file"], "Treat missing files as empty."),
+        Opt::new(
+            "s",
+            &["report-identical-files"],
+            "Tell when two files are the same.",
+        ),
+        Opt::new("i", &["ignore-case"], "Ignore case differences."),
+        Opt::new(
+            "b",
+            &["ignore-space-change"],
+            "Ignore changes in the amount of white space.",
+        ),
+        Opt::new("w", &["ignore-all-space"], "Ignore all white space."),
+        Opt::new(
+            "",
+            &["gitignore"],
+            "Skip files ignored by .gitignore files in directories.",
+        ),
+    ])
+    .operands(Operands::exactly(2));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the changes turning old into new, as lines to delete and add. With \
+                  directories, compare the files with the same name, and subdirectories too with \
+                  -r. The exit status is 0 when the files are the same, 1 when they differ, and \
+                  the one of the error when a file could not be read or the usage is wrong.",
+    examples: &[
+        ("diff -u old.txt new.txt", "Print a unified diff."),
+        ("diff -u a b | patch a", "Make a the same as b."),
+    ],
+    see_also: &["patch"],
+};
+
+pub struct Diff<'a> {
+    vars: &'a CMD,
+}
+
+fn output_error(err: io::Error) -> Error {
+    Error::io("diff", "standard output", err)
+}
+
+impl<'a> Runnable for Diff<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        self.diff(&mut io::stdout().lock())
+    }
+}
+
+impl<'a> Diff<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    /// Compares the operands, writing the differences to `out`.
+    fn diff(&self, out: &mut impl Write) -> Result<(), Error> {
+        let mut options = Options {
+            format: Format::Normal,
+            width: None,
+            suppress_common: false,
+            brief: false,
+            recursive: false,
+            new_file: false,
+            report_identical: false,
+            ignore_case: false,
+            ignore_space_change: false,
+            ignore_all_space: false,
+            gitignore: false,
+        };
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("u") => options.format = Format::Unified(DEFAULT_CONTEXT),
+                Arg::Opt("U") => {
+                    options.format = Format::Unified(Self::parse_number(parser.value())?)
+                }
+                Arg::Opt("unified") => {
+                    let context = match parser.optional_value() {
+                        Some(value) => Self::parse_number(value)?,
+                        None => DEFAULT_CONTEXT,
+                    };
+                    options.format = Format::Unified(context);
+                }
+                Arg::Opt("side-by-side") => options.format = Format::SideBySide,
+                Arg::Opt("width") => options.width = Some(Self::parse_number(parser.value())?),
+                Arg::Opt("suppress-common-lines") => options.suppress_common = true,
+                Arg::Opt("brief") => options.brief = true,
+                Arg::Opt("recursive") => options.recursive = true,
+                Arg::Opt("new-file") => options.new_file = true,
+                Arg::Opt("report-identical-files") => options.report_identical = true,
+                Arg::Opt("ignore-case") => options.ignore_case = true,
+                Arg::Opt("ignore-space-change") => options.ignore_space_change = true,
+                Arg::Opt("ignore-all-space") => options.ignore_all_space = true,
+                Arg::Opt("gitignore") => options.gitignore = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        let (old, new) = (operands[0], operands[1]);
+
+        let old_path = self.vars.resolve_path(old);
+        let new_path = self.vars.resolve_path(new);
+        let mut failures = Failures::default();
+        let differ = match (old_path.is_dir(), new_path.is_dir()) {
+            (true, true) => {
+                // Hidden files are compared too, as in GNU diff.
+                let walker = Walker::new(&old_path)
+                    .hidden(true)
+                    .git_ignore(options.gitignore);
+                let dir = |path: &Path, label: &str| Dir {
+                    path: path.to_path_buf(),
+                    label: label.to_string(),
+                    ignore: walker.ignore_above(path),
+                };
+                self.compare_dirs(
+                    &options,
+                    &walker,
+                    &mut failures,
+                    &dir(&old_path, old),
+                    &dir(&new_path, new),
+                    &mut *out,
+                )
+            }
+            // A file compared with a directory means the file of the same
+            // name inside it.
+            (true, false) => {
+                let name = Path::new(new)
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                let label = format!("{}/{}", old.trim_end_matches('/'), name);
+                self.compare_files(
+                    &options,
+                    &old_path.join(&*name),
+                    &new_path,
+                    &label,
+                    new,
+                    &mut *out,
+                )
+            }
+            (false, true) => {
+                let name = Path::new(old)
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                let label = format!("{}/{}", new.trim_end_matches('/'), name);
+                self.compare_files(
+                    &options,
+                    &old_path,
+                    &new_path.join(&*name),
+                    old,
+                    &label,
+                    &mut *out,
+                )
+            }
+            (false, false) => {
+                self.compare_files(&options, &old_path, &new_path, old, new, &mut *out)
+            }
+        }?;
+        // Trouble with some files outweighs the others differing, as in GNU diff.
+        failures.finish("diff")?;
+        if differ {
+            return Err(Error::differ("diff"));
+        }
+        Ok(())
+    }
+
+    fn parse_number(value: &str) -> Result<usize, Error> {
+        value
+            .parse()
+            .map_err(|_| Error::usage("diff", format!("invalid number '{}'", value)))
+    }
+
+    fn read_side(&self, path: &Path, label: &str) -> io::Result<Side> {
+        if label == "-" {
+            let mut bytes = Vec::new();
+            self.vars.stdin().read_to_end(&mut bytes)?;
+            return Ok(Side {
+                label: label.to_string(),
+                modified: Some(SystemTime::now()),
+                bytes,
+            });
+        }
+        Ok(Side {
+            label: label.to_string(),
+            modified: fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            bytes: fs::read(path)?,
+        })
+    }
+
+    fn missing_side(label: &str) -> Side {
+        Side {
+            label: label.to_string(),
+            modified: None,
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Reads both files; with `-N` a file missing on one side reads as empty.
+    fn read_pair(
+        &self,
+        options: &Options,
+        old_path: &Path,
+        new_path: &Path,
+        old_label: &str,
+        new_label: &str,
+    ) -> Result<(Side, Side), Error> {
+        let read = |path: &Path, label: &str, other: &Path| match self.read_side(path, label) {
+            Ok(side) => Ok(side),
+            Err(err)
+                if err.kind() == io::ErrorKind::NotFound && options.new_file && other.exists() =>
+            {
+                Ok(Self::missing_side(label))
+            }
+            Err(err) => Err(Error::io("diff", label, err)),
+        };
+        Ok((
+            read(old_path, old_label, new_path)?,
+            read(new_path, new_label, old_path)?,
+        ))
+    }
+
+    /// Compares two files; returns whether they differ.
+    fn compare_files(
+        &self,
+        options: &Options,
+        old_path: &Path,
+        new_path: &Path,
+        old_label: &str,
+        new_label: &str,
+        out: &mut impl Write,
+    ) -> Result<bool, Error> {
+        let (old, new) = self.read_pair(options, old_path, new_path, old_label, new_label)?;
+        self.print_diff(options, &old, &new, out)
+            .map_err(output_error)
+    }
+
+    /// Compares the files of two directories, returning whether they differ.
+    /// The files that could not be compared are added to `failures`.
+    fn compare_dirs(
+        &self,
+        options: &Options,
+        walker: &Walker,
+        failures: &mut Failures,
+        old_dir: &Dir,
+        new_dir: &Dir,
+        out: &mut impl Write,
+    ) -> Result<bool, Error> {
+        let names = |dir: &Dir| -> Result<(BTreeSet<String>, Arc<Ignore>), Error> {
+            let (entries, ignore) = walker.read_dir(&dir.path, 0, &dir.ignore).map_err(|err| {
+                Error::failed(
+                    "diff",
+                    format!("cannot read directory '{}': {}", dir.label, err),
+                )
+            })?;
+            Ok((
+                entries.into_iter().map(|entry| entry.name).collect(),
+                ignore,
+            ))
+        };
+        let (old_names, old_ignore) = names(old_dir)?;
+        let (new_names, new_ignore) = names(new_dir)?;
+        let (old_label, new_label) = (old_dir.label.as_str(), new_dir.label.as_str());
+
+        let mut differ = false;
+        for name in old_names.union(&new_names) {
+            let old_path = old_dir.path.join(name);
+            let new_path = new_dir.path.join(name);
+            let old_child = format!("{}/{}", old_label.trim_end_matches('/'), name);
+            let new_child = format!("{}/{}", new_label.trim_end_matches('/'), name);
+            let in_old = old_names.contains(name);
+            let in_both = in_old && new_names.contains(name);
+
+            if !in_both && !options.new_file {
+                let dir = if in_old { old_label } else { new_label };
+                writeln!(out, "Only in {}: {}", dir, name).map_err(output_error)?;
+                differ = true;
+                continue;
+            }
+
+            let old_is_dir = old_path.is_dir();
+            let new_is_dir = new_path.is_dir();
+            let result = if old_is_dir && new_is_dir {
+                if options.recursive {
+                    let old_dir = Dir {
+                        path: old_path,
+                        label: old_child,
+                        ignore: Arc::clone(&old_ignore),
+                    };
+                    let new_dir = Dir {
+                        path: new_path,
+                        label: new_child,
+                        ignore: Arc::clone(&new_ignore),
+                    };
+                    self.compare_dirs(options, walker, failures, &old_dir, &new_dir, out)
+                } else {
+                    writeln!(
+                        out,
+                        "Common subdirectories: {} and {}",
+                        old_child, new_child
+                    )
+                    .map(|_| false)
+                    .map_err(output_error)
+                }
+            } else if old_is_dir != new_is_dir && in_both {
+                let kind = |is_dir: bool| if is_dir { "directory" } else { "regular file" };
+                writeln!(
+                    out,
+                    "File {} is a {} while file {} is a {}",
+                    old_child,
+                    kind(old_is_dir),
+                    new_child,
+                    kind(new_is_dir)
+                )
+                .map(|_| true)
+                .map_err(output_error)
+            } else {
+                self.read_pair(options, &old_path, &new_path, &old_child, &new_child)
+                    .and_then(|(old, new)| {
+                        // Like GNU diff, announce each pair of files that differ.
+                        if !options.brief && old.bytes != new.bytes {
+                            let header =
+                                format!("diff {} {} {}", self.flags(), old_child, new_child);
+                            writeln!(out, "{}", Role::DiffHeader.paint(&header))
+                                .map_err(output_error)?;
+                        }
+                        self.print_diff(options, &old, &new, out)
+                            .map_err(output_error)
+                    })
+            };
+            match result {
+                Ok(changed) => differ |= changed,
+                Err(err) => failures.report(err),
+            }
+        }
+        Ok(differ)
+    }
+
+    /// The flags of this invocation, for the `diff ...` lines of `-r`.
+    fn flags(&self) -> String {
+        self.vars
+            .get_args()
+            .iter()
+            .filter(|arg| arg.starts_with('-') && arg.len() > 1)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn print_diff(
+        &self,
+        options: &Options,
+        old: &Side,
+        new: &Side,
+        out: &mut impl Write,
+    ) -> io::Result<bool> {
+        if old.bytes == new.bytes {
+            if options.report_identical {
+                writeln!(out, "Files {} and {} are identical", old.label, new.label)?;
+            }
+            return Ok(false);
+        }
+        let binary = |bytes: &[u8]| bytes[..bytes.len().min(8192)].contains(&0);
+        if binary(&old.bytes) || binary(&new.bytes) {
+            writeln!(out, "Binary files {} and {} differ", old.label, new.label)?;
+            return Ok(true);
+        }
+
+        let old_text = String::from_utf8_lossy(&old.bytes);
+        let new_text = String::from_utf8_lossy(&new.bytes);
+        let old_lines = diff::lines(&old_text);
+        let new_lines = diff::lines(&new_text);
+
+        // Lines are interned so the diff compares integers.
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        let mut intern = |line: &str| {
+            let key = if options.ignores_anything() {
+                options.key(line)
+            } else {
+                line.to_string()
+            };
+            let next = ids.len();
+            *ids.entry(key).or_insert(next)
+        };
+        let old_ids: Vec<usize> = old_lines.iter().map(|line| intern(line)).collect();
+        let new_ids: Vec<usize> = new_lines.iter().map(|line| intern(line)).collect();
+        let edits = diff::diff(&old_ids, &new_ids);
+        if edits.iter().all(|edit| matches!(edit, Edit::Equal(..))) {
+            return Ok(false);
+        }
+
+        if options.brief {
+            writeln!(out, "Files {} and {} differ", old.label, new.label)?;
+            return Ok(true);
+        }
+        match options.format {
+            Format::Normal => Self::print_normal(&edits, &old_lines, &new_lines, out)?,
+            Format::Unified(context) => {
+                Self::print_unified(old, new, &edits, context, &old_lines, &new_lines, out)?
+            }
+            Format::SideBySide => {
+                let width = options.width.unwrap_or_else(terminal_width);
+                Self::print_side_by_side(options, width, &edits, &old_lines, &new_lines, out)?
+            }
+        }
+        Ok(true)
+    }
+
+    fn write_line(out: &mut impl Write, marker: &str, line: &str) -> io::Result<()> {
+        let text = format!("{}{}", marker, line.trim_end_matches('\n'));
+        let painted = match marker.chars().next() {
+            Some('-' | '<') => Role::Removed.paint(&text),
+            Some('+' | '>') => Role::Added.paint(&text),
+            _ => text.normal(),
+        };
+        writeln!(out, "{}", painted)?;
+        if !line.ends_with('\n') {
+            writeln!(out, "\\ No newline at end of file")?;
+        }
+        Ok(())
+    }
+
+    fn print_normal(
+        edits: &[Edit],
+        old_lines: &[&str],
+        new_lines: &[&str],
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let range = |start: usize, len: usize| match len {
+            0 => format!("{}", start),
+            1 => format!("{}", start + 1),
+            _ => format!("{},{}", start + 1, start + len),
+        };
+        for hunk in diff::hunks(edits, 0) {
+            let command = match (hunk.old_len, hunk.new_len) {
+                (0, _) => 'a',
+                (_, 0) => 'd',
+                _ => 'c',
+            };
+            let header = format!(
+                "{}{}{}",
+                range(hunk.old_start, hunk.old_len),
+                command,
+                range(hunk.new_start, hunk.new_len)
+            );
+            writeln!(out, "{}", Role::DiffHunk.paint(&header))?;
+            for edit in &hunk.edits {
+                if let Edit::Delete(idx) = edit {
+                    Self::write_line(out, "< ", old_lines[*idx])?;
+                }
+            }
+            if command == 'c' {
+                writeln!(out, "---")?;
+            }
+            for edit in &hunk.edits {
+                if let Edit::Insert(idx) = edit {
+                    Self::write_line(out, "> ", new_lines[*idx])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn timestamp(side: &Side) -> String {
+        full_time(side.modified.unwrap_or(SystemTime::UNIX_EPOCH))
+    }
+
+    fn print_unified(
+        old: &Side,
+        new: &Side,
+        edits: &[Edit],
+        context: usize,
+        old_lines: &[&str],
+        new_lines: &[&str],
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let old_header = format!("--- {}\t{}", old.label, Self::timestamp(old));
+        let new_header = format!("+++ {}\t{}", new.label, Self::timestamp(new));
+        writeln!(out, "{}", Role::DiffHeader.paint(&old_header))?;
+        writeln!(out, "{}", Role::DiffHeader.paint(&new_header))?;
+        for hunk in diff::hunks(edits, context) {
+            writeln!(
+                out,
+                "{}",
+                Role::DiffHunk.paint(&unified::hunk_header(&hunk))
+            )?;
+            for edit in &hunk.edits {
+                match *edit {
+                    Edit::Equal(idx, _) => Self::write_line(out, " ", old_lines[idx])?,
+                    Edit::Delete(idx) => Self::write_line(out, "-", old_lines[idx])?,
+                    Edit::Insert(idx) => Self::write_line(out, "+", new_lines[idx])?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Expands tabs and pads or truncates a line to exactly `width` columns.
+    fn fit(line: &str, width: usize) -> String {
+        let mut fitted = String::new();
+        let mut used = 0;
+        for c in line.trim_end_matches(['\n', '\r']).chars() {
+            if c == '\t' {
+                let spaces = (8 - used % 8).min(width - used);
+                fitted.extend(std::iter::repeat_n(' ', spaces));
+                used += spaces;
+            } else {
+                let char_width = c.width().unwrap_or(0);
+                if used + char_width > width {
+                    break;
+                }
+                fitted.push(c);
+                used += char_width;
+            }
+            if used >= width {
+                break;
+            }
+        }
+        fitted.extend(std::iter::repeat_n(' ', width - used));
+        fitted
+    }
+
+    fn print_side_by_side(
+        options: &Options,
+        width: usize,
+        edits: &[Edit],
+        old_lines: &[&str],
+        new_lines: &[&str],
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let column = width.saturating_sub(3) / 2;
+        let row = |out: &mut dyn Write, left: Option<&str>, marker: char, right: Option<&str>| {
+            let left_text = Self::fit(left.unwrap_or(""), column);
+            let right_text = match right {
+                Some(right) => Self::fit(right, column).trim_end().to_string(),
+                None => String::new(),
+            };
+            let (left_text, right_text) = match marker {
+                '|' => (
+                    Role::Removed.paint(&left_text),
+                    Role::Added.paint(&right_text),
+                ),
+                '<' => (Role::Removed.paint(&left_text), right_text.normal()),
+                '>' => (left_text.normal(), Role::Added.paint(&right_text)),
+                _ => (left_text.normal(), right_text.normal()),
+            };
+            let line = format!("{} {} {}", left_text, marker, right_text);
+            writeln!(out, "{}", line.trim_end())
+        };
+
+        let mut idx = 0;
+        while idx < edits.len() {
+            if let Edit::Equal(old_idx, new_idx) = edits[idx] {
+                if !options.suppress_common {
+                    row(out, Some(old_lines[old_idx]), ' ', Some(new_lines[new_idx]))?;
+                }
+                idx += 1;
+                continue;
+            }
+            // Pair up a block of deletions and insertions as changed lines.
+            let mut deleted = Vec::new();
+            let mut inserted = Vec::new();
+            while let Some(edit) = edits.get(idx) {
+                match *edit {
+                    Edit::Delete(old_idx) => deleted.push(old_lines[old_idx]),
+                    Edit::Insert(new_idx) => inserted.push(new_lines[new_idx]),
+                    Edit::Equal(..) => break,
+                }
+                idx += 1;
+            }
+            for pair in 0..deleted.len().max(inserted.len()) {
+                match (deleted.get(pair), inserted.get(pair)) {
+                    (Some(left), Some(right)) => row(out, Some(left), '|', Some(right))?,
+                    (Some(left), None) => row(out, Some(left), '<', None)?,
+                    (None, Some(right)) => row(out, None, '>', Some(right))?,
+                    (None, None) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+
+    /// The exit status of `line`, whose output is dropped.
+    fn status(line: &str) -> i32 {
+        let cmd = CMD::with_line(line);
+        Diff::new(&cmd)
+            .diff(&mut io::sink())
+            .err()
+            .map_or(0, |err| err.status())
+    }
+
+    #[test]
+    fn exit_status_tells_whether_the_files_differ() {
+        let dir = TempDir::new("diff-status");
+        let old = dir.write("old", "a\nb\n");
+        let same = dir.write("same", "a\nb\n");
+        let new = dir.write("new", "a\nc\n");
+
+        let line = |a: &Path, b: &Path| format!("diff -q {} {}", a.display(), b.display());
+        assert_eq!(status(&line(&old, &same)), 0);
+        assert_eq!(status(&line(&old, &new)), 1);
+        assert_eq!(status(&format!("diff {}", old.display())), 2);
+        assert_eq!(
+            status(&format!(
+                "diff --nosuch {} {}",
+                old.display(),
+                new.display()
+            )),
+            2
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::fs::path::expand_tilde;
+
+use super::{cd::locate_dir, man::Page, z, Runnable};
+
+/// Parses a `+N` or `-N` stack position into an index from the top.
+fn stack_index(command: &str, arg: &str, len: usize) -> Option<Result<usize, Error>> {
+    let (from_top, digits) = match arg.split_at_checked(1)? {
+        ("+", digits) => (true, digits),
+        ("-", digits) => (false, digits),
+        _ => return None,
+    };
+    let n: usize = digits.parse().ok()?;
+    if n >= len {
+        return Some(Err(Error::usage(
+            command,
+            format!("{}: directory stack index out of range", arg),
+        )));
+    }
+    Some(Ok(if from_top { n } else { len - 1 - n }))
+}
+
+/// Splits the stack positions off the arguments, since `-1` would
+/// otherwise be read as a flag.
+fn split_args(
+    command: &str,
+    args: &[String],
+    len: usize,
+) -> Result<(Option<usize>, Vec<String>), Error> {
+    let mut index = None;
+    let mut rest = Vec::new();
+    for arg in args {
+        match stack_index(command, arg, len) {
+            Some(parsed) => index = Some(parsed?),
+            None => rest.push(arg.clone()),
+        }
+    }
+    Ok((index, rest))
+}
+
+/// The current directory followed by the saved ones.
+fn full_stack(vars: &CMD) -> Vec<PathBuf> {
+    let mut stack = vec![vars.get_current_dir_path().clone()];
+    stack.extend(vars.get_dir_stack().iter().cloned());
+    stack
+}
+
+/// Makes `stack[0]` the current directory and saves the rest.
+fn apply_stack(vars: &mut CMD, mut stack: Vec<PathBuf>, command: &str) -> Result<(), Error> {
+    let top = stack.remove(0);
+    if top != *vars.get_current_dir_path() {
+        if !top.is_dir() {
+            return Err(Error::not_found(
+                command,
+                format!("{}: No such directory", top.display()),
+            ));
+        }
+        vars.set_current_dir_path(top.clone())
+            .map_err(|err| Error::io(command, &top, err))?;
+        z::record_visit(&top);
+    }
+    *vars.get_dir_stack_mut() = stack;
+    Ok(())
+}
+
+/// Shortens paths below `$HOME` to `~/...` unless `long` is set.
+fn display(path: &Path, long: bool) -> String {
+    if !long {
+        if let Ok(home) = env::var("HOME") {
+            if let Ok(rest) = path.strip_prefix(&home) {
+                if rest.as_os_str().is_empty() {
+                    return "~".to_string();
+                }
+                return format!("~/{}", rest.display());
+            }
+        }
+    }
+    path.display().to_string()
+}
+
+fn print_stack(vars: &CMD) {
+    let line: Vec<String> = full_stack(vars)
+        .iter()
+        .map(|path| display(path, false))
+        .collect();
+    println!("{}", line.join(" "));
+}
+
+pub const PUSHD_SPEC: Spec = Spec::new(
+    "pushd",
+    "change directory, saving the current one on the stack",
+)
+.usage(&["pushd [dir | +N | -N]"])
+.operands(Operands::at_most(1));
+
+pub const PUSHD_MANUAL: Page = Page {
+    spec: &PUSHD_SPEC,
+    description: "Save the current directory on the directory stack and change to dir. Without \
+                  operands, swap the top two directories.\n\n\
+                  +N rotates the stack so the Nth directory from the left, counting from 0, is \
+                  on top, and -N so the Nth directory from the right is.",
+    examples: &[("pushd /tmp", "Go to /tmp, 'popd' coming back.")],
+    see_also: &["popd", "dirs", "cd"],
+};
+
+pub struct Pushd<'a> {
+    vars: &'a mut CMD,
+}
+
+impl<'a> Runnable for Pushd<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut stack = full_stack(self.vars);
+        let (index, rest) = split_args("pushd", self.vars.get_args(), stack.len())?;
+        let mut operands = Vec::new();
+        let mut parser = Parser::new(&PUSHD_SPEC, &rest);
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Value(operand) => operands.push(operand.to_string()),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        match (index, operands.as_slice()) {
+            (Some(_), [_, ..]) => return Err(Error::usage("pushd", "too many arguments")),
+            // Bring the n-th directory to the top by rotating the stack.
+            (Some(index), []) => stack.rotate_left(index),
+            (None, []) => {
+                if stack.len() < 2 {
+                    return Err(Error::failed("pushd", "no other directory"));
+                }
+                stack.swap(0, 1);
+            }
+            (None, [dir, ..]) => {
+                let (path, _) = locate_dir(self.vars, "pushd", &expand_tilde(dir), false)?;
+                stack.insert(0, path);
+            }
+        }
+        apply_stack(self.vars, stack, "pushd")?;
+        print_stack(self.vars);
+        Ok(())
+    }
+}
+
+impl<'a> Pushd<'a> {
+    pub fn new(cmd: &'a mut CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+pub const POPD_SPEC: Spec = Spec::new("popd", "return to the directory on top of the stack")
+    .usage(&["popd [+N | -N]"])
+    .operands(Operands::NONE);
+
+pub const POPD_MANUAL: Page = Page {
+    spec: &POPD_SPEC,
+    description: "Remove the top directory from the directory stack and change to the next \
+                  one.\n\n\
+                  +N removes the Nth directory from the left, counting from 0, instead, and -N \
+                  the Nth directory from the right.",
+    examples: &[("popd", "Go back to where the last pushd came from.")],
+    see_also: &["pushd", "dirs"],
+};
+
+pub struct Popd<'a> {
+    vars: &'a mut CMD,
+}
+
+impl<'a> Runnable for Popd<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut stack = full_stack(self.vars);
+        let (index, rest) = split_args("popd", self.vars.get_args(), stack.len())?;
+        let mut parser = Parser::new(&POPD_SPEC, &rest);
+        if let Some(arg) = parser.next_arg()? {
+            return Err(parser.unexpected(arg));
+        }
+        if stack.len() < 2 {
+            return Err(Error::failed("popd", "directory stack empty"));
+        }
+        stack.remove(index.unwrap_or(0));
+        apply_stack(self.vars, stack, "popd")?;
+        print_stack(self.vars);
+        Ok(())
+    }
+}
+
+impl<'a> Popd<'a> {
+    pub fn new(cmd: &'a mut CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+pub const DIRS_SPEC: Spec = Spec::new("dirs", "show the directory stack")
+    .usage(&["dirs [-c] [-l] [-p] [-v] [+N | -N]"])
+    .options(&[
+        Opt::new("c", &[], "Clear the stack."),
+        Opt::new("l", &[], "Do not shorten $HOME to ~."),
+        Opt::new("p", &[], "Print one directory per line."),
+        Opt::new("v", &[], "Print one directory per line with its position."),
+    ])
+    .operands(Operands::NONE);
+
+pub const DIRS_MANUAL: Page = Page {
+    spec: &DIRS_SPEC,
+    description: "Show the directory stack, the current directory first, or only its Nth entry \
+                  counted from the left with +N or from the right with -N.",
+    examples: &[("dirs -v", "Show the positions to give pushd and popd.")],
+    see_also: &["pushd", "popd"],
+};
+
+pub struct Dirs<'a> {
+    vars: &'a mut CMD,
+}
+
+impl<'a> Runnable for Dirs<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let stack = full_stack(self.vars);
+        let (index, rest) = split_args("dirs", self.vars.get_args(), stack.len())?;
+        let mut clear = false;
+        let mut long = false;
+        let mut per_line = false;
+        let mut numbered = false;
+
+        let mut parser = Parser::new(&DIRS_SPEC, &rest);
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("c") => clear = true,
+                Arg::Opt("l") => long = true,
+                Arg::Opt("p") => per_line = true,
+                Arg::Opt("v") => numbered = true,
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        if clear {
+            self.vars.get_dir_stack_mut().clear();
+            return Ok(());
+        }
+        if let Some(index) = index {
+            println!("{}", display(&stack[index], long));
+            return Ok(());
+        }
+        for (idx, path) in stack.iter().enumerate() {
+            match (numbered, per_line) {
+                (true, _) => println!("{:>2}  {}", idx, display(path, long)),
+                (false, true) => println!("{}", display(path, long)),
+                (false, false) => {
+                    let separator = if idx + 1 == stack.len() { "\n" } else { " " };
+                    print!("{}{}", display(path, long), separator);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Dirs<'a> {
+    pub fn new(cmd: &'a mut CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
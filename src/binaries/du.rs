@@ -0,0 +1,370 @@
+use std::{
+    collections::HashSet,
+    fs::Metadata,
+    os::unix::fs::MetadataExt,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::{
+    format::human_size,
+    ignore::Ignore,
+    walk::{DirEntry, Walker},
+};
+
+use super::{man::Page, Runnable};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Kilobytes,
+    Bytes,
+    Human,
+}
+
+struct Options {
+    all: bool,
+    max_depth: Option<usize>,
+    apparent: bool,
+    unit: Unit,
+    total: bool,
+}
+
+/// The disk usage of a file or directory, with the children that will be printed.
+struct Usage {
+    label: String,
+    size: u64,
+    children: Vec<Usage>,
+}
+
+/// State shared by the threads measuring one operand.
+struct Scan<'o> {
+    options: &'o Options,
+    walker: Walker,
+    /// Files with several links already counted, by device and inode.
+    seen: Mutex<HashSet<(u64, u64)>>,
+    /// The directories that could not be read.
+    failures: Mutex<Failures>,
+}
+
+impl<'o> Scan<'o> {
+    fn report(&self, err: Error) {
+        let mut failures = self.failures.lock().unwrap_or_else(|err| err.into_inner());
+        failures.report(err);
+    }
+
+    /// The size to count for a file, or `None` for a hard link whose inode
+    /// was already counted.
+    fn size(&self, metadata: &Metadata) -> Option<u64> {
+        if !metadata.is_dir() && metadata.nlink() > 1 {
+            let mut seen = self.seen.lock().unwrap_or_else(|err| err.into_inner());
+            if !seen.insert((metadata.dev(), metadata.ino())) {
+                return None;
+            }
+        }
+        Some(if self.options.apparent {
+            metadata.len()
+        } else {
+            metadata.blocks() * 512
+        })
+    }
+
+    /// Whether an entry at `depth` gets a line of its own.
+    fn printed(&self, depth: usize, is_dir: bool) -> bool {
+        (is_dir || self.options.all) && self.options.max_depth.is_none_or(|max| depth <= max)
+    }
+
+    fn measure_entry(&self, entry: DirEntry, label: String, ignore: &Arc<Ignore>) -> Option<Usage> {
+        if entry.is_dir() {
+            Some(self.measure_dir(&entry.path, label, &entry.metadata, entry.depth, ignore))
+        } else {
+            Some(Usage {
+                label,
+                size: self.size(&entry.metadata)?,
+                children: Vec::new(),
+            })
+        }
+    }
+
+    fn measure_dir(
+        &self,
+        dir: &Path,
+        label: String,
+        metadata: &Metadata,
+        depth: usize,
+        ignore: &Arc<Ignore>,
+    ) -> Usage {
+        let mut usage = Usage {
+            label,
+            size: self.size(metadata).unwrap_or_default(),
+            children: Vec::new(),
+        };
+        let (entries, ignore) = match self.walker.read_dir(dir, depth, ignore) {
+            Ok(listed) => listed,
+            Err(err) => {
+                self.report(Error::failed(
+                    "du",
+                    format!("cannot read directory '{}': {}", usage.label, err),
+                ));
+                return usage;
+            }
+        };
+        for entry in sorted(entries) {
+            let is_dir = entry.is_dir();
+            let label = entry_label(&usage.label, &entry);
+            if let Some(child) = self.measure_entry(entry, label, &ignore) {
+                usage.add(child, self.printed(depth + 1, is_dir));
+            }
+        }
+        usage
+    }
+
+    /// Measures the children of the operand on a pool of threads, since
+    /// most of the time goes into waiting for `stat` calls.
+    fn measure_root(&self, root: &Path, label: String, metadata: &Metadata) -> Usage {
+        let mut usage = Usage {
+            label,
+            size: self.size(metadata).unwrap_or_default(),
+            children: Vec::new(),
+        };
+        let ignore = self.walker.root_ignore();
+        let (entries, ignore) = match self.walker.read_dir(root, 0, &ignore) {
+            Ok(listed) => listed,
+            Err(err) => {
+                self.report(Error::failed(
+                    "du",
+                    format!("cannot read directory '{}': {}", usage.label, err),
+                ));
+                return usage;
+            }
+        };
+
+        let entries: Vec<Mutex<Option<DirEntry>>> = sorted(entries)
+            .into_iter()
+            .map(|entry| Mutex::new(Some(entry)))
+            .collect();
+        let next = AtomicUsize::new(0);
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(entries.len());
+        let mut measured: Vec<(usize, Usage, bool)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let idx = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(slot) = entries.get(idx) else {
+                                break;
+                            };
+                            let Some(entry) = slot.lock().ok().and_then(|mut entry| entry.take())
+                            else {
+                                continue;
+                            };
+                            let is_dir = entry.is_dir();
+                            let label = entry_label(&usage.label, &entry);
+                            if let Some(child) = self.measure_entry(entry, label, &ignore) {
+                                done.push((idx, child, is_dir));
+                            }
+                        }
+                        done
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+
+        // Keep the output independent of which thread finished first.
+        measured.sort_by_key(|(idx, _, _)| *idx);
+        for (_, child, is_dir) in measured {
+            usage.add(child, self.printed(1, is_dir));
+        }
+        usage
+    }
+}
+
+impl Usage {
+    fn add(&mut self, child: Usage, keep: bool) {
+        self.size += child.size;
+        if keep {
+            self.children.push(child);
+        }
+    }
+}
+
+fn sorted(mut entries: Vec<DirEntry>) -> Vec<DirEntry> {
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+fn entry_label(parent: &str, entry: &DirEntry) -> String {
+    if parent.ends_with('/') {
+        format!("{}{}", parent, entry.name)
+    } else {
+        format!("{}/{}", parent, entry.name)
+    }
+}
+
+pub const SPEC: Spec = Spec::new("du", "estimate disk usage")
+    .usage(&["du [-a] [-s | -d depth] [-h | -b | -k] [--apparent-size] [-c] [path...]"])
+    .options(&[
+        Opt::new("a", &["all"], "List files too."),
+        Opt::new("s", &["summarize"], "Show only the total of each path."),
+        Opt::new(
+            "d",
+            &["max-depth"],
+            "Show directories at most depth levels deep.",
+        )
+        .value("depth"),
+        Opt::new("h", &["human-readable"], "Print sizes like 1K, 234M or 2G."),
+        Opt::new("b", &["bytes"], "Print apparent sizes in bytes."),
+        Opt::new("k", &[], "Print sizes in 1K blocks, the default."),
+        Opt::new(
+            "",
+            &["apparent-size"],
+            "Print the sizes of the files rather than their disk usage.",
+        ),
+        Opt::new("c", &["total"], "Add a grand total."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Show the disk usage of every directory below the paths, the current directory \
+                  without operands, in 1K blocks. Hard linked files are counted once.",
+    examples: &[("du -sh *", "Show how much space every entry takes.")],
+    see_also: &["df", "tree"],
+};
+
+pub struct Du<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Du<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options {
+            all: false,
+            max_depth: None,
+            apparent: false,
+            unit: Unit::Kilobytes,
+            total: false,
+        };
+        let mut summarize = false;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("all") => options.all = true,
+                Arg::Opt("summarize") => summarize = true,
+                Arg::Opt("max-depth") => {
+                    let value = parser.value();
+                    let depth = value.parse().map_err(|_| {
+                        Error::usage("du", format!("invalid maximum depth '{}'", value))
+                    })?;
+                    options.max_depth = Some(depth);
+                }
+                Arg::Opt("human-readable") => options.unit = Unit::Human,
+                Arg::Opt("k") => options.unit = Unit::Kilobytes,
+                Arg::Opt("bytes") => {
+                    options.unit = Unit::Bytes;
+                    options.apparent = true;
+                }
+                Arg::Opt("apparent-size") => options.apparent = true,
+                Arg::Opt("total") => options.total = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if summarize {
+            if options.max_depth.is_some_and(|depth| depth > 0) {
+                return Err(Error::usage(
+                    "du",
+                    "cannot both summarize and show all entries",
+                ));
+            }
+            options.max_depth = Some(0);
+        }
+        if operands.is_empty() {
+            operands.push(".");
+        }
+
+        let mut total = 0;
+        let mut failures = Failures::default();
+        for operand in operands {
+            let path = self.vars.resolve_path(operand);
+            let metadata = match path.symlink_metadata() {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    failures.report(Error::io("du", operand, err));
+                    continue;
+                }
+            };
+            // Every operand counts its own hard links, as in GNU du.
+            let scan = Scan {
+                options: &options,
+                walker: Walker::new(&path).hidden(true),
+                seen: Mutex::new(HashSet::new()),
+                failures: Mutex::default(),
+            };
+            let usage = if metadata.is_dir() {
+                scan.measure_root(&path, operand.to_string(), &metadata)
+            } else {
+                Usage {
+                    label: operand.to_string(),
+                    size: scan.size(&metadata).unwrap_or_default(),
+                    children: Vec::new(),
+                }
+            };
+            total += usage.size;
+            failures.merge(
+                scan.failures
+                    .into_inner()
+                    .unwrap_or_else(|err| err.into_inner()),
+            );
+            Self::print(&options, &usage);
+        }
+        if options.total {
+            println!("{}\ttotal", Self::format_size(&options, total));
+        }
+        failures.finish("du")
+    }
+}
+
+impl<'a> Du<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    fn format_size(options: &Options, bytes: u64) -> String {
+        match options.unit {
+            Unit::Kilobytes => bytes.div_ceil(1024).to_string(),
+            Unit::Bytes => bytes.to_string(),
+            Unit::Human => human_size(bytes),
+        }
+    }
+
+    /// Prints the children before their parent, like `du` does.
+    fn print(options: &Options, usage: &Usage) {
+        for child in &usage.children {
+            Self::print(options, child);
+        }
+        println!(
+            "{}\t{}",
+            Self::format_size(options, usage.size),
+            usage.label
+        );
+    }
+}
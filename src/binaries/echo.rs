@@ -1,24 +1,192 @@
-use colored::Colorize;
+use std::io::{self, Write};
 
-use crate::cmd::CMD;
+use crate::cmd::{
+    error::Error,
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::theme::Role;
 
-use super::Runnable;
+use super::{man::Page, Runnable};
+
+pub const SPEC: Spec = Spec::new("echo", "print a line of text")
+    .usage(&["echo [-neE] [text...]"])
+    .options(&[
+        Opt::new("n", &[], "Do not print the trailing newline."),
+        Opt::new("e", &[], "Interpret backslash escapes."),
+        Opt::new("E", &[], "Do not interpret backslash escapes, the default."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the words following the command name separated by single spaces, after \
+                  the shell removed their quotes and expanded $?, followed by a newline.\n\n\
+                  Like GNU echo, only leading words made of the letters n, e and E are options, \
+                  so 'echo -x' prints -x, and --help is only recognized alone. Any other word \
+                  starting with dashes is printed as it is.\n\n\
+                  With -e, \\\\ is a backslash, \\a, \\b, \\e, \\f, \\n, \\r, \\t and \\v are \
+                  the usual control characters, \\0nnn is the byte with the octal value nnn, \
+                  \\xHH the byte with the hexadecimal value HH, \\uHHHH and \\UHHHHHHHH the \
+                  Unicode character with that code, and \\c stops the output, newline included.",
+    examples: &[
+        ("echo hello world", "Print 'hello world'."),
+        (
+            "echo -n $?",
+            "Print the status of the last command without a newline.",
+        ),
+        (
+            "echo -e 'name\\tsize'",
+            "Print two words separated by a tab.",
+        ),
+    ],
+    see_also: &["printf", "cat"],
+};
+
+/// The number of leading digits of `text` in `radix`, at most `max`.
+fn digits(text: &str, radix: u32, max: usize) -> usize {
+    text.chars()
+        .take(max)
+        .take_while(|c| c.is_digit(radix))
+        .count()
+}
+
+/// Decodes the escape sequence at the start of `rest`, the text following
+/// a backslash, into `out` and returns its length, or `None` for `\c`.
+/// Octal values need a leading zero with `echo` (`\0nnn`) but not in a
+/// `printf` format (`\nnn`), hence `zero_octal`. Unknown sequences are
+/// kept as they are.
+pub fn read_escape(rest: &str, zero_octal: bool, out: &mut Vec<u8>) -> Option<usize> {
+    let Some(c) = rest.chars().next() else {
+        out.push(b'\\');
+        return Some(0);
+    };
+    let byte = match c {
+        '\\' => Some(b'\\'),
+        'a' => Some(0x07),
+        'b' => Some(0x08),
+        'e' => Some(0x1b),
+        'f' => Some(0x0c),
+        'n' => Some(b'\n'),
+        'r' => Some(b'\r'),
+        't' => Some(b'\t'),
+        'v' => Some(0x0b),
+        '"' if !zero_octal => Some(b'"'),
+        _ => None,
+    };
+    if let Some(byte) = byte {
+        out.push(byte);
+        return Some(1);
+    }
+
+    let (start, radix, max) = match c {
+        'c' => return None,
+        'x' => (1, 16, 2),
+        'u' => (1, 16, 4),
+        'U' => (1, 16, 8),
+        '0' if zero_octal => (1, 8, 3),
+        '0'..='7' if !zero_octal => (0, 8, 3),
+        _ => {
+            out.extend_from_slice(format!("\\{}", c).as_bytes());
+            return Some(c.len_utf8());
+        }
+    };
+    let count = digits(&rest[start..], radix, max);
+    let Ok(value) = u32::from_str_radix(&rest[start..start + count], radix) else {
+        // `\0` alone is a NUL, while `\x` and `\u` without digits are kept.
+        match c {
+            '0' => out.push(0),
+            _ => out.extend_from_slice(format!("\\{}", c).as_bytes()),
+        }
+        return Some(1);
+    };
+    match c {
+        'u' | 'U' => match char::from_u32(value) {
+            Some(decoded) => out.extend_from_slice(decoded.encode_utf8(&mut [0; 4]).as_bytes()),
+            None => out.extend_from_slice(format!("\\{}", &rest[..start + count]).as_bytes()),
+        },
+        // Like GNU tools, octal values above 0377 keep their low byte.
+        _ => out.push(value as u8),
+    }
+    Some(start + count)
+}
+
+/// Appends `text` to `out` with its escapes decoded as by `echo -e`,
+/// returning false when `\c` asked to stop the output.
+pub fn unescape(text: &str, out: &mut Vec<u8>) -> bool {
+    let mut rest = text;
+    while let Some(idx) = rest.find('\\') {
+        out.extend_from_slice(&rest.as_bytes()[..idx]);
+        let Some(length) = read_escape(&rest[idx + 1..], true, out) else {
+            return false;
+        };
+        rest = &rest[idx + 1 + length..];
+    }
+    out.extend_from_slice(rest.as_bytes());
+    true
+}
+
+/// The text `echo` prints for its words and whether a newline follows it.
+fn render(mut words: &[String]) -> (Vec<u8>, bool) {
+    // Parsed by hand: anything that is not an option is printed as it is.
+    let mut newline = true;
+    let mut escapes = false;
+    while let [first, rest @ ..] = words {
+        let letters = match first.strip_prefix('-') {
+            Some(letters) if !letters.is_empty() => letters,
+            _ => break,
+        };
+        if !letters.chars().all(|c| matches!(c, 'n' | 'e' | 'E')) {
+            break;
+        }
+        for c in letters.chars() {
+            match c {
+                'n' => newline = false,
+                'e' => escapes = true,
+                _ => escapes = false,
+            }
+        }
+        words = rest;
+    }
+
+    let mut bytes = Vec::new();
+    for (idx, word) in words.iter().enumerate() {
+        if idx > 0 {
+            bytes.push(b' ');
+        }
+        if !escapes {
+            bytes.extend_from_slice(word.as_bytes());
+        } else if !unescape(word, &mut bytes) {
+            newline = false;
+            break;
+        }
+    }
+    (bytes, newline)
+}
 
 pub struct Echo<'a> {
     vars: &'a CMD,
 }
 
 impl<'a> Runnable for Echo<'a> {
-    fn run(&mut self) -> Result<(), String> {
-        if self.vars.get_tokens_length() >= 2 {
-            let mut itr = 4;
-            while itr < self.vars.get_chars().len() && self.vars.get_chars()[itr] == ' ' {
-                itr += 1;
+    fn run(&mut self) -> Result<(), Error> {
+        let words = self.vars.get_args();
+        if let [only] = words {
+            if only == "--help" {
+                return Err(Error::help(SPEC.help(None)));
             }
-            let tmp: String = self.vars.get_chars().iter().collect();
-            print!("{}", &tmp[itr..self.vars.get_chars().len()].green());
         }
-        Ok(())
+
+        let (bytes, newline) = render(words);
+        let mut out = io::stdout().lock();
+        let written = match String::from_utf8(bytes) {
+            Ok(text) => write!(out, "{}", Role::Echo.paint(&text)),
+            // Bytes from \x or \0 escapes are written as they are.
+            Err(err) => out.write_all(err.as_bytes()),
+        };
+        written
+            .and_then(|_| if newline { writeln!(out) } else { Ok(()) })
+            .and_then(|_| out.flush())
+            .map_err(|err| Error::io("echo", "standard output", err))
     }
 }
 
@@ -27,3 +195,57 @@ impl<'a> Echo<'a> {
         Self { vars: cmd }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo(words: &[&str]) -> String {
+        let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+        let (bytes, newline) = render(&words);
+        let mut text = String::from_utf8(bytes).unwrap();
+        if newline {
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn options_come_first_and_the_last_one_wins() {
+        let cases: [(&[&str], &str); 12] = [
+            (&["a", "b"], "a b\n"),
+            (&["-n", "a"], "a"),
+            (&["a\\tb"], "a\\tb\n"),
+            (&["-e", "a\\tb"], "a\tb\n"),
+            (&["-e", "-E", "a\\tb"], "a\\tb\n"),
+            (&["-E", "-e", "a\\tb"], "a\tb\n"),
+            (&["-eE", "a\\tb"], "a\\tb\n"),
+            (&["-Ee", "a\\tb"], "a\tb\n"),
+            (&["-ne", "a\\n"], "a\n"),
+            (&["-x", "-n", "a"], "-x -n a\n"),
+            (&["a", "-n"], "a -n\n"),
+            (&["--", "-", "a"], "-- - a\n"),
+        ];
+        for (words, expected) in cases {
+            assert_eq!(echo(words), expected, "echo {:?}", words);
+        }
+    }
+
+    #[test]
+    fn escapes_are_decoded_with_e() {
+        let cases: [(&[&str], &str); 9] = [
+            (&["-e", "a\\cb", "c"], "a"),
+            (&["-e", "a", "b\\c"], "a b"),
+            (&["-e", "\\0101\\0"], "A\0\n"),
+            (&["-e", "\\01011"], "A1\n"),
+            (&["-e", "\\101"], "\\101\n"),
+            (&["-e", "\\x41\\x4a\\x4"], "AJ\u{4}\n"),
+            (&["-e", "\\xZZ"], "\\xZZ\n"),
+            (&["-e", "\\u00e9\\U0001F600"], "é\u{1F600}\n"),
+            (&["-e", "\\\\\\q\\"], "\\\\q\\\n"),
+        ];
+        for (words, expected) in cases {
+            assert_eq!(echo(words), expected, "echo {:?}", words);
+        }
+    }
+}
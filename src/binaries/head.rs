@@ -0,0 +1,141 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::format::parse_size;
+
+use super::{man::Page, Runnable};
+
+const DEFAULT_LINES: u64 = 10;
+
+#[derive(Clone, Copy)]
+enum Count {
+    Lines(u64),
+    Bytes(u64),
+}
+
+pub const SPEC: Spec = Spec::new("head", "output the first part of files")
+    .usage(&["head [-n lines] [-c bytes] [-q] [-v] [file...]"])
+    .options(&[
+        Opt::new("n", &["lines"], "Print the first lines lines.").value("lines"),
+        Opt::new(
+            "c",
+            &["bytes"],
+            "Print the first bytes bytes, which may carry a unit like 1K.",
+        )
+        .value("bytes"),
+        Opt::new("q", &["quiet"], "Never print headers."),
+        Opt::new("v", &["verbose"], "Always print headers."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the first 10 lines of each file, or of the standard input. With several \
+                  files each one is preceded by a header with its name.",
+    examples: &[(
+        "head -n 3 notes.txt",
+        "Print the first three lines of notes.txt.",
+    )],
+    see_also: &["tail", "cat"],
+};
+
+pub struct Head<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Head<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut count = Count::Lines(DEFAULT_LINES);
+        let mut headers: Option<bool> = None;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("lines") => count = Count::Lines(Self::parse_count(parser.value())?),
+                Arg::Opt("bytes") => count = Count::Bytes(Self::parse_count(parser.value())?),
+                Arg::Opt("quiet") => headers = Some(false),
+                Arg::Opt("verbose") => headers = Some(true),
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if operands.is_empty() {
+            operands.push("-");
+        }
+        let show_headers = headers.unwrap_or(operands.len() > 1);
+
+        let mut out = io::stdout().lock();
+        let mut failures = Failures::default();
+        for (idx, operand) in operands.iter().enumerate() {
+            let input: Box<dyn Read + '_> = if *operand == "-" {
+                self.vars.stdin()
+            } else {
+                let mut path = self.vars.get_current_dir_path().clone();
+                path.push(operand);
+                match File::open(&path) {
+                    Ok(file) => Box::new(file),
+                    Err(err) => {
+                        failures.report(Error::io("head", operand, err));
+                        continue;
+                    }
+                }
+            };
+
+            if show_headers {
+                let separator = if idx == 0 { "" } else { "\n" };
+                let name = if *operand == "-" {
+                    "standard input"
+                } else {
+                    operand
+                };
+                let _ = writeln!(out, "{}==> {} <==", separator, name);
+            }
+            if let Err(err) = Self::copy_head(input, count, &mut out) {
+                failures.report(Error::io("head", operand, err));
+            }
+        }
+        out.flush()
+            .map_err(|err| Error::io("head", "standard output", err))?;
+        failures.finish("head")
+    }
+}
+
+impl<'a> Head<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    fn parse_count(value: &str) -> Result<u64, Error> {
+        parse_size(value)
+            .ok_or_else(|| Error::usage("head", format!("invalid number: '{}'", value)))
+    }
+
+    /// Copies only the requested prefix, so huge files are never read in full.
+    fn copy_head(input: Box<dyn Read + '_>, count: Count, out: &mut impl Write) -> io::Result<()> {
+        match count {
+            Count::Bytes(bytes) => {
+                io::copy(&mut input.take(bytes), out)?;
+            }
+            Count::Lines(lines) => {
+                let mut reader = BufReader::new(input);
+                let mut line = Vec::new();
+                for _ in 0..lines {
+                    line.clear();
+                    if reader.read_until(b'\n', &mut line)? == 0 {
+                        break;
+                    }
+                    out.write_all(&line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::io::{self, IsTerminal, Read, Write};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::pager::Pager;
+
+use super::{man::Page, Runnable};
+
+pub const SPEC: Spec = Spec::new("less", "view files one screen at a time")
+    .usage(&["less [-S] [-p pattern] [+F] [file...]"])
+    .options(&[
+        Opt::new(
+            "S",
+            &["chop-long-lines"],
+            "Cut long lines instead of wrapping them.",
+        ),
+        Opt::new("p", &["pattern"], "Start at the first match of pattern.").value("pattern"),
+        Opt::new(
+            "",
+            &["follow"],
+            "Start following appended data, like tail -f. +F does the same.",
+        ),
+        Opt::new(
+            "R",
+            &["RAW-CONTROL-CHARS"],
+            "Accepted for compatibility, colors are always kept.",
+        ),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Show the files, or the standard input, in a full-screen viewer. j/k scroll, \
+                  space/b page, g/G go to the top/end, / and ? search forwards and backwards, \
+                  n/N jump to the next/previous match, :N goes to line N, w toggles wrapping, F \
+                  follows appended data and q quits.\n\n\
+                  Colors of the input are kept. When the output is not a terminal the files are \
+                  printed as they are.",
+    examples: &[("less +F app.log", "Watch app.log grow.")],
+    see_also: &["cat", "tail", "man"],
+};
+
+pub struct Less<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Less<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut chop = false;
+        let mut follow = false;
+        let mut pattern = None;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("chop-long-lines") => chop = true,
+                // Escapes are always interpreted, `-R` is accepted for muscle memory.
+                Arg::Opt("RAW-CONTROL-CHARS") => {}
+                Arg::Opt("follow") | Arg::Value("+F") => follow = true,
+                Arg::Opt("pattern") => pattern = Some(parser.value()),
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if operands.is_empty() {
+            operands.push("-");
+        }
+        if !io::stdout().is_terminal() {
+            return self.copy(&operands);
+        }
+
+        for operand in operands {
+            let pager = if operand == "-" {
+                if !self.vars.has_piped_input() && io::stdin().is_terminal() {
+                    return Err(Error::usage("less", "missing filename"));
+                }
+                let mut bytes = Vec::new();
+                self.vars
+                    .stdin()
+                    .read_to_end(&mut bytes)
+                    .map_err(|err| Error::io("less", "standard input", err))?;
+                Pager::new("(standard input)", &String::from_utf8_lossy(&bytes))
+            } else {
+                let mut path = self.vars.get_current_dir_path().clone();
+                path.push(operand);
+                if path.is_dir() {
+                    return Err(Error::io(
+                        "less",
+                        operand,
+                        io::Error::other("Is a directory"),
+                    ));
+                }
+                Pager::from_file(operand, &path).map_err(|err| Error::io("less", operand, err))?
+            };
+
+            let mut pager = pager.chop_long_lines(chop).follow(follow);
+            if let Some(pattern) = pattern {
+                pager = pager.with_search(pattern);
+            }
+            pager
+                .run()
+                .map_err(|err| Error::failed("less", err.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Less<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    /// Copies the inputs to the standard output unchanged, as `less` does
+    /// when it is not writing to a terminal.
+    fn copy(&self, operands: &[&str]) -> Result<(), Error> {
+        let mut out = io::stdout().lock();
+        let mut failures = Failures::default();
+        for operand in operands {
+            let mut input = match self.vars.open_input(operand) {
+                Ok(input) => input,
+                Err(err) => {
+                    failures.report(Error::io("less", *operand, err));
+                    continue;
+                }
+            };
+            if let Err(err) = io::copy(&mut input, &mut out) {
+                failures.report(Error::io("less", *operand, err));
+            }
+        }
+        out.flush()
+            .map_err(|err| Error::io("less", "standard output", err))?;
+        failures.finish("less")
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fs, io,
+    os::unix::fs::{symlink, MetadataExt},
+    path::{Path, PathBuf},
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+
+use super::{man::Page, Runnable};
+
+struct Options {
+    symbolic: bool,
+    force: bool,
+    no_dereference: bool,
+    verbose: bool,
+}
+
+pub const SPEC: Spec = Spec::new("ln", "make links between files")
+    .usage(&[
+        "ln [-s] [-f] [-n] [-v] target [link]",
+        "ln [-s] [-f] [-n] [-v] target... directory",
+    ])
+    .options(&[
+        Opt::new("s", &["symbolic"], "Create symbolic links."),
+        Opt::new("f", &["force"], "Replace existing files."),
+        Opt::new(
+            "n",
+            &["no-dereference"],
+            "Treat a symlink to a directory given as link as a file.",
+        ),
+        Opt::new("v", &["verbose"], "Print the name of every link."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Create a hard link to target named link, or named like target in the current \
+                  directory. With several targets, create the links in directory.",
+    examples: &[(
+        "ln -sf build/app app",
+        "Point the app symlink to build/app.",
+    )],
+    see_also: &["readlink"],
+};
+
+pub struct Ln<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Ln<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options {
+            symbolic: false,
+            force: false,
+            no_dereference: false,
+            verbose: false,
+        };
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("symbolic") => options.symbolic = true,
+                Arg::Opt("force") => options.force = true,
+                Arg::Opt("no-dereference") => options.no_dereference = true,
+                Arg::Opt("verbose") => options.verbose = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let (targets, directory) = match operands.as_slice() {
+            [] => return Err(Error::usage("ln", "missing file operand")),
+            // A single target is linked into the current directory.
+            [target] => (vec![*target], None),
+            [target, link] => {
+                if self.is_directory(&options, link) {
+                    (vec![*target], Some(*link))
+                } else {
+                    let path = self.vars.resolve_path(link);
+                    return self.link(&options, target, &path, link);
+                }
+            }
+            [targets @ .., directory] => {
+                if !self.is_directory(&options, directory) {
+                    return Err(Error::io(
+                        "ln",
+                        *directory,
+                        io::Error::other("target is not a directory"),
+                    ));
+                }
+                (targets.to_vec(), Some(*directory))
+            }
+        };
+
+        let mut failures = Failures::default();
+        for target in targets {
+            let Some(name) = Path::new(target).file_name() else {
+                failures.report(Error::usage(
+                    "ln",
+                    format!("cannot link '{}': no file name", target),
+                ));
+                continue;
+            };
+            let label = match directory {
+                Some(directory) => Path::new(directory).join(name),
+                None => PathBuf::from(name),
+            };
+            let path = self.vars.resolve_path(&label.to_string_lossy());
+            if let Err(err) = self.link(&options, target, &path, &label.to_string_lossy()) {
+                failures.report(err);
+            }
+        }
+        failures.finish("ln")
+    }
+}
+
+impl<'a> Ln<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    /// Whether new links go inside `operand`. With `-n` a symlink to a
+    /// directory is replaced rather than entered.
+    fn is_directory(&self, options: &Options, operand: &str) -> bool {
+        let path = self.vars.resolve_path(operand);
+        if options.no_dereference
+            && fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+        {
+            return false;
+        }
+        path.is_dir()
+    }
+
+    fn link(&self, options: &Options, target: &str, path: &Path, label: &str) -> Result<(), Error> {
+        let error = |err: io::Error| Error::io("ln", label, err);
+        // Hard links name an existing file, while symlink targets are stored
+        // as written and resolved relative to the link later.
+        let source = self.vars.resolve_path(target);
+
+        if let Ok(existing) = fs::symlink_metadata(path) {
+            if !options.force {
+                return Err(error(io::Error::from_raw_os_error(libc::EEXIST)));
+            }
+            if existing.is_dir() {
+                return Err(Error::failed(
+                    "ln",
+                    format!("cannot overwrite directory '{}'", label),
+                ));
+            }
+            let same_file = fs::metadata(&source).is_ok_and(|source| {
+                source.dev() == existing.dev() && source.ino() == existing.ino()
+            });
+            if same_file && !options.symbolic {
+                return Err(Error::failed(
+                    "ln",
+                    format!("'{}' and '{}' are the same file", target, label),
+                ));
+            }
+            fs::remove_file(path).map_err(error)?;
+        }
+
+        if options.symbolic {
+            symlink(target, path).map_err(error)?;
+        } else {
+            fs::hard_link(&source, path).map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound if !source.exists() => Error::io("ln", target, err),
+                _ => error(err),
+            })?;
+        }
+
+        if options.verbose {
+            let arrow = if options.symbolic { "->" } else { "=>" };
+            println!("'{}' {} '{}'", label, arrow, target);
+        }
+        Ok(())
+    }
+}
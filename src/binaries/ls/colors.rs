@@ -0,0 +1,116 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::Metadata,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::Path,
+};
+
+use colored::control::SHOULD_COLORIZE;
+
+use crate::theme::Role;
+
+/// The `dircolors` key describing a file: `di`, `ln`, `or` (broken link), `ex`, `fi`, ...
+/// `metadata` must not follow symlinks.
+pub(crate) fn type_key(path: &Path, metadata: &Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        "di"
+    } else if file_type.is_symlink() {
+        if path.exists() {
+            "ln"
+        } else {
+            "or"
+        }
+    } else if file_type.is_fifo() {
+        "pi"
+    } else if file_type.is_socket() {
+        "so"
+    } else if file_type.is_block_device() {
+        "bd"
+    } else if file_type.is_char_device() {
+        "cd"
+    } else if metadata.permissions().mode() & 0o111 != 0 {
+        "ex"
+    } else {
+        "fi"
+    }
+}
+
+/// How `ls` colors names: the built-in scheme, or the one described by `LS_COLORS`.
+/// Other listing commands such as `tree` reuse it so colors stay consistent.
+#[derive(Default)]
+pub(crate) enum Palette {
+    #[default]
+    Builtin,
+    LsColors {
+        types: HashMap<String, String>,
+        extensions: HashMap<String, String>,
+    },
+}
+
+impl Palette {
+    pub fn from_env() -> Self {
+        match env::var("LS_COLORS") {
+            Ok(spec) if !spec.is_empty() => Self::parse(&spec),
+            _ => Self::Builtin,
+        }
+    }
+
+    /// Parses the `dircolors` format, e.g. `di=01;34:ln=01;36:*.rs=33`.
+    /// Malformed items are skipped, the same way GNU ls ignores them.
+    pub fn parse(spec: &str) -> Self {
+        let mut types = HashMap::new();
+        let mut extensions = HashMap::new();
+        for item in spec.split(':') {
+            let Some((key, code)) = item.split_once('=') else {
+                continue;
+            };
+            if code.is_empty() || !code.chars().all(|c| c.is_ascii_digit() || c == ';') {
+                continue;
+            }
+            match key.strip_prefix("*.") {
+                Some(ext) => extensions.insert(ext.to_lowercase(), code.to_string()),
+                None => types.insert(key.to_string(), code.to_string()),
+            };
+        }
+        Self::LsColors { types, extensions }
+    }
+
+    pub fn paint(&self, text: &str, path: &Path, metadata: &Metadata) -> String {
+        let key = type_key(path, metadata);
+        match self {
+            Self::Builtin => match key {
+                "di" => Role::Dir.paint(text).to_string(),
+                "ln" => Role::Symlink.paint(text).to_string(),
+                "or" => Role::BrokenLink.paint(text).to_string(),
+                "fi" => Role::File.paint(text).to_string(),
+                "ex" => Role::Executable.paint(text).to_string(),
+                _ => text.to_string(),
+            },
+            Self::LsColors { types, extensions } => {
+                let by_extension = match key {
+                    "fi" | "ex" => path
+                        .extension()
+                        .and_then(|ext| extensions.get(&ext.to_string_lossy().to_lowercase())),
+                    _ => None,
+                };
+                let fallback = match key {
+                    "ex" => "fi",
+                    "or" => "ln",
+                    _ => "",
+                };
+                let code = by_extension
+                    .or_else(|| types.get(key))
+                    .or_else(|| types.get(fallback));
+
+                match code {
+                    Some(code) if SHOULD_COLORIZE.should_colorize() => {
+                        format!("\x1b[{}m{}\x1b[0m", code, text)
+                    }
+                    _ => text.to_string(),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::{env, fmt::Write};
+
+use console::measure_text_width;
+
+const COLUMN_GAP: usize = 2;
+
+/// Width of the terminal, preferring `COLUMNS` like GNU ls does.
+pub(crate) fn terminal_width() -> usize {
+    if let Some(width) = env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse::<usize>().ok())
+        .filter(|&width| width > 0)
+    {
+        return width;
+    }
+    match crossterm::terminal::size() {
+        Ok((width, _)) if width > 0 => width as usize,
+        _ => 80,
+    }
+}
+
+/// Prints already decorated `cells` in columns, filled top to bottom like GNU ls.
+/// Widths are measured without escape codes so colored names line up.
+pub(super) fn print(out: &mut String, cells: &[String], width: usize) {
+    if cells.is_empty() {
+        return;
+    }
+
+    let widths: Vec<usize> = cells.iter().map(|cell| measure_text_width(cell)).collect();
+    let (rows, column_widths) = layout(&widths, width);
+
+    for row in 0..rows {
+        let mut line = String::new();
+        for (col, column_width) in column_widths.iter().enumerate() {
+            let idx = col * rows + row;
+            if idx >= cells.len() {
+                break;
+            }
+            line.push_str(&cells[idx]);
+            let is_last = col + 1 == column_widths.len() || idx + rows >= cells.len();
+            if !is_last {
+                line.push_str(&" ".repeat(column_width - widths[idx] + COLUMN_GAP));
+            }
+        }
+        let _ = writeln!(out, "{}", line);
+    }
+}
+
+/// Finds the largest number of columns that fits into `width`,
+/// returning the number of rows and the width of each column.
+fn layout(widths: &[usize], width: usize) -> (usize, Vec<usize>) {
+    for columns in (1..=widths.len()).rev() {
+        let rows = widths.len().div_ceil(columns);
+        let column_widths: Vec<usize> = widths
+            .chunks(rows)
+            .map(|column| column.iter().copied().max().unwrap_or(0))
+            .collect();
+        let total = column_widths.iter().sum::<usize>()
+            + COLUMN_GAP * column_widths.len().saturating_sub(1);
+        if total <= width || columns == 1 {
+            return (rows, column_widths);
+        }
+    }
+    (0, Vec::new())
+}
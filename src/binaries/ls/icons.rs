@@ -0,0 +1,38 @@
+use super::Entry;
+
+/// Nerd Font glyph for `entry`, chosen by file type and then by extension.
+pub(super) fn icon_for(entry: &Entry) -> &'static str {
+    match entry.type_key() {
+        "di" => return "\u{f115}",
+        "ln" | "or" => return "\u{f0c1}",
+        _ => {}
+    }
+
+    let extension = entry
+        .path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "rs" => "\u{e7a8}",
+        "toml" | "ini" | "cfg" | "conf" | "yaml" | "yml" => "\u{e615}",
+        "json" => "\u{e60b}",
+        "md" | "markdown" => "\u{e609}",
+        "sh" | "bash" | "zsh" | "fish" => "\u{f489}",
+        "py" => "\u{e606}",
+        "js" | "mjs" => "\u{e74e}",
+        "ts" => "\u{e628}",
+        "c" | "h" => "\u{e61e}",
+        "cpp" | "cc" | "hpp" => "\u{e61d}",
+        "go" => "\u{e626}",
+        "html" | "htm" => "\u{e736}",
+        "css" => "\u{e749}",
+        "db" | "sqlite" | "sqlite3" => "\u{f1c0}",
+        "lock" => "\u{f023}",
+        "txt" | "log" => "\u{f15c}",
+        "png" | "jpg" | "jpeg" | "gif" | "svg" | "bmp" | "webp" => "\u{f1c5}",
+        "zip" | "tar" | "gz" | "xz" | "bz2" | "7z" | "zst" => "\u{f410}",
+        _ if entry.type_key() == "ex" => "\u{f489}",
+        _ => "\u{f15b}",
+    }
+}
@@ -0,0 +1,74 @@
+use std::{fmt::Write, os::unix::fs::MetadataExt};
+
+use crate::fs::{
+    format::{human_size, listing_time, mode_string},
+    users::Users,
+};
+
+use super::{Entry, Options};
+
+struct Row {
+    mode: String,
+    links: String,
+    owner: String,
+    group: String,
+    size: String,
+    time: String,
+}
+
+/// Prints `entries` in the `ls -l` format, aligning every numeric and name column.
+pub(super) fn print(
+    out: &mut String,
+    entries: &[Entry],
+    options: &Options,
+    users: &Users,
+    show_total: bool,
+) {
+    let rows: Vec<Row> = entries
+        .iter()
+        .map(|entry| {
+            let metadata = &entry.metadata;
+            Row {
+                mode: mode_string(metadata.mode()),
+                links: metadata.nlink().to_string(),
+                owner: users.user_name(metadata.uid()),
+                group: users.group_name(metadata.gid()),
+                size: if options.human {
+                    human_size(metadata.size())
+                } else {
+                    metadata.size().to_string()
+                },
+                time: listing_time(entry.modified()),
+            }
+        })
+        .collect();
+
+    if show_total {
+        // st_blocks is counted in 512 byte units, ls reports 1K blocks.
+        let blocks: u64 = entries.iter().map(|e| e.metadata.blocks()).sum::<u64>() / 2;
+        if options.human {
+            let _ = writeln!(out, "total {}", human_size(blocks * 1024));
+        } else {
+            let _ = writeln!(out, "total {}", blocks);
+        }
+    }
+
+    let links_width = rows.iter().map(|r| r.links.len()).max().unwrap_or(0);
+    let owner_width = rows.iter().map(|r| r.owner.len()).max().unwrap_or(0);
+    let group_width = rows.iter().map(|r| r.group.len()).max().unwrap_or(0);
+    let size_width = rows.iter().map(|r| r.size.len()).max().unwrap_or(0);
+
+    for (row, entry) in rows.iter().zip(entries.iter()) {
+        let _ = writeln!(
+            out,
+            "{} {:>links_width$} {:<owner_width$} {:<group_width$} {:>size_width$} {} {}",
+            row.mode,
+            row.links,
+            row.owner,
+            row.group,
+            row.size,
+            row.time,
+            entry.decorated_name(options),
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use std::{
+    fmt::Write,
+    fs::{self, Metadata},
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    output::Format,
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::{ignore::Ignore, record::FileRecord, users::Users, walk::Walker};
+use crate::pager;
+use crate::theme::Role;
+
+use self::colors::Palette;
+
+use super::{man::Page, Runnable};
+
+pub(crate) mod colors;
+pub(crate) mod grid;
+mod icons;
+mod long;
+
+#[derive(Default, PartialEq)]
+enum SortKey {
+    #[default]
+    Name,
+    Time,
+    Size,
+}
+
+#[derive(Default)]
+struct Options {
+    all: bool,
+    almost_all: bool,
+    long: bool,
+    human: bool,
+    recursive: bool,
+    gitignore: bool,
+    sort: SortKey,
+    reverse: bool,
+    one_per_line: bool,
+    classify: bool,
+    icons: bool,
+    palette: Palette,
+    format: Format,
+}
+
+/// A file to be listed. `metadata` never follows symlinks.
+struct Entry {
+    name: String,
+    path: PathBuf,
+    metadata: Metadata,
+}
+
+impl Entry {
+    fn new(name: &str, path: PathBuf) -> Result<Self, Error> {
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) => Ok(Self {
+                name: name.to_string(),
+                path,
+                metadata,
+            }),
+            Err(err) => Err(Error::io("ls", name, err)),
+        }
+    }
+
+    /// `path` is how the entry was reached from the operand, e.g. `src/main.rs`.
+    fn record(&self, path: &str, users: &Users) -> FileRecord {
+        FileRecord::new(&self.name, path, &self.path, &self.metadata, users)
+    }
+
+    fn modified(&self) -> SystemTime {
+        self.metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+
+    fn type_key(&self) -> &'static str {
+        colors::type_key(&self.path, &self.metadata)
+    }
+
+    /// The `-F` indicator appended to the name.
+    fn classifier(&self) -> Option<char> {
+        match self.type_key() {
+            "di" => Some('/'),
+            "ln" | "or" => Some('@'),
+            "ex" => Some('*'),
+            "pi" => Some('|'),
+            "so" => Some('='),
+            _ => None,
+        }
+    }
+
+    /// The name as printed: optional icon, colored name and `-F` indicator.
+    /// Long listings show symlink targets instead of the `@` indicator.
+    fn decorated_name(&self, options: &Options) -> String {
+        let mut out = String::new();
+        if options.icons {
+            out.push_str(icons::icon_for(self));
+            out.push(' ');
+        }
+        out.push_str(
+            &options
+                .palette
+                .paint(&self.name, &self.path, &self.metadata),
+        );
+
+        let is_symlink = self.metadata.file_type().is_symlink();
+        if options.long && is_symlink {
+            if let Ok(target) = fs::read_link(&self.path) {
+                let target_name = target.to_string_lossy().to_string();
+                let target_path = self.path.parent().unwrap_or(Path::new("/")).join(&target);
+                let target_display = match Entry::new(&target_name, target_path) {
+                    Ok(target) => {
+                        options
+                            .palette
+                            .paint(&target_name, &target.path, &target.metadata)
+                    }
+                    Err(_) => Role::BrokenLink.paint(&target_name).to_string(),
+                };
+                out.push_str(" -> ");
+                out.push_str(&target_display);
+            }
+        } else if options.classify {
+            if let Some(indicator) = self.classifier() {
+                out.push(indicator);
+            }
+        }
+        out
+    }
+}
+
+pub const SPEC: Spec = Spec::new("ls", "list directory contents")
+    .usage(&["ls [-aAlhRtSr1CF] [--icons] [--gitignore] [--json | --ndjson] [file...]"])
+    .options(&[
+        Opt::new(
+            "a",
+            &["all"],
+            "Include entries starting with '.', with . and .. too.",
+        ),
+        Opt::new(
+            "A",
+            &["almost-all"],
+            "Include entries starting with '.', but not . and ...",
+        ),
+        Opt::new(
+            "l",
+            &[],
+            "Long format: permissions, links, owner, group, size and modification time.",
+        ),
+        Opt::new("h", &["human-readable"], "Print sizes like 1K, 234M or 2G."),
+        Opt::new("R", &["recursive"], "List subdirectories recursively."),
+        Opt::new("t", &[], "Sort by modification time, newest first."),
+        Opt::new("S", &[], "Sort by size, largest first."),
+        Opt::new("r", &["reverse"], "Reverse the order of the sort."),
+        Opt::new("1", &[], "List one entry per line."),
+        Opt::new(
+            "C",
+            &[],
+            "List entries in columns, the default on terminals.",
+        ),
+        Opt::new(
+            "F",
+            &["classify"],
+            "Append / to directories, * to executables, @ to symlinks, | to pipes and = to \
+             sockets.",
+        ),
+        Opt::new("", &["icons"], "Show an icon before every name."),
+        Opt::new(
+            "",
+            &["gitignore"],
+            "Skip files ignored by .gitignore files.",
+        ),
+        Opt::new(
+            "",
+            &["json"],
+            "Print the entries as a JSON array of records.",
+        ),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "List the files given, and the contents of the directories given, or of the \
+                  current directory without operands. Names are colored by type, or as described \
+                  by $LS_COLORS.\n\n\
+                  With --json every entry becomes a record with its name, path, type, size, \
+                  mode, permissions, owner, group and mtime, and piped into a command reading \
+                  records ls passes them on.",
+    examples: &[
+        (
+            "ls -lh",
+            "List the current directory with human readable sizes.",
+        ),
+        (
+            "ls | where size > 1kb | sort-by mtime",
+            "Show the files larger than 1 KiB, oldest first.",
+        ),
+    ],
+    see_also: &["tree", "stat", "where"],
+};
+
+pub struct Ls<'a> {
+    vars: &'a mut CMD,
+}
+
+impl<'a> Runnable for Ls<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options {
+            palette: Palette::from_env(),
+            format: self.vars.output_format(),
+            one_per_line: !io::stdout().is_terminal(),
+            ..Default::default()
+        };
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            if let Some(format) = Format::from_arg(arg) {
+                options.format = format;
+                continue;
+            }
+            match arg {
+                Arg::Opt("all") => options.all = true,
+                Arg::Opt("almost-all") => options.almost_all = true,
+                Arg::Opt("l") => options.long = true,
+                Arg::Opt("human-readable") => options.human = true,
+                Arg::Opt("recursive") => options.recursive = true,
+                Arg::Opt("gitignore") => options.gitignore = true,
+                Arg::Opt("t") => options.sort = SortKey::Time,
+                Arg::Opt("S") => options.sort = SortKey::Size,
+                Arg::Opt("reverse") => options.reverse = true,
+                Arg::Opt("1") => options.one_per_line = true,
+                Arg::Opt("C") => options.one_per_line = false,
+                Arg::Opt("classify") => options.classify = true,
+                Arg::Opt("icons") => options.icons = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if operands.is_empty() {
+            operands.push(".");
+        }
+
+        let mut files = Vec::new();
+        let mut dirs = Vec::new();
+        let mut failures = Failures::default();
+        for operand in operands.iter() {
+            let mut path = self.vars.get_current_dir_path().clone();
+            path.push(operand);
+            match Entry::new(operand, path) {
+                Ok(entry) => {
+                    // Like GNU ls, a symlink operand pointing to a directory is
+                    // listed as that directory unless the long format is requested.
+                    let follows_to_dir = entry.metadata.file_type().is_symlink()
+                        && !options.long
+                        && entry.path.is_dir();
+                    if entry.metadata.is_dir() || follows_to_dir {
+                        dirs.push(entry);
+                    } else {
+                        files.push(entry);
+                    }
+                }
+                Err(err) => failures.report(err),
+            }
+        }
+
+        let users = if options.long || !options.format.is_text() {
+            Some(Users::load())
+        } else {
+            None
+        };
+        let show_headers = options.recursive || operands.len() > 1;
+
+        sort_entries(&mut files, &options);
+        sort_entries(&mut dirs, &options);
+
+        // Collected first so long listings can be paged when they don't fit the screen.
+        let mut out = match (options.format, &users) {
+            (Format::Text, _) | (_, None) => Sink::Text(String::new()),
+            (_, Some(users)) => Sink::Records(
+                files
+                    .iter()
+                    .map(|file| file.record(&file.name, users))
+                    .collect(),
+            ),
+        };
+        if let Sink::Text(text) = &mut out {
+            if !files.is_empty() {
+                print_entries(text, &files, &options, users.as_ref(), false);
+            }
+        }
+        for dir in dirs.iter() {
+            let walker = Walker::new(&dir.path)
+                .hidden(options.all || options.almost_all)
+                .git_ignore(options.gitignore);
+            let listing = Listing {
+                walker: &walker,
+                options: &options,
+                users: users.as_ref(),
+            };
+            listing.list_directory(
+                &mut out,
+                &mut failures,
+                &dir.path,
+                &dir.name,
+                &walker.root_ignore(),
+                show_headers,
+            );
+        }
+
+        match out {
+            Sink::Records(records) => options.format.print("ls", &records)?,
+            Sink::Text(text) if options.long => pager::page_or_print("ls", &text)
+                .map_err(|err| Error::io("ls", "standard output", err))?,
+            Sink::Text(text) => print!("{}", text),
+        }
+        failures.finish("ls")
+    }
+}
+
+/// Where the listing goes: text for the terminal, or records with `--json`.
+enum Sink {
+    Text(String),
+    Records(Vec<FileRecord>),
+}
+
+/// Everything needed to list one directory operand, possibly recursively.
+struct Listing<'a> {
+    walker: &'a Walker,
+    options: &'a Options,
+    users: Option<&'a Users>,
+}
+
+impl<'a> Listing<'a> {
+    /// Lists `path` into `out`, adding the directories that could not be
+    /// read to `failures`.
+    fn list_directory(
+        &self,
+        out: &mut Sink,
+        failures: &mut Failures,
+        path: &Path,
+        display_name: &str,
+        ignore: &Arc<Ignore>,
+        show_header: bool,
+    ) {
+        if let Sink::Text(text) = out {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            if show_header {
+                let _ = writeln!(text, "{}:", display_name);
+            }
+        }
+
+        let (mut entries, ignore) = match self.read_entries(path, ignore) {
+            Ok(listed) => listed,
+            Err(err) => {
+                failures.report(Error::failed(
+                    "ls",
+                    format!("cannot open directory '{}': {}", display_name, err),
+                ));
+                return;
+            }
+        };
+        sort_entries(&mut entries, self.options);
+        match out {
+            Sink::Text(text) => print_entries(text, &entries, self.options, self.users, true),
+            Sink::Records(records) => {
+                if let Some(users) = self.users {
+                    records.extend(entries.iter().map(|entry| {
+                        // Entries of the current directory are named like they
+                        // would be typed, `main.rs` rather than `./main.rs`.
+                        let path = child_path(display_name, &entry.name);
+                        entry.record(path.strip_prefix("./").unwrap_or(&path), users)
+                    }));
+                }
+            }
+        }
+
+        if self.options.recursive {
+            for entry in entries.iter() {
+                if entry.metadata.is_dir() && entry.name != "." && entry.name != ".." {
+                    let child_name = child_path(display_name, &entry.name);
+                    self.list_directory(out, failures, &entry.path, &child_name, &ignore, true);
+                }
+            }
+        }
+    }
+
+    fn read_entries(
+        &self,
+        path: &Path,
+        ignore: &Arc<Ignore>,
+    ) -> Result<(Vec<Entry>, Arc<Ignore>), String> {
+        let mut entries = Vec::new();
+        if self.options.all {
+            for name in [".", ".."] {
+                if let Ok(entry) = Entry::new(name, path.join(name)) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        let (listed, ignore) = self.walker.read_dir(path, 0, ignore)?;
+        entries.extend(listed.into_iter().map(|entry| Entry {
+            name: entry.name,
+            path: entry.path,
+            metadata: entry.metadata,
+        }));
+        Ok((entries, ignore))
+    }
+}
+
+fn child_path(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+fn sort_entries(entries: &mut [Entry], options: &Options) {
+    entries.sort_by(|a, b| {
+        let by_name = a
+            .name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.name.cmp(&b.name));
+        match options.sort {
+            SortKey::Name => by_name,
+            SortKey::Time => b.modified().cmp(&a.modified()).then(by_name),
+            SortKey::Size => b.metadata.len().cmp(&a.metadata.len()).then(by_name),
+        }
+    });
+    if options.reverse {
+        entries.reverse();
+    }
+}
+
+/// `is_directory` controls whether the `total` line of the long format is shown.
+fn print_entries(
+    out: &mut String,
+    entries: &[Entry],
+    options: &Options,
+    users: Option<&Users>,
+    is_directory: bool,
+) {
+    match users {
+        Some(users) if options.long => long::print(out, entries, options, users, is_directory),
+        _ => {
+            let names: Vec<String> = entries
+                .iter()
+                .map(|entry| entry.decorated_name(options))
+                .collect();
+            if options.one_per_line {
+                for name in names.iter() {
+                    let _ = writeln!(out, "{}", name);
+                }
+            } else {
+                grid::print(out, &names, grid::terminal_width());
+            }
+        }
+    }
+}
+
+impl<'a> Ls<'a> {
+    pub fn new(cmd: &'a mut CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
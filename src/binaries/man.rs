@@ -1,62 +1,323 @@
-use std::collections::HashMap;
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    spec::{fill, Operands, Opt, Spec},
+    CMD,
+};
+use crate::pager;
+use crate::theme::Role;
 
-use crate::cmd::CMD;
+use super::{
+    cat, cd, chmod, chown, complete, convert, cut, df, diff, dirs, du, echo, head, less, ln,
+    ls::{self, grid::terminal_width},
+    patch, printf, pwd, query, readlink, sed, sort, stat, tail, theme, todo, touch, tr, tree, uniq,
+    wc, z, Runnable,
+};
 
-use super::Runnable;
-
-pub struct Man<'a> {
-    vars: &'a CMD,
+/// A manual page. Every binary declares its own next to its code, and
+/// `PAGES` collects them.
+pub struct Page {
+    /// The name, summary, usage and options. Every command named first on
+    /// a usage line is documented by this page, as `quit` is by the one of
+    /// `exit`.
+    pub spec: &'static Spec,
+    /// Paragraphs separated by blank lines.
+    pub description: &'static str,
+    /// Command lines with what they do.
+    pub examples: &'static [(&'static str, &'static str)],
+    pub see_also: &'static [&'static str],
 }
 
-const MANUAL: &str = "echo: repeats input
-cat: concatenate files
-ls: list directories
-find: locate files or directories
-grep: matches text in files
+/// Notes about the shell itself, shown after the list of commands.
+const NOTES: &str = "Commands can be chained with '|', e.g. 'ls -l | sort -k5n | tail -n 3'
+'$?' expands to the exit status of the last command: 0 on success, 1 when it failed,
+2 for wrong usage, 3 for file errors, 4 when something was not found, 5 for database
+errors and 127 for unknown commands. A command that went on past failed operands exits
+with the status of the worst of them. Set CLI_ERROR_FORMAT=json to get errors as JSON
+'ls', 'pwd', 'stat' and 'todo list' print JSON records with --json, or one per line with
+--ndjson. Set CLI_OUTPUT_FORMAT=json or ndjson to make that the default
+Piped into a command reading records they pass records on, which end up drawn as a
+table, e.g. 'ls | where size > 1kb | sort-by mtime' or 'todo list | where is_done == false'
+Colors are used on terminals only. Every command takes --color=auto|always|never, and so
+does the shell when started, NO_COLOR turns them off and CLICOLOR_FORCE on otherwise
+The colors come from a theme: 'dark' (default), 'light', 'high-contrast', or a file in
+$XDG_CONFIG_HOME/cli-util/themes. Set CLI_THEME to pick one at start, see 'man theme'
+Every command prints a short help with --help, and 'complete' lists what can follow a
+command line, e.g. 'complete ls --g' or 'complete todo \"\"'
 ";
 
-const COMMANDS: [&str; 10] = [
-    "echo", "pwd", "cd", "ls", "find", "grep", "cat", "exit", "quit", "man",
+/// `exit` and `quit` are run by the shell itself rather than by a binary.
+const EXIT_SPEC: Spec = Spec::new("exit", "leave the shell")
+    .usage(&["exit [status]", "quit [status]"])
+    .operands(Operands::at_most(1));
+
+const EXIT: Page = Page {
+    spec: &EXIT_SPEC,
+    description: "Leave the shell with the given exit status, or with the status of the last \
+                  command when none is given.",
+    examples: &[("exit 1", "Leave the shell with status 1.")],
+    see_also: &[],
+};
+
+const SPEC: Spec = Spec::new("man", "show the manual of commands")
+    .usage(&["man [command]", "man -k keyword..."])
+    .options(&[Opt::new(
+        "k",
+        &["apropos"],
+        "Search the names, summaries and descriptions of all pages for the keywords, \
+             ignoring case, and list the pages matching any of them.",
+    )]);
+
+const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Without operands, list every command with a short summary followed by notes \
+                  about the shell. With a command, show its manual page, paged like 'less' when \
+                  it does not fit the terminal.",
+    examples: &[
+        ("man ls", "Show the manual page of ls."),
+        ("man -k json", "List the commands that read or write JSON."),
+    ],
+    see_also: &["less"],
+};
+
+/// Every manual page, in the order `man` lists them.
+const PAGES: [&Page; 43] = [
+    &echo::MANUAL,
+    &printf::MANUAL,
+    &pwd::MANUAL,
+    &cd::MANUAL,
+    &ls::MANUAL,
+    &cat::MANUAL,
+    &EXIT,
+    &MANUAL,
+    &tree::MANUAL,
+    &head::MANUAL,
+    &tail::MANUAL,
+    &less::MANUAL,
+    &wc::MANUAL,
+    &sort::MANUAL,
+    &uniq::MANUAL,
+    &cut::MANUAL,
+    &tr::MANUAL,
+    &sed::MANUAL,
+    &diff::MANUAL,
+    &patch::MANUAL,
+    &stat::MANUAL,
+    &du::MANUAL,
+    &df::MANUAL,
+    &touch::MANUAL,
+    &chmod::MANUAL,
+    &chown::MANUAL,
+    &ln::MANUAL,
+    &readlink::MANUAL,
+    &dirs::PUSHD_MANUAL,
+    &dirs::POPD_MANUAL,
+    &dirs::DIRS_MANUAL,
+    &z::MANUAL,
+    &todo::MANUAL,
+    &convert::FROM_JSON_MANUAL,
+    &convert::FROM_CSV_MANUAL,
+    &convert::TO_JSON_MANUAL,
+    &convert::TO_CSV_MANUAL,
+    &query::WHERE_MANUAL,
+    &query::SELECT_MANUAL,
+    &query::SORT_BY_MANUAL,
+    &query::GROUP_BY_MANUAL,
+    &theme::MANUAL,
+    &complete::MANUAL,
 ];
 
-impl<'a> Runnable for Man<'a> {
-    fn run(&mut self) -> Result<(), String> {
-        let manual_detail: HashMap<&str, &str> = [
-            ("echo", "for what"),
-            ("pwd", "for what"),
-            ("cd", "for what"),
-            ("ls", "for what"),
-            ("find", "for what"),
-            ("grep", "for what"),
-            ("cat", "for what"),
-            ("exit", "for what"),
-            ("quit", "for what"),
-            ("man", "for what"),
-        ]
+/// Pages wider than this are hard to read, whatever the terminal.
+const MAX_WIDTH: usize = 80;
+const INDENT: usize = 7;
+
+impl Page {
+    /// The commands this page documents, the first words of its synopsis.
+    pub fn commands(&self) -> impl Iterator<Item = &'static str> {
+        self.spec
+            .usage
+            .iter()
+            .filter_map(|line| line.split_whitespace().next())
+    }
+
+    fn matches(&self, keyword: &str) -> bool {
+        let keyword = keyword.to_lowercase();
+        [self.spec.name, self.spec.summary, self.description]
+            .iter()
+            .any(|text| text.to_lowercase().contains(&keyword))
+    }
+
+    /// The page in the sections of a Unix manual page.
+    pub fn render(&self, width: usize) -> String {
+        let width = width.clamp(40, MAX_WIDTH);
+        let mut out = String::new();
+        section(&mut out, "NAME");
+        let name = self.commands().fold(Vec::new(), |mut names, command| {
+            if !names.contains(&command) {
+                names.push(command);
+            }
+            names
+        });
+        wrap(
+            &mut out,
+            &format!("{} - {}", name.join(", "), self.spec.summary),
+            INDENT,
+            width,
+        );
+
+        section(&mut out, "SYNOPSIS");
+        for line in self.spec.usage {
+            let command = line.split(' ').next().unwrap_or(line);
+            out.push_str(&" ".repeat(INDENT));
+            out.push_str(&format!(
+                "{}{}\n",
+                Role::Keyword.paint(command),
+                &line[command.len()..]
+            ));
+        }
+
+        section(&mut out, "DESCRIPTION");
+        for (idx, paragraph) in self.description.split("\n\n").enumerate() {
+            if idx > 0 {
+                out.push('\n');
+            }
+            wrap(&mut out, paragraph, INDENT, width);
+        }
+
+        if !self.spec.subcommands.is_empty() {
+            section(&mut out, "COMMANDS");
+            for (idx, sub) in self.spec.subcommands.iter().enumerate() {
+                if idx > 0 {
+                    out.push('\n');
+                }
+                let names = sub.names().collect::<Vec<_>>().join(", ");
+                out.push_str(&" ".repeat(INDENT));
+                out.push_str(&format!("{}\n", Role::Keyword.paint(&names)));
+                wrap(&mut out, sub.summary, INDENT * 2, width);
+            }
+        }
+
+        // Subcommands bring their own options, listed after the common ones.
+        let options: Vec<&Opt> = self
+            .spec
+            .options
+            .iter()
+            .chain(self.spec.subcommands.iter().flat_map(|sub| sub.options))
+            .collect();
+        if !options.is_empty() {
+            section(&mut out, "OPTIONS");
+            for (idx, opt) in options.iter().enumerate() {
+                if idx > 0 {
+                    out.push('\n');
+                }
+                out.push_str(&" ".repeat(INDENT));
+                out.push_str(&format!("{}\n", Role::Keyword.paint(&opt.synopsis())));
+                wrap(&mut out, opt.help, INDENT * 2, width);
+            }
+        }
+
+        if !self.examples.is_empty() {
+            section(&mut out, "EXAMPLES");
+            for (idx, (command, text)) in self.examples.iter().enumerate() {
+                if idx > 0 {
+                    out.push('\n');
+                }
+                out.push_str(&" ".repeat(INDENT));
+                out.push_str(&format!("{}\n", Role::Keyword.paint(command)));
+                wrap(&mut out, text, INDENT * 2, width);
+            }
+        }
+
+        if !self.see_also.is_empty() {
+            section(&mut out, "SEE ALSO");
+            wrap(&mut out, &self.see_also.join(", "), INDENT, width);
+        }
+        out
+    }
+}
+
+fn section(out: &mut String, title: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&format!("{}\n", Role::Header.paint(title)));
+}
+
+fn wrap(out: &mut String, text: &str, indent: usize, width: usize) {
+    fill(out, &" ".repeat(indent), text, indent, width);
+}
+
+/// The page documenting `command`.
+pub fn find(command: &str) -> Option<&'static Page> {
+    PAGES
         .iter()
-        .cloned()
-        .collect();
+        .copied()
+        .find(|page| page.commands().any(|name| name == command))
+}
 
-        if self.vars.get_tokens_length() == 1 {
-            println!(
-                "{}",
-                "For more detailed manual for each command, type 'man <command name>'"
-            );
-            println!("");
-            println!("{}", MANUAL);
-            Ok(())
-        } else if self.vars.get_tokens_length() == 2 {
-            if COMMANDS.contains(&self.vars.get_token(1)) {
-                println!("{}", manual_detail[&self.vars.get_token(1)]);
-                Ok(())
-            } else {
-                Err(format!("man: Command {} not found", self.vars.get_token(1)))
-            }
-        } else {
-            Err(
-                "man: too many arguments. Type 'man man' for more detailed information."
-                    .to_string(),
-            )
+pub struct Man<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Man<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut apropos = false;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("apropos") => apropos = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        if apropos {
+            if operands.is_empty() {
+                return Err(Error::usage("man", "missing keyword"));
+            }
+            let found: Vec<&Page> = PAGES
+                .iter()
+                .copied()
+                .filter(|page| operands.iter().any(|keyword| page.matches(keyword)))
+                .collect();
+            if found.is_empty() {
+                return Err(Error::not_found(
+                    "man",
+                    format!("nothing appropriate for {}", operands.join(" ")),
+                ));
+            }
+            for page in found {
+                println!(
+                    "{} - {}",
+                    Role::Keyword.paint(page.spec.name),
+                    page.spec.summary
+                );
+            }
+            return Ok(());
+        }
+
+        match operands.as_slice() {
+            [] => {
+                let mut text = String::from(
+                    "For more detailed manual for each command, type 'man <command name>'\n\n",
+                );
+                for page in PAGES {
+                    text.push_str(&format!("{}: {}\n", page.spec.name, page.spec.summary));
+                }
+                text.push_str(&format!("\n{}\n", NOTES));
+                pager::page_or_print("man", &text)
+                    .map_err(|err| Error::io("man", "standard output", err))
+            }
+            [command] => {
+                let page = find(command).ok_or_else(|| {
+                    Error::not_found("man", format!("no manual entry for {}", command))
+                })?;
+                pager::page_or_print(command, &page.render(terminal_width()))
+                    .map_err(|err| Error::io("man", "standard output", err))
+            }
+            _ => Err(Error::usage("man", "too many arguments")),
         }
     }
 }
@@ -66,3 +327,189 @@ impl<'a> Man<'a> {
         Self { vars: cmd }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binaries::{BinEnum, COMMANDS};
+    use crate::cmd::{spec::Value, BUILTINS};
+
+    #[test]
+    fn every_command_has_a_page() {
+        for command in COMMANDS.iter().chain(BUILTINS.iter()) {
+            assert!(find(command).is_some(), "no manual page for {}", command);
+        }
+    }
+
+    #[test]
+    fn pages_document_registered_commands() {
+        let mut cmd = CMD::new().unwrap();
+        for page in PAGES {
+            assert!(
+                page.commands().any(|command| command == page.spec.name),
+                "the synopsis of {} does not start with it",
+                page.spec.name
+            );
+            for command in page.commands() {
+                if BUILTINS.contains(&command) {
+                    continue;
+                }
+                assert!(
+                    COMMANDS.contains(&command),
+                    "{} documents the unknown command {}",
+                    page.spec.name,
+                    command
+                );
+                assert!(
+                    BinEnum::create(command, &mut cmd).is_ok(),
+                    "{} is listed but not created",
+                    command
+                );
+            }
+        }
+    }
+
+    /// Checks the options of `spec` and of its subcommands.
+    fn check_options(spec: &Spec) {
+        let mut names = Vec::new();
+        for opt in spec.options {
+            assert!(
+                !opt.help.is_empty(),
+                "an option of {} has no help",
+                spec.name
+            );
+            let flags = opt.flags();
+            assert!(!flags.is_empty(), "an option of {} has no name", spec.name);
+            for flag in flags {
+                assert!(
+                    flag != "--help" && flag != "--color",
+                    "{} declares {}, which the parser handles",
+                    spec.name,
+                    flag
+                );
+                assert!(
+                    !names.contains(&flag),
+                    "{} declares {} twice",
+                    spec.name,
+                    flag
+                );
+                names.push(flag);
+            }
+        }
+        for sub in spec.subcommands {
+            check_options(sub);
+        }
+    }
+
+    #[test]
+    fn specs_are_consistent() {
+        for page in PAGES {
+            let spec = page.spec;
+            check_options(spec);
+            if let Some(max) = spec.operands.max {
+                assert!(
+                    max >= spec.operands.min,
+                    "{} needs more operands than it allows",
+                    spec.name
+                );
+            }
+            for (idx, sub) in spec.subcommands.iter().enumerate() {
+                assert!(
+                    sub.usage.iter().all(|line| line.starts_with(spec.name)),
+                    "the usage of {} {} does not start with {}",
+                    spec.name,
+                    sub.name,
+                    spec.name
+                );
+                for name in sub.names() {
+                    assert!(
+                        spec.subcommands[..idx]
+                            .iter()
+                            .all(|other| other.subcommand(name).is_none()),
+                        "{} has two commands called {}",
+                        spec.name,
+                        name
+                    );
+                }
+            }
+        }
+    }
+
+    /// Runs every option of `spec` through the binary of `command`, followed
+    /// by an unknown option so that parsing fails before anything is done.
+    fn run_options(command: &str, words: &str, spec: &Spec) {
+        for opt in spec.options {
+            let value = match opt.value {
+                Value::Required(_) => " 1",
+                _ => "",
+            };
+            for flag in opt.flags() {
+                let line = format!("{}{} {}{} --no-such-option", command, words, flag, value);
+                let mut cmd = CMD::with_line(&line);
+                let Ok(mut binary) = BinEnum::create(command, &mut cmd) else {
+                    // The shell handles its builtins itself.
+                    return;
+                };
+                let Err(err) = binary.run() else {
+                    continue;
+                };
+                let rejected = match flag.strip_prefix("--") {
+                    Some(name) => format!("unrecognized option '--{}'", name),
+                    None => format!("invalid option -- '{}'", &flag[1..]),
+                };
+                assert!(
+                    !err.to_string().contains(&rejected),
+                    "'{}' does not handle {}: {}",
+                    line,
+                    flag,
+                    err
+                );
+            }
+        }
+        for sub in spec.subcommands {
+            run_options(command, &format!("{} {}", words, sub.name), sub);
+        }
+    }
+
+    #[test]
+    fn options_are_handled() {
+        for page in PAGES {
+            // Both parse their words by hand, to print or match them as they are.
+            if matches!(page.spec.name, "echo" | "where") {
+                continue;
+            }
+            run_options(page.spec.name, "", page.spec);
+        }
+    }
+
+    #[test]
+    fn pages_are_unique_and_complete() {
+        for (idx, page) in PAGES.iter().enumerate() {
+            assert!(
+                PAGES[..idx]
+                    .iter()
+                    .all(|other| other.spec.name != page.spec.name),
+                "{} has two pages",
+                page.spec.name
+            );
+            assert!(
+                !page.spec.summary.is_empty(),
+                "{} has no summary",
+                page.spec.name
+            );
+            assert!(
+                !page.description.is_empty(),
+                "{} has no description",
+                page.spec.name
+            );
+            for see in page.see_also {
+                assert!(
+                    find(see).is_some(),
+                    "{} refers to the missing page {}",
+                    page.spec.name,
+                    see
+                );
+            }
+        }
+    }
+}
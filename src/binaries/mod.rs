@@ -1,61 +1,278 @@
-use crate::cmd::CMD;
+use crate::cmd::{error::Error, CMD};
 
-use self::{cat::Cat, cd::Cd, echo::Echo, ls::Ls, man::Man, pwd::Pwd, todo::Todo, touch::Touch};
+use self::{
+    cat::Cat,
+    cd::Cd,
+    chmod::Chmod,
+    chown::Chown,
+    complete::Complete,
+    convert::{FromCsv, FromJson, ToCsv, ToJson},
+    cut::Cut,
+    df::Df,
+    diff::Diff,
+    dirs::{Dirs, Popd, Pushd},
+    du::Du,
+    echo::Echo,
+    head::Head,
+    less::Less,
+    ln::Ln,
+    ls::Ls,
+    man::Man,
+    patch::Patch,
+    printf::Printf,
+    pwd::Pwd,
+    query::{GroupBy, Select, SortBy, Where},
+    readlink::Readlink,
+    sed::Sed,
+    sort::Sort,
+    stat::Stat,
+    tail::Tail,
+    theme::Theme,
+    todo::Todo,
+    touch::Touch,
+    tr::Tr,
+    tree::Tree,
+    uniq::Uniq,
+    wc::Wc,
+    z::Z,
+};
 
 pub mod cat;
 pub mod cd;
+pub mod chmod;
+pub mod chown;
+pub mod complete;
+pub mod convert;
+pub mod cut;
+pub mod df;
+pub mod diff;
+pub mod dirs;
+pub mod du;
 pub mod echo;
+pub mod head;
+pub mod less;
+pub mod ln;
 pub mod ls;
 pub mod man;
+pub mod patch;
+pub mod printf;
 pub mod pwd;
+pub mod query;
+pub mod readlink;
+pub mod sed;
+pub mod sort;
+pub mod stat;
+pub mod tail;
+pub mod theme;
 pub mod todo;
 pub mod touch;
+pub mod tr;
+pub mod tree;
+pub mod uniq;
+pub mod wc;
+pub mod z;
 
 pub trait Runnable {
-    fn run(&mut self) -> Result<(), String>;
+    fn run(&mut self) -> Result<(), Error>;
+}
+
+/// Every command `BinEnum::create` knows, each with a page in `man`.
+pub const COMMANDS: [&str; 42] = [
+    "echo",
+    "printf",
+    "pwd",
+    "ls",
+    "less",
+    "cat",
+    "head",
+    "tail",
+    "cd",
+    "man",
+    "touch",
+    "todo",
+    "tree",
+    "wc",
+    "sort",
+    "uniq",
+    "cut",
+    "tr",
+    "sed",
+    "diff",
+    "patch",
+    "stat",
+    "du",
+    "df",
+    "chmod",
+    "chown",
+    "ln",
+    "readlink",
+    "pushd",
+    "popd",
+    "dirs",
+    "z",
+    "from-json",
+    "from-csv",
+    "to-json",
+    "to-csv",
+    "where",
+    "select",
+    "sort-by",
+    "group-by",
+    "theme",
+    "complete",
+];
+
+/// Commands reading records, for which the previous pipeline stage prints
+/// JSON instead of text.
+pub fn reads_records(command: &str) -> bool {
+    matches!(
+        command,
+        "where" | "select" | "sort-by" | "group-by" | "to-json" | "to-csv"
+    )
 }
 
 pub enum BinEnum<'a> {
     Cat(Cat<'a>),
     Cd(Cd<'a>),
+    Chmod(Chmod<'a>),
+    Chown(Chown<'a>),
+    Complete(Complete<'a>),
+    Cut(Cut<'a>),
+    Df(Df<'a>),
+    Diff(Diff<'a>),
+    Dirs(Dirs<'a>),
+    Du(Du<'a>),
     Echo(Echo<'a>),
+    FromCsv(FromCsv<'a>),
+    FromJson(FromJson<'a>),
+    GroupBy(GroupBy<'a>),
+    Head(Head<'a>),
+    Less(Less<'a>),
+    Ln(Ln<'a>),
     Ls(Ls<'a>),
     Man(Man<'a>),
+    Patch(Patch<'a>),
+    Popd(Popd<'a>),
+    Printf(Printf<'a>),
+    Pushd(Pushd<'a>),
     Pwd(Pwd<'a>),
+    Readlink(Readlink<'a>),
+    Select(Select<'a>),
+    Sed(Sed<'a>),
+    Sort(Sort<'a>),
+    SortBy(SortBy<'a>),
+    Stat(Stat<'a>),
+    Tail(Tail<'a>),
+    Theme(Theme<'a>),
+    ToCsv(ToCsv<'a>),
+    ToJson(ToJson<'a>),
     Touch(Touch<'a>),
     Todo(Todo<'a>),
+    Tr(Tr<'a>),
+    Tree(Tree<'a>),
+    Uniq(Uniq<'a>),
+    Wc(Wc<'a>),
+    Where(Where<'a>),
+    Z(Z<'a>),
 }
 
 impl<'a> BinEnum<'a> {
-    pub fn create(command: &str, vars: &'a mut CMD) -> Result<Self, String> {
+    pub fn create(command: &str, vars: &'a mut CMD) -> Result<Self, Error> {
         match command {
             "echo" => Ok(BinEnum::Echo(Echo::new(vars))),
+            "printf" => Ok(BinEnum::Printf(Printf::new(vars))),
             "pwd" => Ok(BinEnum::Pwd(Pwd::new(vars))),
             "ls" => Ok(BinEnum::Ls(Ls::new(vars))),
+            "less" => Ok(BinEnum::Less(Less::new(vars))),
             "cat" => Ok(BinEnum::Cat(Cat::new(vars))),
+            "head" => Ok(BinEnum::Head(Head::new(vars))),
+            "tail" => Ok(BinEnum::Tail(Tail::new(vars))),
             "cd" => Ok(BinEnum::Cd(Cd::new(vars))),
             "man" => Ok(BinEnum::Man(Man::new(vars))),
             "touch" => Ok(BinEnum::Touch(Touch::new(vars))),
             "todo" => Ok(BinEnum::Todo(Todo::new(vars))),
-            &_ => Err(format!(
-                "Error: Command {} not found, see 'man' for help",
-                command
-            )),
+            "tree" => Ok(BinEnum::Tree(Tree::new(vars))),
+            "wc" => Ok(BinEnum::Wc(Wc::new(vars))),
+            "sort" => Ok(BinEnum::Sort(Sort::new(vars))),
+            "uniq" => Ok(BinEnum::Uniq(Uniq::new(vars))),
+            "cut" => Ok(BinEnum::Cut(Cut::new(vars))),
+            "tr" => Ok(BinEnum::Tr(Tr::new(vars))),
+            "sed" => Ok(BinEnum::Sed(Sed::new(vars))),
+            "diff" => Ok(BinEnum::Diff(Diff::new(vars))),
+            "patch" => Ok(BinEnum::Patch(Patch::new(vars))),
+            "stat" => Ok(BinEnum::Stat(Stat::new(vars))),
+            "du" => Ok(BinEnum::Du(Du::new(vars))),
+            "df" => Ok(BinEnum::Df(Df::new(vars))),
+            "chmod" => Ok(BinEnum::Chmod(Chmod::new(vars))),
+            "chown" => Ok(BinEnum::Chown(Chown::new(vars))),
+            "ln" => Ok(BinEnum::Ln(Ln::new(vars))),
+            "readlink" => Ok(BinEnum::Readlink(Readlink::new(vars))),
+            "pushd" => Ok(BinEnum::Pushd(Pushd::new(vars))),
+            "popd" => Ok(BinEnum::Popd(Popd::new(vars))),
+            "dirs" => Ok(BinEnum::Dirs(Dirs::new(vars))),
+            "z" => Ok(BinEnum::Z(Z::new(vars))),
+            "from-json" => Ok(BinEnum::FromJson(FromJson::new(vars))),
+            "from-csv" => Ok(BinEnum::FromCsv(FromCsv::new(vars))),
+            "to-json" => Ok(BinEnum::ToJson(ToJson::new(vars))),
+            "to-csv" => Ok(BinEnum::ToCsv(ToCsv::new(vars))),
+            "where" => Ok(BinEnum::Where(Where::new(vars))),
+            "select" => Ok(BinEnum::Select(Select::new(vars))),
+            "sort-by" => Ok(BinEnum::SortBy(SortBy::new(vars))),
+            "group-by" => Ok(BinEnum::GroupBy(GroupBy::new(vars))),
+            "theme" => Ok(BinEnum::Theme(Theme::new(vars))),
+            "complete" => Ok(BinEnum::Complete(Complete::new(vars))),
+            &_ => Err(Error::UnknownCommand {
+                name: command.to_string(),
+            }),
         }
     }
 }
 
 impl<'a> Runnable for BinEnum<'a> {
-    fn run(&mut self) -> Result<(), String> {
+    fn run(&mut self) -> Result<(), Error> {
         match self {
             BinEnum::Cat(cat) => cat.run(),
             BinEnum::Cd(cd) => cd.run(),
+            BinEnum::Chmod(chmod) => chmod.run(),
+            BinEnum::Chown(chown) => chown.run(),
+            BinEnum::Complete(complete) => complete.run(),
+            BinEnum::Cut(cut) => cut.run(),
+            BinEnum::Df(df) => df.run(),
+            BinEnum::Diff(diff) => diff.run(),
+            BinEnum::Dirs(dirs) => dirs.run(),
+            BinEnum::Du(du) => du.run(),
             BinEnum::Echo(echo) => echo.run(),
+            BinEnum::FromCsv(from_csv) => from_csv.run(),
+            BinEnum::FromJson(from_json) => from_json.run(),
+            BinEnum::GroupBy(group_by) => group_by.run(),
+            BinEnum::Head(head) => head.run(),
+            BinEnum::Less(less) => less.run(),
+            BinEnum::Ln(ln) => ln.run(),
             BinEnum::Ls(ls) => ls.run(),
             BinEnum::Man(man) => man.run(),
+            BinEnum::Patch(patch) => patch.run(),
+            BinEnum::Popd(popd) => popd.run(),
+            BinEnum::Printf(printf) => printf.run(),
+            BinEnum::Pushd(pushd) => pushd.run(),
             BinEnum::Pwd(pwd) => pwd.run(),
+            BinEnum::Readlink(readlink) => readlink.run(),
+            BinEnum::Select(select) => select.run(),
+            BinEnum::Sed(sed) => sed.run(),
+            BinEnum::Sort(sort) => sort.run(),
+            BinEnum::SortBy(sort_by) => sort_by.run(),
+            BinEnum::Stat(stat) => stat.run(),
+            BinEnum::Tail(tail) => tail.run(),
+            BinEnum::Theme(theme) => theme.run(),
+            BinEnum::ToCsv(to_csv) => to_csv.run(),
+            BinEnum::ToJson(to_json) => to_json.run(),
             BinEnum::Touch(touch) => touch.run(),
             BinEnum::Todo(todo) => todo.run(),
+            BinEnum::Tr(tr) => tr.run(),
+            BinEnum::Tree(tree) => tree.run(),
+            BinEnum::Uniq(uniq) => uniq.run(),
+            BinEnum::Wc(wc) => wc.run(),
+            BinEnum::Where(filter) => filter.run(),
+            BinEnum::Z(z) => z.run(),
         }
     }
 }
@@ -0,0 +1,390 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    color,
+    error::{Error, Failures},
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::diff::unified::{self, FilePatch, PatchHunk};
+use crate::fs::atomic;
+use crate::theme::Role;
+
+use super::{man::Page, Runnable};
+
+const DEV_NULL: &str = "/dev/null";
+
+struct Options {
+    strip: Option<usize>,
+    reverse: bool,
+    dry_run: bool,
+    backup: bool,
+}
+
+/// Outcome of applying the hunks of one file.
+struct Applied {
+    lines: Vec<String>,
+    rejects: Vec<PatchHunk>,
+}
+
+pub const SPEC: Spec = Spec::new("patch", "apply a diff to files")
+    .usage(&["patch [-p strip] [-R] [--dry-run] [-b] [-i patchfile] [file [patchfile]]"])
+    .options(&[
+        Opt::new(
+            "p",
+            &["strip"],
+            "Remove the first strip components of the file names of the patch.",
+        )
+        .value("strip"),
+        Opt::new("R", &["reverse"], "Undo the patch."),
+        Opt::new("", &["dry-run"], "Only report what would happen."),
+        Opt::new(
+            "b",
+            &["backup"],
+            "Keep the original of every file as file.orig.",
+        ),
+        Opt::new("i", &["input"], "Read the patch from patchfile.").value("patchfile"),
+    ])
+    .operands(Operands::at_most(2));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Apply a unified diff read from patchfile or the standard input to the files it \
+                  names, or to file. Hunks are searched for near their line numbers, and failed \
+                  ones are saved to file.rej.",
+    examples: &[(
+        "patch -p1 -i fix.diff",
+        "Apply fix.diff made in the parent directory.",
+    )],
+    see_also: &["diff", "sed"],
+};
+
+pub struct Patch<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Patch<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options {
+            strip: None,
+            reverse: false,
+            dry_run: false,
+            backup: false,
+        };
+        let mut patch_file = None;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("strip") => {
+                    let value = parser.value();
+                    let strip = value.parse().map_err(|_| {
+                        Error::usage("patch", format!("strip count {} is not a number", value))
+                    })?;
+                    options.strip = Some(strip);
+                }
+                Arg::Opt("reverse") => options.reverse = true,
+                Arg::Opt("dry-run") => options.dry_run = true,
+                Arg::Opt("backup") => options.backup = true,
+                Arg::Opt("input") => patch_file = Some(parser.value()),
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        let target = operands.first().copied();
+        if patch_file.is_none() {
+            patch_file = operands.get(1).copied();
+        }
+
+        let text = match patch_file {
+            Some(file) => fs::read(self.vars.resolve_path(file))
+                .map_err(|err| Error::io("patch", file, err))?,
+            None => {
+                if !self.vars.has_piped_input() && io::stdin().is_terminal() {
+                    return Err(Error::usage(
+                        "patch",
+                        "no patch given, pipe a diff into patch or use -i file",
+                    ));
+                }
+                let mut bytes = Vec::new();
+                self.vars
+                    .stdin()
+                    .read_to_end(&mut bytes)
+                    .map_err(|err| Error::io("patch", "standard input", err))?;
+                bytes
+            }
+        };
+        let patches = unified::parse(&String::from_utf8_lossy(&text))
+            .map_err(|err| Error::failed("patch", err))?;
+
+        let mut failures = Failures::default();
+        for patch in &patches {
+            match self.apply_file(&options, patch, target) {
+                Ok(true) => {}
+                // The rejected hunks were reported by `apply_file`.
+                Ok(false) => failures.count(&Error::failed("patch", "hunks were rejected")),
+                Err(err) => failures.report(err),
+            }
+        }
+        failures.finish("patch")
+    }
+}
+
+impl<'a> Patch<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    /// Strips `count` leading components from a name in the patch. Without
+    /// `-p` only the file name is kept, as in GNU patch.
+    fn strip_name(name: &str, strip: Option<usize>) -> Option<String> {
+        if name == DEV_NULL {
+            return None;
+        }
+        match strip {
+            None => Path::new(name)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            Some(count) => {
+                let components: Vec<&str> =
+                    name.split('/').filter(|part| !part.is_empty()).collect();
+                if count >= components.len() {
+                    return None;
+                }
+                let stripped = components[count..].join("/");
+                Some(if count == 0 && name.starts_with('/') {
+                    format!("/{}", stripped)
+                } else {
+                    stripped
+                })
+            }
+        }
+    }
+
+    /// Patches the file `patch` is about, returning whether every hunk applied.
+    fn apply_file(
+        &self,
+        options: &Options,
+        patch: &FilePatch,
+        target: Option<&str>,
+    ) -> Result<bool, Error> {
+        let (old_name, new_name) = if options.reverse {
+            (&patch.new_name, &patch.old_name)
+        } else {
+            (&patch.old_name, &patch.new_name)
+        };
+        let creating = old_name == DEV_NULL;
+        let deleting = new_name == DEV_NULL;
+
+        let name = match target {
+            Some(target) => target.to_string(),
+            None => {
+                let candidates: Vec<String> = [old_name, new_name]
+                    .iter()
+                    .filter_map(|name| Self::strip_name(name, options.strip))
+                    .collect();
+                candidates
+                    .iter()
+                    .find(|name| self.vars.resolve_path(name).exists())
+                    .or(candidates.first())
+                    .cloned()
+                    .ok_or_else(|| {
+                        Error::not_found(
+                            "patch",
+                            format!("can't find file to patch for '{}'", new_name),
+                        )
+                    })?
+            }
+        };
+        let path = self.vars.resolve_path(&name);
+        if creating && path.exists() {
+            return Err(Error::failed(
+                "patch",
+                format!(
+                    "{}: file to be created already exists, the patch may be applied already",
+                    name
+                ),
+            ));
+        }
+
+        let content = match fs::read(&path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound && creating => String::new(),
+            Err(err) => return Err(Error::io("patch", &name, err)),
+        };
+
+        println!(
+            "{} file {}",
+            if options.dry_run {
+                "checking"
+            } else {
+                "patching"
+            },
+            name
+        );
+        let hunks: Vec<PatchHunk> = if options.reverse {
+            patch.hunks.iter().map(PatchHunk::reversed).collect()
+        } else {
+            patch.hunks.clone()
+        };
+        let applied = Self::apply_hunks(&content, &hunks);
+
+        if !applied.rejects.is_empty() {
+            let reject_path = PathBuf::from(format!("{}.rej", path.display()));
+            color::on_stderr(|| {
+                eprintln!(
+                    "{}",
+                    Role::Error.paint(&format!(
+                        "{} out of {} hunk{} FAILED -- {}",
+                        applied.rejects.len(),
+                        hunks.len(),
+                        if hunks.len() == 1 { "" } else { "s" },
+                        if options.dry_run {
+                            "no changes were written".to_string()
+                        } else {
+                            format!("saving rejects to file {}.rej", name)
+                        }
+                    ))
+                )
+            });
+            if !options.dry_run {
+                let mut rejects = format!("--- {}\n+++ {}\n", old_name, new_name);
+                for hunk in &applied.rejects {
+                    rejects.push_str(&hunk.render());
+                }
+                fs::write(&reject_path, rejects)
+                    .map_err(|err| Error::io("patch", format!("{}.rej", name), err))?;
+            }
+        }
+        let complete = applied.rejects.is_empty();
+        if options.dry_run || applied.rejects.len() == hunks.len() {
+            return Ok(complete);
+        }
+
+        self.write_result(options, &path, &name, &applied.lines, deleting)
+            .map_err(|err| Error::io("patch", &name, err))?;
+        Ok(complete)
+    }
+
+    fn write_result(
+        &self,
+        options: &Options,
+        path: &Path,
+        name: &str,
+        lines: &[String],
+        deleting: bool,
+    ) -> io::Result<()> {
+        if options.backup && path.exists() {
+            fs::copy(path, format!("{}.orig", path.display()))?;
+        }
+        if deleting && lines.is_empty() {
+            println!("removing file {}", name);
+            return fs::remove_file(path);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic::replace(path, |out| {
+            for line in lines {
+                out.write_all(line.as_bytes())?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Finds where `wanted` occurs in `lines`, trying `expected` first and
+    /// then positions increasingly far away in both directions.
+    fn locate(lines: &[String], wanted: &[&str], expected: usize) -> Option<usize> {
+        let fits = |start: usize| {
+            start + wanted.len() <= lines.len()
+                && lines[start..start + wanted.len()]
+                    .iter()
+                    .zip(wanted)
+                    .all(|(line, wanted)| line == wanted)
+        };
+        let expected = expected.min(lines.len());
+        for distance in 0..=lines.len() {
+            if fits(expected + distance) {
+                return Some(expected + distance);
+            }
+            if distance > 0 && distance <= expected && fits(expected - distance) {
+                return Some(expected - distance);
+            }
+        }
+        None
+    }
+
+    fn apply_hunks(content: &str, hunks: &[PatchHunk]) -> Applied {
+        let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+        let mut rejects = Vec::new();
+        let mut offset: isize = 0;
+
+        for (number, hunk) in hunks.iter().enumerate() {
+            let number = number + 1;
+            let old_lines = hunk.old_lines();
+            let new_lines = hunk.new_lines();
+            // An empty old side names the line after which text is inserted.
+            let start = if old_lines.is_empty() {
+                hunk.old_start
+            } else {
+                hunk.old_start.saturating_sub(1)
+            };
+            let expected = (start as isize + offset).max(0) as usize;
+
+            match Self::locate(&lines, &old_lines, expected) {
+                Some(position) => {
+                    let drift = position as isize - expected as isize;
+                    if drift != 0 {
+                        println!(
+                            "Hunk #{} succeeded at {} (offset {} line{}).",
+                            number,
+                            position + 1,
+                            drift,
+                            if drift.abs() == 1 { "" } else { "s" }
+                        );
+                    }
+                    let moved = position as isize - start as isize;
+                    lines.splice(
+                        position..position + old_lines.len(),
+                        new_lines.iter().map(|line| line.to_string()),
+                    );
+                    offset = moved + new_lines.len() as isize - old_lines.len() as isize;
+                }
+                None => {
+                    if !new_lines.is_empty() && Self::locate(&lines, &new_lines, expected).is_some()
+                    {
+                        color::on_stderr(|| {
+                            eprintln!(
+                                "{}",
+                                Role::Warning.paint(&format!(
+                                    "Hunk #{} is already applied (or the patch is reversed), \
+                                     skipping",
+                                    number
+                                ))
+                            )
+                        });
+                    } else {
+                        color::on_stderr(|| {
+                            eprintln!(
+                                "{}",
+                                Role::Error.paint(&format!(
+                                    "Hunk #{} FAILED at {}.",
+                                    number,
+                                    expected + 1
+                                ))
+                            )
+                        });
+                    }
+                    rejects.push(hunk.clone());
+                }
+            }
+        }
+        Applied { lines, rejects }
+    }
+}
@@ -0,0 +1,529 @@
+use std::io::{self, Write};
+
+use crate::cmd::{
+    error::{Error, Failures},
+    spec::{Operands, Spec},
+    CMD,
+};
+
+use super::{
+    echo::{read_escape, unescape},
+    man::Page,
+    Runnable,
+};
+
+pub const SPEC: Spec = Spec::new("printf", "format and print data")
+    .usage(&["printf format [argument...]"])
+    .operands(Operands::at_least(1));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the arguments as format says, like the printf of C. The format is \
+                  printed as it is except for escapes and conversions, and used again as long as \
+                  arguments are left. No newline is added.\n\n\
+                  The escapes are those of 'echo -e', with octal values written \\nnn, and \\\" \
+                  for a double quote. A conversion starts with %, followed by flags among - \
+                  (align left), + (always print a sign), a space (print a space instead of +), # \
+                  (alternate form) and 0 (pad with zeros), a width, a precision after a dot, \
+                  either of which may be * to take it from the arguments, and one of:\n\n\
+                  %s prints a string, at most precision characters of it, %b a string with its \
+                  escapes decoded, %c its first character, %d and %i a decimal integer, %o, %u, \
+                  %x and %X an unsigned integer in octal, decimal or hexadecimal, %f and %F a \
+                  decimal number, %e and %E one with an exponent, %g and %G whichever is \
+                  shorter, and %% a percent sign.\n\n\
+                  Integers may be written in hexadecimal with 0x or in octal with a leading 0, \
+                  and 'a stands for the code of a. Missing arguments are empty strings or 0. \
+                  --help is only recognized alone.",
+    examples: &[
+        (
+            "printf '%s\\n' a b c",
+            "Print a, b and c on lines of their own.",
+        ),
+        (
+            "printf '%-10s|%5.1f\\n' width 3.14159",
+            "Print width padded to 10 columns, then 3.1 in 5.",
+        ),
+        ("printf '%x\\n' 255", "Print ff."),
+    ],
+    see_also: &["echo"],
+};
+
+/// The arguments left to convert.
+struct Arguments<'a> {
+    words: &'a [String],
+    pos: usize,
+    /// The arguments that were not numbers, reported already.
+    failures: Failures,
+}
+
+impl<'a> Arguments<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let word = self.words.get(self.pos)?;
+        self.pos += 1;
+        Some(word)
+    }
+
+    fn text(&mut self) -> &'a str {
+        self.next().unwrap_or("")
+    }
+
+    /// Reports `word` as not being a number, which then counts as 0.
+    fn invalid<T: Default>(&mut self, word: &str) -> T {
+        self.failures.report(Error::failed(
+            "printf",
+            format!("'{}': expected a numeric value", word),
+        ));
+        T::default()
+    }
+
+    fn integer(&mut self) -> i128 {
+        let Some(word) = self.next() else {
+            return 0;
+        };
+        if let Some(c) = word
+            .strip_prefix(['\'', '"'])
+            .and_then(|rest| rest.chars().next())
+        {
+            return i128::from(u32::from(c));
+        }
+        let trimmed = word.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let parsed = if let Some(hex) = digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+            i128::from_str_radix(hex, 16)
+        } else if digits.len() > 1 && digits.starts_with('0') {
+            i128::from_str_radix(&digits[1..], 8)
+        } else {
+            digits.parse()
+        };
+        match parsed {
+            Ok(value) if negative => -value,
+            Ok(value) => value,
+            Err(_) => self.invalid(word),
+        }
+    }
+
+    fn float(&mut self) -> f64 {
+        let Some(word) = self.next() else {
+            return 0.0;
+        };
+        if let Some(c) = word
+            .strip_prefix(['\'', '"'])
+            .and_then(|rest| rest.chars().next())
+        {
+            return f64::from(u32::from(c));
+        }
+        match word.trim().parse() {
+            Ok(value) => value,
+            Err(_) => self.invalid(word),
+        }
+    }
+}
+
+/// A `%` conversion of the format.
+#[derive(Default)]
+struct Conversion {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: char,
+}
+
+impl Conversion {
+    /// Reads the conversion at the start of `rest`, the text following a
+    /// `%`, returning it with its length. A `*` takes a number from `args`.
+    fn parse(rest: &str, args: &mut Arguments) -> Result<(Self, usize), Error> {
+        let mut conversion = Self::default();
+        let mut chars = rest.char_indices().peekable();
+        while let Some(&(_, c)) = chars.peek() {
+            match c {
+                '-' => conversion.left = true,
+                '+' => conversion.plus = true,
+                ' ' => conversion.space = true,
+                '#' => conversion.alternate = true,
+                '0' => conversion.zero = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        if chars.next_if(|&(_, c)| c == '*').is_some() {
+            let width = args.integer();
+            conversion.left |= width < 0;
+            conversion.width = usize::try_from(width.unsigned_abs()).unwrap_or(usize::MAX);
+        } else {
+            while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                conversion.width = conversion.width * 10 + c as usize - '0' as usize;
+            }
+        }
+
+        if chars.next_if(|&(_, c)| c == '.').is_some() {
+            conversion.precision = if chars.next_if(|&(_, c)| c == '*').is_some() {
+                // A negative precision counts as none.
+                usize::try_from(args.integer()).ok()
+            } else {
+                let mut precision = 0;
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                    precision = precision * 10 + c as usize - '0' as usize;
+                }
+                Some(precision)
+            };
+        }
+
+        // Length modifiers change nothing, every integer is wide enough.
+        while chars
+            .next_if(|(_, c)| matches!(c, 'h' | 'l' | 'L' | 'q' | 'j' | 'z' | 't'))
+            .is_some()
+        {}
+
+        match chars.next() {
+            Some((idx, kind)) if "sbcdiouxXfFeEgG".contains(kind) => {
+                conversion.kind = kind;
+                Ok((conversion, idx + kind.len_utf8()))
+            }
+            Some((idx, kind)) => Err(Error::usage(
+                "printf",
+                format!(
+                    "'%{}': invalid conversion specification",
+                    &rest[..idx + kind.len_utf8()]
+                ),
+            )),
+            None => Err(Error::usage(
+                "printf",
+                format!("'%{}': missing conversion specifier", rest),
+            )),
+        }
+    }
+
+    /// Pads `text` with spaces to the width.
+    fn pad(&self, text: &[u8], out: &mut Vec<u8>) {
+        let fill = self.width.saturating_sub(text.len());
+        if !self.left {
+            out.resize(out.len() + fill, b' ');
+        }
+        out.extend_from_slice(text);
+        if self.left {
+            out.resize(out.len() + fill, b' ');
+        }
+    }
+
+    /// Pads a number made of `sign`, `prefix` and `digits` to the width,
+    /// with zeros between the prefix and the digits for the `0` flag.
+    fn pad_number(&self, sign: &str, prefix: &str, digits: &str, zeros: bool, out: &mut Vec<u8>) {
+        let length = sign.len() + prefix.len() + digits.len();
+        if zeros && self.zero && !self.left && length < self.width {
+            out.extend_from_slice(sign.as_bytes());
+            out.extend_from_slice(prefix.as_bytes());
+            out.resize(out.len() + self.width - length, b'0');
+            out.extend_from_slice(digits.as_bytes());
+        } else {
+            self.pad(format!("{}{}{}", sign, prefix, digits).as_bytes(), out);
+        }
+    }
+
+    fn sign(&self, negative: bool) -> &'static str {
+        match (negative, self.plus, self.space) {
+            (true, _, _) => "-",
+            (false, true, _) => "+",
+            (false, false, true) => " ",
+            _ => "",
+        }
+    }
+
+    /// Writes the conversion of the next arguments, returning false when
+    /// `\c` in a `%b` argument asked to stop the output.
+    fn write(&self, args: &mut Arguments, out: &mut Vec<u8>) -> bool {
+        match self.kind {
+            's' => {
+                let text = args.text();
+                let text = match self.precision {
+                    Some(precision) => text.chars().take(precision).collect(),
+                    None => text.to_string(),
+                };
+                self.pad(text.as_bytes(), out);
+            }
+            'b' => {
+                let mut text = Vec::new();
+                let complete = unescape(args.text(), &mut text);
+                if let Some(precision) = self.precision {
+                    text.truncate(precision);
+                }
+                self.pad(&text, out);
+                return complete;
+            }
+            'c' => {
+                let text = args.text();
+                let first = text.chars().next().map_or(0, char::len_utf8);
+                self.pad(&text.as_bytes()[..first], out);
+            }
+            'd' | 'i' => {
+                let value = args.integer();
+                let digits = self.digits(value.unsigned_abs().to_string());
+                let zeros = self.precision.is_none();
+                self.pad_number(self.sign(value < 0), "", &digits, zeros, out);
+            }
+            'o' | 'u' | 'x' | 'X' => {
+                // Like C, negative values wrap around to 64 bits.
+                let value = match args.integer() {
+                    value if value < 0 => u128::from(value as i64 as u64),
+                    value => value as u128,
+                };
+                let digits = self.digits(match self.kind {
+                    'o' => format!("{:o}", value),
+                    'x' => format!("{:x}", value),
+                    'X' => format!("{:X}", value),
+                    _ => value.to_string(),
+                });
+                let prefix = match self.kind {
+                    'o' if self.alternate && !digits.starts_with('0') => "0",
+                    'x' if self.alternate && value != 0 => "0x",
+                    'X' if self.alternate && value != 0 => "0X",
+                    _ => "",
+                };
+                let zeros = self.precision.is_none();
+                self.pad_number("", prefix, &digits, zeros, out);
+            }
+            _ => {
+                let value = args.float();
+                let sign = self.sign(value.is_sign_negative() && !value.is_nan());
+                let digits = self.float(value.abs());
+                let digits = match self.kind.is_ascii_uppercase() {
+                    true => digits.to_uppercase(),
+                    false => digits,
+                };
+                self.pad_number(sign, "", &digits, value.is_finite(), out);
+            }
+        }
+        true
+    }
+
+    /// Pads integer digits with zeros to the precision.
+    fn digits(&self, digits: String) -> String {
+        match self.precision {
+            Some(0) if digits == "0" => String::new(),
+            Some(precision) if digits.len() < precision => {
+                format!("{}{}", "0".repeat(precision - digits.len()), digits)
+            }
+            _ => digits,
+        }
+    }
+
+    /// Formats `value`, which is not negative, for `f`, `e` or `g`.
+    fn float(&self, value: f64) -> String {
+        if value.is_nan() {
+            return "nan".to_string();
+        }
+        if value.is_infinite() {
+            return "inf".to_string();
+        }
+        let precision = self.precision.unwrap_or(6);
+        let mut text = match self.kind.to_ascii_lowercase() {
+            'f' => format!("{:.*}", precision, value),
+            'e' => exponent(value, precision),
+            _ => {
+                // %g picks %e when the exponent is below -4 or not below
+                // the precision, and drops trailing zeros.
+                let precision = precision.max(1);
+                let exp = exponent(value, precision - 1);
+                let power: i32 = exp[exp.find('e').unwrap_or(0) + 1..].parse().unwrap_or(0);
+                let mut text = if power < -4 || power >= precision as i32 {
+                    exp
+                } else {
+                    format!("{:.*}", (precision as i32 - 1 - power) as usize, value)
+                };
+                if !self.alternate && text.contains('.') {
+                    let (mantissa, exp) = text.split_at(text.find('e').unwrap_or(text.len()));
+                    text = format!(
+                        "{}{}",
+                        mantissa.trim_end_matches('0').trim_end_matches('.'),
+                        exp
+                    );
+                }
+                text
+            }
+        };
+        if self.alternate && !text.contains('.') {
+            let at = text.find('e').unwrap_or(text.len());
+            text.insert(at, '.');
+        }
+        text
+    }
+}
+
+/// Formats `value` with `precision` digits after the point and an
+/// exponent of at least two digits, as in `1.500000e+02`.
+fn exponent(value: f64, precision: usize) -> String {
+    let text = format!("{:.*e}", precision, value);
+    let (mantissa, exp) = text.split_once('e').unwrap_or((&text, "0"));
+    let exp: i32 = exp.parse().unwrap_or(0);
+    let sign = if exp < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exp.abs())
+}
+
+/// Writes `format` once, converting the next arguments. Returns false when
+/// `\c` asked to stop the output.
+fn write_format(format: &str, args: &mut Arguments, out: &mut Vec<u8>) -> Result<bool, Error> {
+    let mut rest = format;
+    while let Some(idx) = rest.find(['\\', '%']) {
+        out.extend_from_slice(&rest.as_bytes()[..idx]);
+        let after = &rest[idx + 1..];
+        if rest[idx..].starts_with('\\') {
+            let Some(length) = read_escape(after, false, out) else {
+                return Ok(false);
+            };
+            rest = &after[length..];
+        } else if let Some(after) = after.strip_prefix('%') {
+            out.push(b'%');
+            rest = after;
+        } else {
+            let (conversion, length) = Conversion::parse(after, args)?;
+            if !conversion.write(args, out) {
+                return Ok(false);
+            }
+            rest = &after[length..];
+        }
+    }
+    out.extend_from_slice(rest.as_bytes());
+    Ok(true)
+}
+
+/// Writes `format` as many times as it takes to convert every argument.
+fn render(format: &str, args: &mut Arguments) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    // The format is used again while it converts arguments and some are left.
+    loop {
+        let start = args.pos;
+        if !write_format(format, args, &mut out)? {
+            break;
+        }
+        if args.pos == start || args.pos >= args.words.len() {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+pub struct Printf<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Printf<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        // Parsed by hand, since formats and arguments may start with dashes.
+        let words = match self.vars.get_args() {
+            [only] if only == "--help" => return Err(Error::help(SPEC.help(None))),
+            [first, rest @ ..] if first == "--" => rest,
+            words => words,
+        };
+        let Some((format, words)) = words.split_first() else {
+            return Err(Error::usage("printf", "missing operand"));
+        };
+
+        let mut args = Arguments {
+            words,
+            pos: 0,
+            failures: Failures::default(),
+        };
+        let out = render(format, &mut args)?;
+
+        let mut stdout = io::stdout().lock();
+        stdout
+            .write_all(&out)
+            .and_then(|_| stdout.flush())
+            .map_err(|err| Error::io("printf", "standard output", err))?;
+        args.failures.finish("printf")
+    }
+}
+
+impl<'a> Printf<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printf(format: &str, words: &[&str]) -> String {
+        let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+        let mut args = Arguments {
+            words: &words,
+            pos: 0,
+            failures: Failures::default(),
+        };
+        String::from_utf8(render(format, &mut args).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn formats_convert_their_arguments() {
+        let cases: [(&str, &[&str], &str); 14] = [
+            ("%5.2f|", &["3.14159"], " 3.14|"),
+            ("%5.2f|", &["-2"], "-2.00|"),
+            ("%-10s|", &["ab"], "ab        |"),
+            ("%10s|", &["ab"], "        ab|"),
+            ("%.3s", &["abcdef"], "abc"),
+            ("%05d|%x|%#o", &["42", "255", "8"], "00042|ff|010"),
+            ("%*d|%-*d|", &["4", "7", "3", "1"], "   7|1  |"),
+            ("%%%d", &["5"], "%5"),
+            ("%b|%s", &["a\\tb", "a\\tb"], "a\tb|a\\tb"),
+            ("%b|%b", &["\\0101", "\\101"], "A|\\101"),
+            ("%5b|", &["\\x41"], "    A|"),
+            ("%b.%s", &["a\\cb", "c"], "a"),
+            ("%d %s", &[], "0 "),
+            ("%c", &["été"], "é"),
+        ];
+        for (format, words, expected) in cases {
+            assert_eq!(printf(format, words), expected, "format '{}'", format);
+        }
+    }
+
+    #[test]
+    fn formats_are_reused_for_the_arguments_left() {
+        let cases: [(&str, &[&str], &str); 4] = [
+            ("%s\n", &["a", "b", "c"], "a\nb\nc\n"),
+            ("%s=%d;", &["a", "1", "b"], "a=1;b=0;"),
+            ("[%b]", &["x", "y\\c", "z"], "[x][y"),
+            ("none\n", &["a", "b"], "none\n"),
+        ];
+        for (format, words, expected) in cases {
+            assert_eq!(printf(format, words), expected, "format '{}'", format);
+        }
+    }
+
+    #[test]
+    fn formats_decode_their_escapes() {
+        let cases = [
+            ("\\x41\\102\\n", "AB\n"),
+            ("\\x4a\\x4", "J\u{4}"),
+            ("\\0101", "\u{8}1"),
+            ("a\\cb", "a"),
+            ("\\\"\\q\\", "\"\\q\\"),
+        ];
+        for (format, expected) in cases {
+            assert_eq!(printf(format, &[]), expected, "format '{}'", format);
+        }
+    }
+
+    #[test]
+    fn invalid_conversions_are_rejected() {
+        for format in ["%", "%5", "%y", "%-q"] {
+            let mut args = Arguments {
+                words: &[],
+                pos: 0,
+                failures: Failures::default(),
+            };
+            assert!(
+                render(format, &mut args).is_err(),
+                "'{}' was accepted",
+                format
+            );
+        }
+    }
+}
@@ -1,19 +1,60 @@
-use colored::Colorize;
+use serde::Serialize;
 
-use crate::cmd::CMD;
+use crate::cmd::{
+    args::Parser,
+    error::Error,
+    output::Format,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::theme::Role;
 
-use super::Runnable;
+use super::{man::Page, Runnable};
+
+#[derive(Serialize)]
+struct Record<'a> {
+    path: &'a str,
+}
+
+pub const SPEC: Spec = Spec::new("pwd", "print the current directory")
+    .usage(&["pwd [--json | --ndjson]"])
+    .options(&[
+        Opt::new("", &["json"], "Print a record with the path as JSON."),
+        Opt::new(
+            "",
+            &["ndjson"],
+            "Print the record as a single line of JSON.",
+        ),
+    ])
+    .operands(Operands::NONE);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the absolute path of the current directory of the shell.",
+    examples: &[("pwd --json", "Print {\"path\": ...} for scripts.")],
+    see_also: &["cd", "dirs"],
+};
 
 pub struct Pwd<'a> {
     vars: &'a CMD,
 }
 
 impl<'a> Runnable for Pwd<'a> {
-    fn run(&mut self) -> Result<(), String> {
-        println!(
-            "{}",
-            format!("{}", self.vars.get_current_dir_path().display()).cyan()
-        );
+    fn run(&mut self) -> Result<(), Error> {
+        let mut format = self.vars.output_format();
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match Format::from_arg(arg) {
+                Some(chosen) => format = chosen,
+                None => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let path = self.vars.get_current_dir_path().display().to_string();
+        if !format.is_text() {
+            return format.print_one("pwd", &Record { path: &path });
+        }
+        println!("{}", Role::Path.paint(&path));
         Ok(())
     }
 }
@@ -0,0 +1,357 @@
+use std::cmp::Ordering;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    output::Format,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::table::{self, Record};
+
+use super::{man::Page, Runnable};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Matches,
+    NotMatches,
+}
+
+impl Operator {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "==" | "=" => Some(Self::Equal),
+            "!=" => Some(Self::NotEqual),
+            "<" => Some(Self::Less),
+            "<=" => Some(Self::LessOrEqual),
+            ">" => Some(Self::Greater),
+            ">=" => Some(Self::GreaterOrEqual),
+            "=~" => Some(Self::Matches),
+            "!~" => Some(Self::NotMatches),
+            _ => None,
+        }
+    }
+
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Equal => ordering.is_eq(),
+            Self::NotEqual => ordering.is_ne(),
+            Self::Less => ordering.is_lt(),
+            Self::LessOrEqual => ordering.is_le(),
+            Self::Greater => ordering.is_gt(),
+            Self::GreaterOrEqual => ordering.is_ge(),
+            Self::Matches | Self::NotMatches => false,
+        }
+    }
+}
+
+/// `column operator value`, as in `where size > 1kb`.
+struct Condition {
+    column: String,
+    operator: Operator,
+    literal: String,
+    /// Compiled for `=~` and `!~`.
+    pattern: Option<Regex>,
+}
+
+impl Condition {
+    fn parse(words: &[String]) -> Result<Self, Error> {
+        let [column, operator, literal] = words else {
+            return Err(Error::usage(
+                "where",
+                "expected a condition such as 'size > 1kb'",
+            ));
+        };
+        let operator = Operator::parse(operator)
+            .ok_or_else(|| Error::usage("where", format!("unknown operator '{}'", operator)))?;
+        let pattern = match operator {
+            Operator::Matches | Operator::NotMatches => Some(
+                Regex::new(literal)
+                    .map_err(|err| Error::usage("where", format!("invalid regex: {}", err)))?,
+            ),
+            _ => None,
+        };
+        Ok(Self {
+            column: column.clone(),
+            operator,
+            literal: literal.clone(),
+            pattern,
+        })
+    }
+
+    /// Records without the column never match.
+    fn matches(&self, record: &Record) -> Result<bool, Error> {
+        let Some(value) = record.get(&self.column) else {
+            return Ok(false);
+        };
+        if let Some(pattern) = &self.pattern {
+            let found = pattern.is_match(&table::cell_text(value));
+            return Ok(found == (self.operator == Operator::Matches));
+        }
+        let literal = match (value, table::literal_like(value, &self.literal)) {
+            (_, Some(literal)) => literal,
+            (Value::Number(_), None) => {
+                return Err(Error::usage(
+                    "where",
+                    format!("'{}' is not a number or size", self.literal),
+                ))
+            }
+            (Value::Bool(_), None) => {
+                return Err(Error::usage(
+                    "where",
+                    format!("'{}' is not true or false", self.literal),
+                ))
+            }
+            _ => return Ok(self.operator == Operator::NotEqual),
+        };
+        Ok(table::compare_values(value, &literal)
+            .is_some_and(|ordering| self.operator.accepts(ordering)))
+    }
+}
+
+pub const WHERE_SPEC: Spec = Spec::new("where", "keep the records matching a condition")
+    .usage(&["where column operator value"])
+    .options(&[
+        Opt::new("", &["json"], "Print the records as JSON."),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ])
+    .operands(Operands::exactly(3));
+
+pub const WHERE_MANUAL: Page = Page {
+    spec: &WHERE_SPEC,
+    description: "Keep the records of the previous pipeline stage whose column compares to value \
+                  with ==, !=, <, <=, > or >=, or whose column matches the regex value with =~, \
+                  or does not with !~.\n\n\
+                  Values are read like the column: numbers may carry a size unit as in 'size > \
+                  1kb', and a.b reaches into nested records. Records without the column are left \
+                  out.",
+    examples: &[
+        ("ls | where size > 1kb", "Show the files larger than 1 KiB."),
+        ("ls | where name =~ '\\.rs$'", "Show the Rust files."),
+    ],
+    see_also: &["select", "sort-by", "group-by"],
+};
+
+pub struct Where<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Where<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        // Parsed by hand so values such as `-1` are not taken for flags.
+        let mut format = self.vars.output_format();
+        let mut words = Vec::new();
+        for arg in self.vars.get_args() {
+            match arg.as_str() {
+                "--json" => format = Format::Json,
+                "--ndjson" => format = Format::Ndjson,
+                "--help" => return Err(Error::help(WHERE_SPEC.help(None))),
+                _ => words.push(arg.clone()),
+            }
+        }
+        let condition = Condition::parse(&words)?;
+
+        let mut selected = Vec::new();
+        for record in table::read("where", self.vars)? {
+            if condition.matches(&record)? {
+                selected.push(record);
+            }
+        }
+        table::emit("where", format, &selected)
+    }
+}
+
+impl<'a> Where<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+/// Parses the column operands of `select`, `sort-by` and `group-by`.
+/// `flags` handles the command's own options and returns false for unknown ones.
+fn parse_columns<'a>(
+    spec: &'static Spec,
+    vars: &'a CMD,
+    mut flags: impl FnMut(Arg) -> bool,
+) -> Result<(Format, Vec<&'a str>), Error> {
+    let mut format = vars.output_format();
+    let mut columns = Vec::new();
+    let mut parser = Parser::new(spec, vars.get_args());
+    while let Some(arg) = parser.next_arg()? {
+        if let Some(chosen) = Format::from_arg(arg) {
+            format = chosen;
+            continue;
+        }
+        match arg {
+            Arg::Value(column) => columns.push(column),
+            _ if flags(arg) => {}
+            _ => return Err(parser.unexpected(arg)),
+        }
+    }
+    Ok((format, columns))
+}
+
+pub const SELECT_SPEC: Spec = Spec::new("select", "pick columns of records")
+    .usage(&["select column..."])
+    .options(&[
+        Opt::new("", &["json"], "Print the records as JSON."),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ])
+    .operands(Operands::at_least(1));
+
+pub const SELECT_MANUAL: Page = Page {
+    spec: &SELECT_SPEC,
+    description: "Keep only the given columns of every record of the previous pipeline stage, \
+                  in that order.",
+    examples: &[("ls | select name size", "Show only names and sizes.")],
+    see_also: &["where", "cut"],
+};
+
+pub struct Select<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Select<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let (format, columns) = parse_columns(&SELECT_SPEC, self.vars, |_| false)?;
+        let records: Vec<Record> = table::read("select", self.vars)?
+            .iter()
+            .map(|record| {
+                let mut selected = Record::default();
+                for column in columns.iter() {
+                    let value = record.get(column).cloned().unwrap_or(Value::Null);
+                    selected.insert(column, value);
+                }
+                selected
+            })
+            .collect();
+        table::emit("select", format, &records)
+    }
+}
+
+impl<'a> Select<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+pub const SORT_BY_SPEC: Spec = Spec::new("sort-by", "sort records")
+    .usage(&["sort-by [-r] column..."])
+    .options(&[
+        Opt::new("r", &["reverse"], "Sort in decreasing order."),
+        Opt::new("", &["json"], "Print the records as JSON."),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ])
+    .operands(Operands::at_least(1));
+
+pub const SORT_BY_MANUAL: Page = Page {
+    spec: &SORT_BY_SPEC,
+    description: "Sort the records of the previous pipeline stage by the columns, numbers \
+                  numerically and text alphabetically.",
+    examples: &[("ls | sort-by -r size", "Show the largest files first.")],
+    see_also: &["sort", "where"],
+};
+
+pub struct SortBy<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for SortBy<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut reverse = false;
+        let (format, columns) = parse_columns(&SORT_BY_SPEC, self.vars, |arg| match arg {
+            Arg::Opt("reverse") => {
+                reverse = true;
+                true
+            }
+            _ => false,
+        })?;
+
+        let mut records = table::read("sort-by", self.vars)?;
+        records.sort_by(|a, b| {
+            let ordering = columns
+                .iter()
+                .map(|column| table::compare(a.get(column), b.get(column)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal);
+            if reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        table::emit("sort-by", format, &records)
+    }
+}
+
+impl<'a> SortBy<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+pub const GROUP_BY_SPEC: Spec = Spec::new("group-by", "group records by a column")
+    .usage(&["group-by column"])
+    .options(&[
+        Opt::new("", &["json"], "Print the records as JSON."),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ])
+    .operands(Operands::exactly(1));
+
+pub const GROUP_BY_MANUAL: Page = Page {
+    spec: &GROUP_BY_SPEC,
+    description: "Make one record per distinct value of column in the records of the previous \
+                  pipeline stage, with the number of records and the records themselves.",
+    examples: &[("ls | group-by type", "Count files, directories and links.")],
+    see_also: &["uniq", "where"],
+};
+
+pub struct GroupBy<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for GroupBy<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let (format, columns) = parse_columns(&GROUP_BY_SPEC, self.vars, |_| false)?;
+        let column = columns[0];
+
+        // Groups keep the order in which their first record came.
+        let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+        for record in table::read("group-by", self.vars)? {
+            let key = record.get(column).cloned().unwrap_or(Value::Null);
+            let item = serde_json::to_value(&record)
+                .map_err(|err| Error::failed("group-by", err.to_string()))?;
+            match groups.iter_mut().find(|(existing, _)| *existing == key) {
+                Some((_, items)) => items.push(item),
+                None => groups.push((key, vec![item])),
+            }
+        }
+
+        let records: Vec<Record> = groups
+            .into_iter()
+            .map(|(key, items)| {
+                let mut group = Record::default();
+                group.insert(column, key);
+                group.insert("count", Value::from(items.len()));
+                group.insert("items", Value::Array(items));
+                group
+            })
+            .collect();
+        table::emit("group-by", format, &records)
+    }
+}
+
+impl<'a> GroupBy<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
@@ -0,0 +1,103 @@
+use std::fs;
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::fs::path::{canonicalize, Missing};
+
+use super::{man::Page, Runnable};
+
+pub const SPEC: Spec = Spec::new(
+    "readlink",
+    "print resolved symbolic links or canonical file names",
+)
+.usage(&["readlink [-f | -e | -m] [-n] file..."])
+.options(&[
+    Opt::new(
+        "f",
+        &["canonicalize"],
+        "Print the canonical path with every link resolved; all components but the last must \
+             exist.",
+    ),
+    Opt::new(
+        "e",
+        &["canonicalize-existing"],
+        "Like -f, but all components must exist.",
+    ),
+    Opt::new(
+        "m",
+        &["canonicalize-missing"],
+        "Like -f, but no component has to exist.",
+    ),
+    Opt::new(
+        "n",
+        &["no-newline"],
+        "Do not print a newline after the last name.",
+    ),
+])
+.operands(Operands::at_least(1));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the target of the symbolic links.",
+    examples: &[(
+        "readlink -f .",
+        "Print the current directory with links resolved.",
+    )],
+    see_also: &["ln", "pwd"],
+};
+
+pub struct Readlink<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Readlink<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut missing = None;
+        let mut newline = true;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("canonicalize") => missing = Some(Missing::Last),
+                Arg::Opt("canonicalize-existing") => missing = Some(Missing::None),
+                Arg::Opt("canonicalize-missing") => missing = Some(Missing::Any),
+                Arg::Opt("no-newline") => newline = false,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        // Like GNU readlink, -n only applies when there is a single name to print.
+        let newline = newline || operands.len() > 1;
+
+        let mut failures = Failures::default();
+        for operand in operands {
+            let path = self.vars.resolve_path(operand);
+            let result = match missing {
+                Some(missing) => canonicalize(&path, missing),
+                None => fs::read_link(&path),
+            };
+            match result {
+                Ok(resolved) => {
+                    if newline {
+                        println!("{}", resolved.display());
+                    } else {
+                        print!("{}", resolved.display());
+                    }
+                }
+                Err(err) => failures.report(Error::io("readlink", operand, err)),
+            }
+        }
+        failures.finish("readlink")
+    }
+}
+
+impl<'a> Readlink<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::atomic;
+
+use self::script::{Line, Script};
+
+use super::{man::Page, Runnable};
+
+mod script;
+
+/// Reads lines from a sequence of inputs, one line ahead so that `$` can
+/// tell when the current line is the last one.
+struct Lines<'a> {
+    inputs: Vec<(&'a str, Box<dyn BufRead + 'a>)>,
+    next: Option<Line>,
+    /// The inputs that could not be read to the end.
+    failures: Failures,
+}
+
+impl<'a> Lines<'a> {
+    fn new(mut inputs: Vec<(&'a str, Box<dyn BufRead + 'a>)>) -> Self {
+        inputs.reverse();
+        let mut lines = Self {
+            inputs,
+            next: None,
+            failures: Failures::default(),
+        };
+        lines.next = lines.read();
+        lines
+    }
+
+    fn read(&mut self) -> Option<Line> {
+        let mut buffer = Vec::new();
+        while let Some((name, input)) = self.inputs.last_mut() {
+            buffer.clear();
+            match input.read_until(b'\n', &mut buffer) {
+                Ok(0) => {}
+                Ok(_) => {
+                    let newline = buffer.ends_with(b"\n");
+                    if newline {
+                        buffer.pop();
+                    }
+                    return Some(Line {
+                        text: String::from_utf8_lossy(&buffer).into_owned(),
+                        newline,
+                    });
+                }
+                Err(err) => {
+                    self.failures.report(Error::io("sed", *name, err));
+                }
+            }
+            self.inputs.pop();
+        }
+        None
+    }
+
+    fn next_line(&mut self) -> Option<(Line, bool)> {
+        let line = self.next.take()?;
+        self.next = self.read();
+        Some((line, self.next.is_none()))
+    }
+}
+
+pub const SPEC: Spec = Spec::new("sed", "stream editor for filtering and transforming text")
+    .usage(&["sed [-n] [-E] [-i[suffix]] [-e script]... [script] [file...]"])
+    .options(&[
+        Opt::new(
+            "n",
+            &["quiet", "silent"],
+            "Print only what p commands and flags print.",
+        ),
+        Opt::new(
+            "Er",
+            &["regexp-extended"],
+            "Use extended regexes, where ( ) { } + ? and | need no backslash.",
+        ),
+        Opt::new("e", &["expression"], "Add script to the commands to run.").value("script"),
+        Opt::new(
+            "i",
+            &["in-place"],
+            "Rewrite the files in place, keeping a backup with the suffix appended when one is \
+             given.",
+        )
+        .optional_value("suffix"),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Run the script on every line of the files, or of the standard input, and print \
+                  the result. The script is made of commands separated by ';' or newlines: \
+                  's/regex/replacement/flags' substitutes, with the flags g, i, p and a number, \
+                  'd' deletes the line, 'p' prints it and 'q' quits.\n\n\
+                  Every command may be limited to an address such as 3, $ or /regex/, or to a \
+                  range such as 2,/end/.",
+    examples: &[
+        ("sed -n 5,10p notes.txt", "Print lines 5 to 10."),
+        (
+            "sed -i.bak 's/foo/bar/g' a.txt",
+            "Replace every foo in a.txt, keeping a.txt.bak.",
+        ),
+    ],
+    see_also: &["tr", "patch"],
+};
+
+pub struct Sed<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Sed<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut quiet = false;
+        let mut extended = false;
+        let mut in_place: Option<&str> = None;
+        let mut expressions = Vec::new();
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("quiet") => quiet = true,
+                Arg::Opt("regexp-extended") => extended = true,
+                Arg::Opt("expression") => expressions.push(parser.value()),
+                Arg::Opt("in-place") => in_place = Some(parser.optional_value().unwrap_or("")),
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        if expressions.is_empty() {
+            if operands.is_empty() {
+                return Err(Error::usage("sed", "no script specified"));
+            }
+            expressions.push(operands.remove(0));
+        }
+        let mut script = Script::parse(&expressions.join("\n"), extended, quiet)?;
+
+        let mut failures = Failures::default();
+        match in_place {
+            Some(suffix) => {
+                if operands.is_empty() {
+                    return Err(Error::usage("sed", "no input files"));
+                }
+                for operand in operands {
+                    script.reset();
+                    match self.edit_in_place(&mut script, operand, suffix) {
+                        Ok(true) => {}
+                        Ok(false) => break,
+                        Err(err) => failures.report(Error::io("sed", operand, err)),
+                    }
+                }
+            }
+            None => {
+                if operands.is_empty() {
+                    operands.push("-");
+                }
+                let mut inputs = Vec::new();
+                for operand in operands {
+                    match self.vars.open_input(operand) {
+                        Ok(input) => inputs.push((operand, input)),
+                        Err(err) => failures.report(Error::io("sed", operand, err)),
+                    }
+                }
+                let mut out = BufWriter::new(io::stdout().lock());
+                let mut lines = Lines::new(inputs);
+                let _ = Self::process(&mut script, &mut lines, &mut out).and_then(|_| out.flush());
+                failures.merge(lines.failures);
+            }
+        }
+        failures.finish("sed")
+    }
+}
+
+impl<'a> Sed<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    /// Returns `false` when the script quit before the end of the input.
+    fn process(script: &mut Script, lines: &mut Lines, out: &mut impl Write) -> io::Result<bool> {
+        while let Some((line, is_last)) = lines.next_line() {
+            if !script.execute(line, is_last, out)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Edits through a temporary file renamed over the original, so an
+    /// interrupted edit never leaves a truncated file behind.
+    fn edit_in_place(&self, script: &mut Script, operand: &str, suffix: &str) -> io::Result<bool> {
+        let path = self.vars.resolve_path(operand);
+        if !fs::metadata(&path)?.is_file() {
+            return Err(io::Error::other("couldn't edit: not a regular file"));
+        }
+        let input = BufReader::new(File::open(&path)?);
+
+        if !suffix.is_empty() {
+            let backup = Self::backup_path(&path, suffix);
+            let _ = fs::remove_file(&backup);
+            if fs::hard_link(&path, &backup).is_err() {
+                fs::copy(&path, &backup)?;
+            }
+        }
+        atomic::replace(&path, |out| {
+            Self::process(
+                script,
+                &mut Lines::new(vec![(operand, Box::new(input))]),
+                out,
+            )
+        })
+    }
+
+    /// The backup name appends the suffix, unless it contains `*`, which is
+    /// replaced by the file name as in GNU sed (`-i 'bak/*'`).
+    fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let dir = path.parent().unwrap_or(Path::new("."));
+        if suffix.contains('*') {
+            dir.join(suffix.replace('*', &name))
+        } else {
+            dir.join(format!("{}{}", name, suffix))
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use std::io::{self, Write};
+
+use regex::{Captures, Regex};
+
+use crate::cmd::error::Error;
+
+/// A line of input together with whether it was terminated by a newline, so
+/// a missing newline at the end of a file is preserved.
+pub(crate) struct Line {
+    pub text: String,
+    pub newline: bool,
+}
+
+enum Address {
+    Line(usize),
+    Last,
+    Pattern(Regex),
+}
+
+impl Address {
+    fn matches(&self, line_number: usize, is_last: bool, pattern_space: &str) -> bool {
+        match self {
+            Address::Line(number) => line_number == *number,
+            Address::Last => is_last,
+            Address::Pattern(regex) => regex.is_match(pattern_space),
+        }
+    }
+}
+
+enum Part {
+    Literal(String),
+    Group(usize),
+}
+
+struct Substitution {
+    regex: Regex,
+    replacement: Vec<Part>,
+    global: bool,
+    occurrence: usize,
+    print: bool,
+}
+
+impl Substitution {
+    /// Replaces the selected matches and tells whether anything was replaced.
+    fn apply(&self, pattern_space: &mut String) -> bool {
+        let mut result = String::new();
+        let mut last_end = 0;
+        let mut replaced = false;
+        for (idx, captures) in self.regex.captures_iter(pattern_space).enumerate() {
+            let count = idx + 1;
+            if count < self.occurrence {
+                continue;
+            }
+            if count > self.occurrence && !self.global {
+                break;
+            }
+            let whole = captures.get(0).expect("group 0 always matches");
+            result.push_str(&pattern_space[last_end..whole.start()]);
+            self.expand(&captures, &mut result);
+            last_end = whole.end();
+            replaced = true;
+        }
+        if replaced {
+            result.push_str(&pattern_space[last_end..]);
+            *pattern_space = result;
+        }
+        replaced
+    }
+
+    fn expand(&self, captures: &Captures, out: &mut String) {
+        for part in &self.replacement {
+            match part {
+                Part::Literal(text) => out.push_str(text),
+                Part::Group(group) => {
+                    if let Some(matched) = captures.get(*group) {
+                        out.push_str(matched.as_str());
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum Action {
+    Substitute(Substitution),
+    Delete,
+    Print,
+    Quit,
+}
+
+struct Command {
+    start: Option<Address>,
+    end: Option<Address>,
+    negated: bool,
+    action: Action,
+    in_range: bool,
+}
+
+impl Command {
+    fn selects(&mut self, line_number: usize, is_last: bool, pattern_space: &str) -> bool {
+        let selected = match (&self.start, &self.end) {
+            (None, _) => true,
+            (Some(start), None) => start.matches(line_number, is_last, pattern_space),
+            (Some(start), Some(end)) => {
+                if self.in_range {
+                    let done = match end {
+                        Address::Line(number) => line_number >= *number,
+                        _ => end.matches(line_number, is_last, pattern_space),
+                    };
+                    self.in_range = !done;
+                    true
+                } else if start.matches(line_number, is_last, pattern_space) {
+                    // The end address is only checked from the next line on,
+                    // except that a line number already passed closes the range.
+                    self.in_range = match end {
+                        Address::Line(number) => *number > line_number,
+                        Address::Last => !is_last,
+                        Address::Pattern(_) => true,
+                    };
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+        selected != self.negated
+    }
+}
+
+/// A parsed sed script, run over one stream of lines at a time.
+pub(crate) struct Script {
+    commands: Vec<Command>,
+    quiet: bool,
+    line_number: usize,
+}
+
+impl Script {
+    pub fn parse(text: &str, extended: bool, quiet: bool) -> Result<Self, Error> {
+        let mut parser = ScriptParser {
+            chars: text.chars().collect(),
+            pos: 0,
+            extended,
+        };
+        let mut commands = Vec::new();
+        while let Some(command) = parser.command()? {
+            commands.push(command);
+        }
+        Ok(Self {
+            commands,
+            quiet,
+            line_number: 0,
+        })
+    }
+
+    /// Starts numbering lines and matching ranges from scratch, as each file
+    /// edited in place is a stream of its own.
+    pub fn reset(&mut self) {
+        self.line_number = 0;
+        for command in &mut self.commands {
+            command.in_range = false;
+        }
+    }
+
+    /// Runs the script over one line. Returns `false` once `q` was executed.
+    pub fn execute(&mut self, line: Line, is_last: bool, out: &mut impl Write) -> io::Result<bool> {
+        self.line_number += 1;
+        let mut pattern_space = line.text;
+        let mut deleted = false;
+        let mut running = true;
+        for command in &mut self.commands {
+            if !command.selects(self.line_number, is_last, &pattern_space) {
+                continue;
+            }
+            match &command.action {
+                Action::Substitute(substitution) => {
+                    if substitution.apply(&mut pattern_space) && substitution.print {
+                        writeln!(out, "{}", pattern_space)?;
+                    }
+                }
+                Action::Delete => {
+                    deleted = true;
+                    break;
+                }
+                Action::Print => writeln!(out, "{}", pattern_space)?,
+                Action::Quit => {
+                    running = false;
+                    break;
+                }
+            }
+        }
+        if !deleted && !self.quiet {
+            out.write_all(pattern_space.as_bytes())?;
+            if line.newline {
+                out.write_all(b"\n")?;
+            }
+        }
+        Ok(running)
+    }
+}
+
+struct ScriptParser {
+    chars: Vec<char>,
+    pos: usize,
+    extended: bool,
+}
+
+impl ScriptParser {
+    fn error(&self, message: &str) -> Error {
+        Error::usage(
+            "sed",
+            format!("-e expression #1, char {}: {}", self.pos, message),
+        )
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_blanks(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn command(&mut self) -> Result<Option<Command>, Error> {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | ';')) {
+            self.pos += 1;
+        }
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+
+        let start = self.address()?;
+        let mut end = None;
+        self.skip_blanks();
+        if start.is_some() && self.peek() == Some(',') {
+            self.pos += 1;
+            self.skip_blanks();
+            end = self.address()?;
+            if end.is_none() {
+                return Err(self.error("unexpected ','"));
+            }
+        }
+        self.skip_blanks();
+        let mut negated = false;
+        while self.peek() == Some('!') {
+            negated = true;
+            self.pos += 1;
+            self.skip_blanks();
+        }
+
+        let Some(name) = self.peek() else {
+            return Err(self.error("missing command"));
+        };
+        self.pos += 1;
+        let action = match name {
+            's' => Action::Substitute(self.substitution()?),
+            'd' => Action::Delete,
+            'p' => Action::Print,
+            'q' => Action::Quit,
+            _ => return Err(self.error(&format!("unknown command: '{}'", name))),
+        };
+
+        self.skip_blanks();
+        match self.peek() {
+            None | Some(';' | '\n') => {}
+            Some(_) => return Err(self.error("extra characters after command")),
+        }
+        Ok(Some(Command {
+            start,
+            end,
+            negated,
+            action,
+            in_range: false,
+        }))
+    }
+
+    fn address(&mut self) -> Result<Option<Address>, Error> {
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() => {
+                let mut number = 0usize;
+                while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+                    number = number * 10 + digit as usize;
+                    self.pos += 1;
+                }
+                if number == 0 {
+                    return Err(self.error("invalid usage of line address 0"));
+                }
+                Ok(Some(Address::Line(number)))
+            }
+            Some('$') => {
+                self.pos += 1;
+                Ok(Some(Address::Last))
+            }
+            Some('/') => {
+                self.pos += 1;
+                let pattern = self.delimited('/', "unterminated address regex")?;
+                Ok(Some(Address::Pattern(self.regex(&pattern, false)?)))
+            }
+            Some('\\') => {
+                self.pos += 1;
+                let Some(delimiter) = self.peek() else {
+                    return Err(self.error("unexpected end of expression"));
+                };
+                self.pos += 1;
+                let pattern = self.delimited(delimiter, "unterminated address regex")?;
+                Ok(Some(Address::Pattern(self.regex(&pattern, false)?)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads up to the next unescaped `delimiter`; `\<delimiter>` stands for
+    /// the delimiter itself and every other escape is kept for later.
+    fn delimited(&mut self, delimiter: char, unterminated: &str) -> Result<String, Error> {
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error(unterminated)),
+                Some(c) if c == delimiter => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c) if c == delimiter => text.push(c),
+                        Some('n') if delimiter != 'n' => text.push('\n'),
+                        Some(c) => {
+                            text.push('\\');
+                            text.push(c);
+                        }
+                        None => return Err(self.error(unterminated)),
+                    }
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn substitution(&mut self) -> Result<Substitution, Error> {
+        let unterminated = "unterminated 's' command";
+        let delimiter = match self.peek() {
+            Some(c) if c != '\n' && c != '\\' => c,
+            _ => return Err(self.error(unterminated)),
+        };
+        self.pos += 1;
+        let pattern = self.delimited(delimiter, unterminated)?;
+        let replacement = self.delimited(delimiter, unterminated)?;
+
+        let mut global = false;
+        let mut ignore_case = false;
+        let mut print = false;
+        let mut occurrence = None;
+        while let Some(flag) = self.peek() {
+            match flag {
+                'g' => global = true,
+                'i' | 'I' => ignore_case = true,
+                'p' => print = true,
+                '0'..='9' => {
+                    let mut number = 0usize;
+                    while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+                        number = number * 10 + digit as usize;
+                        self.pos += 1;
+                    }
+                    if number == 0 {
+                        return Err(self.error("number option to 's' command may not be zero"));
+                    }
+                    if occurrence.replace(number).is_some() {
+                        return Err(self.error("multiple number options to 's' command"));
+                    }
+                    continue;
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        Ok(Substitution {
+            regex: self.regex(&pattern, ignore_case)?,
+            replacement: Self::replacement(&replacement),
+            global,
+            occurrence: occurrence.unwrap_or(1),
+            print,
+        })
+    }
+
+    fn replacement(text: &str) -> Vec<Part> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            let group = match c {
+                '&' => Some(0),
+                '\\' => match chars.next() {
+                    Some(digit @ '0'..='9') => digit.to_digit(10).map(|d| d as usize),
+                    Some('t') => {
+                        literal.push('\t');
+                        None
+                    }
+                    Some(other) => {
+                        literal.push(other);
+                        None
+                    }
+                    None => {
+                        literal.push('\\');
+                        None
+                    }
+                },
+                _ => {
+                    literal.push(c);
+                    None
+                }
+            };
+            if let Some(group) = group {
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(Part::Group(group));
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        parts
+    }
+
+    fn regex(&self, pattern: &str, ignore_case: bool) -> Result<Regex, Error> {
+        let mut translated = if self.extended {
+            pattern.to_string()
+        } else {
+            translate_basic(pattern)
+        };
+        if ignore_case {
+            translated.insert_str(0, "(?i)");
+        }
+        Regex::new(&translated).map_err(|err| {
+            let message = err.to_string();
+            let summary = message.lines().last().unwrap_or(&message);
+            self.error(summary.trim_start_matches("error: "))
+        })
+    }
+}
+
+/// Converts a POSIX basic regular expression to the syntax of the `regex`
+/// crate: `\(`, `\{`, `\+`, `\?` and `\|` are operators, their bare forms are
+/// literals, and a leading `*` matches itself.
+fn translate_basic(pattern: &str) -> String {
+    let mut out = String::new();
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        match c {
+            '\\' if idx + 1 < chars.len() => {
+                let next = chars[idx + 1];
+                match next {
+                    '(' | ')' | '{' | '}' | '+' | '?' | '|' => out.push(next),
+                    _ => {
+                        out.push('\\');
+                        out.push(next);
+                    }
+                }
+                idx += 2;
+                continue;
+            }
+            '(' | ')' | '{' | '}' | '+' | '?' | '|' => {
+                out.push('\\');
+                out.push(c);
+            }
+            // A `*` with nothing to repeat is literal in basic expressions.
+            '*' if out.is_empty()
+                || out == "^"
+                || (out.ends_with('(') && !out.ends_with("\\(")) =>
+            {
+                out.push_str("\\*")
+            }
+            '[' => {
+                // Bracket expressions are copied as they are; a `]` right after
+                // the opening bracket (or `[^`) is a member, not the end.
+                let start = idx;
+                idx += 1;
+                if chars.get(idx) == Some(&'^') {
+                    idx += 1;
+                }
+                if chars.get(idx) == Some(&']') {
+                    idx += 1;
+                }
+                while idx < chars.len() && chars[idx] != ']' {
+                    if chars[idx] == '[' && matches!(chars.get(idx + 1), Some(':' | '.' | '=')) {
+                        let kind = chars[idx + 1];
+                        idx += 2;
+                        while idx + 1 < chars.len()
+                            && !(chars[idx] == kind && chars[idx + 1] == ']')
+                        {
+                            idx += 1;
+                        }
+                        idx += 1;
+                    }
+                    idx += 1;
+                }
+                let end = (idx + 1).min(chars.len());
+                out.extend(&chars[start..end]);
+                idx = end;
+                continue;
+            }
+            _ => out.push(c),
+        }
+        idx += 1;
+    }
+    out
+}
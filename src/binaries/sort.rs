@@ -0,0 +1,479 @@
+use std::{
+    cmp::Ordering,
+    io::{self, BufRead, BufWriter, Write},
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    spec::{Opt, Spec},
+    CMD,
+};
+
+use super::{man::Page, Runnable};
+
+#[derive(Default, Clone, Copy)]
+struct Flags {
+    numeric: bool,
+    reverse: bool,
+    fold_case: bool,
+}
+
+impl Flags {
+    fn any(&self) -> bool {
+        self.numeric || self.reverse || self.fold_case
+    }
+
+    /// Applies an option letter that is valid both globally and in a key.
+    fn set(&mut self, letter: char) -> bool {
+        match letter {
+            'n' => self.numeric = true,
+            'r' => self.reverse = true,
+            'f' => self.fold_case = true,
+            // Leading blanks are never part of a field here.
+            'b' => {}
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// A `-k` key definition: `start[.char][opts][,end[.char][opts]]`, 1-based.
+struct Key {
+    start_field: usize,
+    start_char: usize,
+    end_field: Option<usize>,
+    end_char: usize,
+    flags: Flags,
+}
+
+impl Key {
+    fn parse(spec: &str) -> Result<Self, Error> {
+        let invalid = || Error::usage("sort", format!("invalid key specification '{}'", spec));
+        let (start, end) = match spec.split_once(',') {
+            Some((start, end)) => (start, Some(end)),
+            None => (spec, None),
+        };
+
+        let mut flags = Flags::default();
+        let mut position = |text: &str| -> Result<(usize, usize), Error> {
+            let digits_end = text
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(text.len());
+            for letter in text[digits_end..].chars() {
+                if !flags.set(letter) {
+                    return Err(invalid());
+                }
+            }
+            let (field, char) = match text[..digits_end].split_once('.') {
+                Some((field, char)) => (field, char.parse().map_err(|_| invalid())?),
+                None => (&text[..digits_end], 0),
+            };
+            match field.parse() {
+                Ok(field) if field > 0 => Ok((field, char)),
+                _ => Err(invalid()),
+            }
+        };
+
+        let (start_field, start_char) = position(start)?;
+        let (end_field, end_char) = match end {
+            Some(end) => {
+                let (field, char) = position(end)?;
+                (Some(field), char)
+            }
+            None => (None, 0),
+        };
+        Ok(Self {
+            start_field,
+            start_char: start_char.max(1),
+            end_field,
+            end_char,
+            flags,
+        })
+    }
+
+    fn extract<'l>(&self, line: &'l str, separator: Option<char>) -> &'l str {
+        let fields = field_spans(line, separator);
+        let Some(&(field_start, field_end)) = fields.get(self.start_field - 1) else {
+            return "";
+        };
+        let start = advance(line, field_start, field_end, self.start_char - 1);
+
+        let end = match self.end_field {
+            None => line.len(),
+            Some(field) => match fields.get(field - 1) {
+                None => line.len(),
+                Some(&(_, end)) if self.end_char == 0 => end,
+                Some(&(begin, end)) => advance(line, begin, end, self.end_char),
+            },
+        };
+        if start >= end {
+            ""
+        } else {
+            &line[start..end]
+        }
+    }
+}
+
+/// Byte ranges of the fields of a line. Without a separator fields are runs of
+/// non-blank characters.
+fn field_spans(line: &str, separator: Option<char>) -> Vec<(usize, usize)> {
+    match separator {
+        Some(separator) => {
+            let mut spans = Vec::new();
+            let mut start = 0;
+            for (idx, c) in line.char_indices() {
+                if c == separator {
+                    spans.push((start, idx));
+                    start = idx + c.len_utf8();
+                }
+            }
+            spans.push((start, line.len()));
+            spans
+        }
+        None => {
+            let mut spans = Vec::new();
+            let mut start = None;
+            for (idx, c) in line.char_indices() {
+                match (c.is_whitespace(), start) {
+                    (true, Some(begin)) => {
+                        spans.push((begin, idx));
+                        start = None;
+                    }
+                    (false, None) => start = Some(idx),
+                    _ => {}
+                }
+            }
+            if let Some(begin) = start {
+                spans.push((begin, line.len()));
+            }
+            spans
+        }
+    }
+}
+
+/// Byte offset `chars` characters into the field, clamped to its end.
+fn advance(line: &str, start: usize, end: usize, chars: usize) -> usize {
+    line[start..end]
+        .char_indices()
+        .nth(chars)
+        .map(|(idx, _)| start + idx)
+        .unwrap_or(end)
+}
+
+/// Value of the leading number of a string; anything else counts as zero.
+fn numeric_value(text: &str) -> f64 {
+    let text = text.trim_start();
+    let mut end = 0;
+    let mut seen_dot = false;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '-' | '+' if idx == 0 => {}
+            '.' if !seen_dot => seen_dot = true,
+            c if c.is_ascii_digit() => {}
+            _ => break,
+        }
+        end = idx + c.len_utf8();
+    }
+    text[..end].parse().unwrap_or(0.0)
+}
+
+fn compare(a: &str, b: &str, flags: Flags) -> Ordering {
+    let ordering = if flags.numeric {
+        numeric_value(a)
+            .partial_cmp(&numeric_value(b))
+            .unwrap_or(Ordering::Equal)
+    } else if flags.fold_case {
+        a.chars()
+            .flat_map(char::to_lowercase)
+            .cmp(b.chars().flat_map(char::to_lowercase))
+    } else {
+        a.cmp(b)
+    };
+    if flags.reverse {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+struct Options {
+    flags: Flags,
+    keys: Vec<Key>,
+    separator: Option<char>,
+    unique: bool,
+    stable: bool,
+}
+
+impl Options {
+    fn compare_keys(&self, a: &str, b: &str) -> Ordering {
+        if self.keys.is_empty() {
+            return compare(a, b, self.flags);
+        }
+        self.keys
+            .iter()
+            .map(|key| {
+                let flags = if key.flags.any() {
+                    key.flags
+                } else {
+                    self.flags
+                };
+                compare(
+                    key.extract(a, self.separator),
+                    key.extract(b, self.separator),
+                    flags,
+                )
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// Lines with equal keys are ordered by their whole content, unless a
+    /// stable or unique sort was asked for.
+    fn compare_lines(&self, a: &str, b: &str) -> Ordering {
+        let ordering = self.compare_keys(a, b);
+        if ordering.is_ne() || self.stable || self.unique {
+            return ordering;
+        }
+        if self.flags.reverse {
+            b.cmp(a)
+        } else {
+            a.cmp(b)
+        }
+    }
+}
+
+pub const SPEC: Spec = Spec::new("sort", "sort lines of text")
+    .usage(&["sort [-nrfbus] [-t sep] [-k field[,field][nrfb]]... [file...]"])
+    .options(&[
+        Opt::new("n", &["numeric-sort"], "Compare numbers by their value."),
+        Opt::new("r", &["reverse"], "Reverse the result of comparisons."),
+        Opt::new(
+            "f",
+            &["ignore-case"],
+            "Fold lowercase letters to uppercase ones.",
+        ),
+        Opt::new("b", &["ignore-leading-blanks"], "Ignore leading blanks."),
+        Opt::new("u", &["unique"], "Print only the first of equal lines."),
+        Opt::new("s", &["stable"], "Keep the input order of equal lines."),
+        Opt::new("t", &["field-separator"], "Separate fields with sep.").value("sep"),
+        Opt::new(
+            "k",
+            &["key"],
+            "Sort on the fields from the first to the second one.",
+        )
+        .value("field[,field]"),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the lines of the files, or of the standard input, sorted. Keys given \
+                  with -k compare fields separated by blanks, or by the separator of -t, and may \
+                  carry their own n, r, f and b flags.",
+    examples: &[
+        (
+            "sort -k5n data.txt",
+            "Sort data.txt by the number in its fifth field.",
+        ),
+        (
+            "sort -t , -k2 users.csv",
+            "Sort comma separated lines by their second field.",
+        ),
+    ],
+    see_also: &["uniq", "sort-by"],
+};
+
+pub struct Sort<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Sort<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options {
+            flags: Flags::default(),
+            keys: Vec::new(),
+            separator: None,
+            unique: false,
+            stable: false,
+        };
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("numeric-sort") => options.flags.numeric = true,
+                Arg::Opt("reverse") => options.flags.reverse = true,
+                Arg::Opt("ignore-case") => options.flags.fold_case = true,
+                // Leading blanks are never part of a field here.
+                Arg::Opt("ignore-leading-blanks") => {}
+                Arg::Opt("unique") => options.unique = true,
+                Arg::Opt("stable") => options.stable = true,
+                Arg::Opt("key") => options.keys.push(Key::parse(parser.value())?),
+                Arg::Opt("field-separator") => {
+                    let value = parser.value();
+                    let mut chars = value.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(separator), None) => options.separator = Some(separator),
+                        _ => {
+                            return Err(Error::usage(
+                                "sort",
+                                format!("multi-character tab '{}'", value),
+                            ))
+                        }
+                    }
+                }
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if operands.is_empty() {
+            operands.push("-");
+        }
+
+        let mut lines = Vec::new();
+        for operand in operands {
+            let result = self.vars.open_input(operand).and_then(|mut input| {
+                let mut line = Vec::new();
+                while input.read_until(b'\n', &mut line)? > 0 {
+                    if line.ends_with(b"\n") {
+                        line.pop();
+                    }
+                    lines.push(String::from_utf8_lossy(&line).into_owned());
+                    line.clear();
+                }
+                Ok(())
+            });
+            if let Err(err) = result {
+                return Err(Error::io("sort", operand, err));
+            }
+        }
+
+        // `sort_by` is stable, so equal lines keep their input order.
+        lines.sort_by(|a, b| options.compare_lines(a, b));
+        if options.unique {
+            lines.dedup_by(|b, a| options.compare_keys(a, b).is_eq());
+        }
+
+        let mut out = BufWriter::new(io::stdout().lock());
+        for line in lines {
+            if writeln!(out, "{}", line).is_err() {
+                break;
+            }
+        }
+        let _ = out.flush();
+        Ok(())
+    }
+}
+
+impl<'a> Sort<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(keys: &[&str], separator: Option<char>, flags: Flags) -> Options {
+        Options {
+            flags,
+            keys: keys.iter().map(|key| Key::parse(key).unwrap()).collect(),
+            separator,
+            unique: false,
+            stable: false,
+        }
+    }
+
+    #[test]
+    fn keys_extract_their_fields() {
+        let cases = [
+            ("2", None, "a  b c", "b c"),
+            ("2,2", None, "a  b c", "b"),
+            ("1.2", None, "abc def", "bc def"),
+            ("1.2,1.3", None, "abcd ef", "bc"),
+            ("2", Some(':'), "a:b:c", "b:c"),
+            ("2,2", Some(':'), "a::c", ""),
+            ("4", Some(':'), "a:b", ""),
+        ];
+        for (spec, separator, line, expected) in cases {
+            let key = Key::parse(spec).unwrap();
+            assert_eq!(
+                key.extract(line, separator),
+                expected,
+                "key {} of '{}'",
+                spec,
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn keys_compare_with_their_flags() {
+        let numeric = Flags {
+            numeric: true,
+            ..Flags::default()
+        };
+        let cases = [
+            (
+                options(&[], None, Flags::default()),
+                "10",
+                "9",
+                Ordering::Less,
+            ),
+            (options(&[], None, numeric), "10", "9", Ordering::Greater),
+            (
+                options(&["2"], None, Flags::default()),
+                "a 10",
+                "b 9",
+                Ordering::Less,
+            ),
+            (
+                options(&["2n"], None, Flags::default()),
+                "a 10",
+                "b 9",
+                Ordering::Greater,
+            ),
+            (
+                options(&["2"], None, numeric),
+                "a 10",
+                "b 9",
+                Ordering::Greater,
+            ),
+            (
+                options(&["2,2n"], Some(':'), Flags::default()),
+                "x:2:b",
+                "x:10:a",
+                Ordering::Less,
+            ),
+            (
+                options(&["2nr"], Some(':'), Flags::default()),
+                "x:2",
+                "x:10",
+                Ordering::Greater,
+            ),
+            (
+                options(&["1f"], None, Flags::default()),
+                "Apple",
+                "apple z",
+                Ordering::Less,
+            ),
+            (
+                options(&["1,1", "2n"], None, Flags::default()),
+                "a 9",
+                "a 10",
+                Ordering::Less,
+            ),
+        ];
+        for (options, a, b, expected) in cases {
+            assert_eq!(options.compare_keys(a, b), expected, "'{}' and '{}'", a, b);
+        }
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        for spec in ["", "0", "a", "1x", "1.a", "1,0", "2,x"] {
+            assert!(Key::parse(spec).is_err(), "'{}' was accepted", spec);
+        }
+    }
+}
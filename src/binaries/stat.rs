@@ -0,0 +1,307 @@
+use std::{
+    fs::{self, Metadata},
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    output::Format,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::fs::{
+    format::{full_time, mode_string},
+    record::{timestamp, FileRecord},
+    users::Users,
+};
+
+use super::{man::Page, Runnable};
+
+fn file_type(metadata: &Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        "symbolic link"
+    } else if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_char_device() {
+        "character special file"
+    } else if file_type.is_block_device() {
+        "block special file"
+    } else if metadata.len() == 0 {
+        "regular empty file"
+    } else {
+        "regular file"
+    }
+}
+
+/// The change time has no `SystemTime` accessor, so it is rebuilt from the raw fields.
+fn change_time(metadata: &Metadata) -> SystemTime {
+    let nanos = Duration::from_nanos(metadata.ctime_nsec() as u64);
+    match u64::try_from(metadata.ctime()) {
+        Ok(secs) => UNIX_EPOCH + Duration::from_secs(secs) + nanos,
+        Err(_) => UNIX_EPOCH - Duration::from_secs(metadata.ctime().unsigned_abs()) + nanos,
+    }
+}
+
+/// One file as described by `stat`.
+struct Described<'a> {
+    operand: &'a str,
+    metadata: Metadata,
+    link_target: Option<String>,
+}
+
+/// What `stat --json` prints: the fields of `ls --json` and the rest of `stat`.
+#[derive(Serialize)]
+struct StatRecord {
+    #[serde(flatten)]
+    file: FileRecord,
+    uid: u32,
+    gid: u32,
+    inode: u64,
+    links: u64,
+    blocks: u64,
+    io_block: u64,
+    device: u64,
+    atime: Option<String>,
+    ctime: String,
+    birth: Option<String>,
+}
+
+impl<'a> Described<'a> {
+    fn record(&self, path: &Path, users: &Users) -> StatRecord {
+        let metadata = &self.metadata;
+        StatRecord {
+            file: FileRecord::new(self.operand, self.operand, path, metadata, users),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            inode: metadata.ino(),
+            links: metadata.nlink(),
+            blocks: metadata.blocks(),
+            io_block: metadata.blksize(),
+            device: metadata.dev(),
+            atime: metadata.accessed().ok().map(timestamp),
+            ctime: timestamp(change_time(metadata)),
+            birth: metadata.created().ok().map(timestamp),
+        }
+    }
+
+    fn quoted_name(&self) -> String {
+        match &self.link_target {
+            Some(target) => format!("'{}' -> '{}'", self.operand, target),
+            None => format!("'{}'", self.operand),
+        }
+    }
+
+    fn default_format(&self, users: &Users) -> String {
+        let metadata = &self.metadata;
+        let birth = match metadata.created() {
+            Ok(time) => full_time(time),
+            Err(_) => "-".to_string(),
+        };
+        let name = match &self.link_target {
+            Some(target) => format!("{} -> {}", self.operand, target),
+            None => self.operand.to_string(),
+        };
+        format!(
+            "  File: {}\
+             \n  Size: {:<15} Blocks: {:<10} IO Block: {:<6} {}\
+             \nDevice: {:x}h/{}d\tInode: {:<11} Links: {}\
+             \nAccess: ({:04o}/{})  Uid: ({:>5}/{:>8})   Gid: ({:>5}/{:>8})\
+             \nAccess: {}\
+             \nModify: {}\
+             \nChange: {}\
+             \n Birth: {}",
+            name,
+            metadata.len(),
+            metadata.blocks(),
+            metadata.blksize(),
+            file_type(metadata),
+            metadata.dev(),
+            metadata.dev(),
+            metadata.ino(),
+            metadata.nlink(),
+            metadata.mode() & 0o7777,
+            mode_string(metadata.mode()),
+            metadata.uid(),
+            users.user_name(metadata.uid()),
+            metadata.gid(),
+            users.group_name(metadata.gid()),
+            metadata.accessed().map(full_time).unwrap_or_default(),
+            metadata.modified().map(full_time).unwrap_or_default(),
+            full_time(change_time(metadata)),
+            birth
+        )
+    }
+
+    fn custom_format(&self, format: &str, users: &Users) -> String {
+        let metadata = &self.metadata;
+        let epoch = |time: SystemTime| match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs().to_string(),
+            Err(err) => format!("-{}", err.duration().as_secs()),
+        };
+
+        let mut out = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            let Some(directive) = chars.next() else {
+                out.push('%');
+                break;
+            };
+            let value = match directive {
+                'n' => self.operand.to_string(),
+                'N' => self.quoted_name(),
+                's' => metadata.len().to_string(),
+                'b' => metadata.blocks().to_string(),
+                'B' => "512".to_string(),
+                'i' => metadata.ino().to_string(),
+                'h' => metadata.nlink().to_string(),
+                'a' => format!("{:o}", metadata.mode() & 0o7777),
+                'A' => mode_string(metadata.mode()),
+                'F' => file_type(metadata).to_string(),
+                'u' => metadata.uid().to_string(),
+                'U' => users.user_name(metadata.uid()),
+                'g' => metadata.gid().to_string(),
+                'G' => users.group_name(metadata.gid()),
+                'd' => metadata.dev().to_string(),
+                'x' => metadata.accessed().map(full_time).unwrap_or_default(),
+                'y' => metadata.modified().map(full_time).unwrap_or_default(),
+                'z' => full_time(change_time(metadata)),
+                'X' => metadata.accessed().map(epoch).unwrap_or_default(),
+                'Y' => metadata.modified().map(epoch).unwrap_or_default(),
+                'Z' => epoch(change_time(metadata)),
+                '%' => "%".to_string(),
+                other => format!("?{}", other),
+            };
+            out.push_str(&value);
+        }
+        out
+    }
+}
+
+pub const SPEC: Spec = Spec::new("stat", "show file status")
+    .usage(&["stat [-L] [-c format] [--json | --ndjson] file..."])
+    .options(&[
+        Opt::new("L", &["dereference"], "Follow symlinks."),
+        Opt::new("c", &["format"], "Print format for every file instead.").value("format"),
+        Opt::new("", &["json"], "Print records with every field as JSON."),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ])
+    .operands(Operands::at_least(1));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Show the size, blocks, inode, mode, owner and times of the files.\n\n\
+                  The format of -c may use %n name, %s size, %b blocks, %i inode, %h links, %a \
+                  and %A mode, %F type, %u and %U owner, %g and %G group, %x, %y and %z access, \
+                  modify and change time, and %X, %Y and %Z for the times as seconds.",
+    examples: &[(
+        "stat -c '%n %s' *.rs",
+        "Print the name and size of the Rust files.",
+    )],
+    see_also: &["ls", "du"],
+};
+
+pub struct Stat<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Stat<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut dereference = false;
+        let mut format = None;
+        let mut explicit = None;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            if let Some(chosen) = Format::from_arg(arg) {
+                explicit = Some(chosen);
+                continue;
+            }
+            match arg {
+                Arg::Opt("dereference") => dereference = true,
+                Arg::Opt("format") => format = Some(parser.value()),
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if format.is_some() && explicit.is_some() {
+            return Err(Error::usage(
+                "stat",
+                "--format cannot be combined with --json or --ndjson",
+            ));
+        }
+        // An explicit -c overrides the records of CLI_OUTPUT_FORMAT or a pipeline.
+        let output = match (format, explicit) {
+            (Some(_), _) => Format::Text,
+            (None, Some(chosen)) => chosen,
+            (None, None) => self.vars.output_format(),
+        };
+
+        let users = Users::load();
+        let mut failures = Failures::default();
+        let mut records = Vec::new();
+        for operand in operands {
+            let path = self.vars.resolve_path(operand);
+            let described = match Self::describe(operand, &path, dereference) {
+                Ok(described) => described,
+                Err(err) => {
+                    failures.report(Error::io("stat", operand, err));
+                    continue;
+                }
+            };
+            match format {
+                _ if !output.is_text() => records.push(described.record(&path, &users)),
+                Some(format) => println!("{}", described.custom_format(format, &users)),
+                None => println!("{}", described.default_format(&users)),
+            }
+        }
+        if !output.is_text() {
+            output.print("stat", &records)?;
+        }
+        failures.finish("stat")
+    }
+}
+
+impl<'a> Stat<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    fn describe<'o>(
+        operand: &'o str,
+        path: &Path,
+        dereference: bool,
+    ) -> std::io::Result<Described<'o>> {
+        let metadata = if dereference {
+            fs::metadata(path)?
+        } else {
+            fs::symlink_metadata(path)?
+        };
+        let link_target = if metadata.file_type().is_symlink() {
+            fs::read_link(path)
+                .ok()
+                .map(|target| target.display().to_string())
+        } else {
+            None
+        };
+        Ok(Described {
+            operand,
+            metadata,
+            link_target,
+        })
+    }
+}
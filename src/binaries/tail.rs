@@ -0,0 +1,391 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    os::unix::fs::MetadataExt,
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    color,
+    error::{Error, Failures},
+    interrupt::Interrupt,
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::format::parse_size;
+use crate::theme::Role;
+
+use super::{man::Page, Runnable};
+
+const DEFAULT_LINES: u64 = 10;
+const CHUNK_SIZE: usize = 8192;
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What to print: the last `n` lines/bytes, or everything from line/byte `n` on (`+n`).
+#[derive(Clone, Copy)]
+enum Count {
+    LastLines(u64),
+    LastBytes(u64),
+    FromLine(u64),
+    FromByte(u64),
+}
+
+/// A file watched by `tail -f`.
+struct Followed {
+    name: String,
+    path: PathBuf,
+    file: File,
+    inode: u64,
+    position: u64,
+}
+
+pub const SPEC: Spec = Spec::new("tail", "output the last part of files")
+    .usage(&["tail [-n [+]lines] [-c [+]bytes] [-f] [-s seconds] [-q] [-v] [file...]"])
+    .options(&[
+        Opt::new(
+            "n",
+            &["lines"],
+            "Print the last lines lines, or from line lines on with a leading '+'.",
+        )
+        .value("[+]lines"),
+        Opt::new(
+            "c",
+            &["bytes"],
+            "Print the last bytes bytes, or from byte bytes on with a leading '+'.",
+        )
+        .value("[+]bytes"),
+        Opt::new(
+            "fF",
+            &["follow"],
+            "Keep printing data appended to the files until Ctrl-C, reopening files that are \
+             replaced.",
+        ),
+        Opt::new(
+            "s",
+            &["sleep-interval"],
+            "Check the files every seconds seconds while following.",
+        )
+        .value("seconds"),
+        Opt::new("q", &["quiet"], "Never print headers."),
+        Opt::new("v", &["verbose"], "Always print headers."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the last 10 lines of each file, or of the standard input. With several \
+                  files each one is preceded by a header with its name.",
+    examples: &[(
+        "tail -f app.log",
+        "Print the end of app.log and what gets appended.",
+    )],
+    see_also: &["head", "less"],
+};
+
+pub struct Tail<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Tail<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut count = Count::LastLines(DEFAULT_LINES);
+        let mut follow = false;
+        let mut interval = DEFAULT_INTERVAL;
+        let mut headers: Option<bool> = None;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("lines") => {
+                    count = match Self::parse_count(parser.value())? {
+                        (true, n) => Count::FromLine(n),
+                        (false, n) => Count::LastLines(n),
+                    }
+                }
+                Arg::Opt("bytes") => {
+                    count = match Self::parse_count(parser.value())? {
+                        (true, n) => Count::FromByte(n),
+                        (false, n) => Count::LastBytes(n),
+                    }
+                }
+                Arg::Opt("follow") => follow = true,
+                Arg::Opt("sleep-interval") => {
+                    let value = parser.value();
+                    match value.parse::<f64>() {
+                        Ok(seconds) if seconds > 0.0 => interval = Duration::from_secs_f64(seconds),
+                        _ => {
+                            return Err(Error::usage(
+                                "tail",
+                                format!("invalid sleep interval: '{}'", value),
+                            ))
+                        }
+                    }
+                }
+                Arg::Opt("quiet") => headers = Some(false),
+                Arg::Opt("verbose") => headers = Some(true),
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if operands.is_empty() {
+            operands.push("-");
+        }
+        let show_headers = headers.unwrap_or(operands.len() > 1);
+
+        let mut out = io::stdout().lock();
+        let mut followed = Vec::new();
+        let mut failures = Failures::default();
+        for (idx, operand) in operands.iter().enumerate() {
+            if show_headers {
+                let separator = if idx == 0 { "" } else { "\n" };
+                let name = if *operand == "-" {
+                    "standard input"
+                } else {
+                    operand
+                };
+                let _ = writeln!(out, "{}==> {} <==", separator, name);
+            }
+
+            if *operand == "-" {
+                if let Err(err) = Self::tail_stream(self.vars.stdin(), count, &mut out) {
+                    failures.report(Error::io("tail", "standard input", err));
+                }
+                continue;
+            }
+
+            let mut path = self.vars.get_current_dir_path().clone();
+            path.push(operand);
+            let result = File::open(&path).and_then(|mut file| {
+                let position = Self::tail_file(&mut file, count, &mut out)?;
+                let inode = file.metadata()?.ino();
+                Ok(Followed {
+                    name: operand.to_string(),
+                    path,
+                    file,
+                    inode,
+                    position,
+                })
+            });
+            match result {
+                Ok(state) => followed.push(state),
+                Err(err) => failures.report(Error::io("tail", *operand, err)),
+            }
+        }
+        out.flush()
+            .map_err(|err| Error::io("tail", "standard output", err))?;
+
+        if follow && !followed.is_empty() {
+            Self::follow(&mut followed, interval, show_headers);
+        }
+        failures.finish("tail")
+    }
+}
+
+impl<'a> Tail<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    /// Returns whether the count was given as `+n`, and `n`.
+    fn parse_count(value: &str) -> Result<(bool, u64), Error> {
+        let (from_start, number) = match value.strip_prefix('+') {
+            Some(number) => (true, number),
+            None => (false, value.strip_prefix('-').unwrap_or(value)),
+        };
+        match parse_size(number) {
+            Some(n) => Ok((from_start, n)),
+            None => Err(Error::usage("tail", format!("invalid number: '{}'", value))),
+        }
+    }
+
+    /// Prints the requested part of a regular file by seeking from the end,
+    /// without reading what comes before it. Returns the offset printed up to.
+    fn tail_file(file: &mut File, count: Count, out: &mut impl Write) -> io::Result<u64> {
+        let len = file.metadata()?.len();
+        let start = match count {
+            Count::LastBytes(bytes) => len.saturating_sub(bytes),
+            Count::FromByte(byte) => byte.saturating_sub(1).min(len),
+            Count::LastLines(lines) => Self::last_lines_offset(file, len, lines)?,
+            Count::FromLine(line) => {
+                file.seek(SeekFrom::Start(0))?;
+                let mut reader = BufReader::new(&mut *file);
+                let mut offset = 0;
+                let mut buf = Vec::new();
+                for _ in 1..line {
+                    buf.clear();
+                    let read = reader.read_until(b'\n', &mut buf)?;
+                    if read == 0 {
+                        break;
+                    }
+                    offset += read as u64;
+                }
+                offset
+            }
+        };
+
+        file.seek(SeekFrom::Start(start))?;
+        let copied = io::copy(&mut (&mut *file).take(len - start), out)?;
+        Ok(start + copied)
+    }
+
+    /// Scans backwards in chunks until `lines` line breaks have been seen.
+    /// A newline terminating the file does not start another line.
+    fn last_lines_offset(file: &mut File, len: u64, lines: u64) -> io::Result<u64> {
+        if lines == 0 {
+            return Ok(len);
+        }
+
+        let mut buf = vec![0; CHUNK_SIZE];
+        let mut position = len;
+        let mut seen = 0;
+        while position > 0 {
+            let size = (CHUNK_SIZE as u64).min(position) as usize;
+            position -= size as u64;
+            file.seek(SeekFrom::Start(position))?;
+            file.read_exact(&mut buf[..size])?;
+
+            for idx in (0..size).rev() {
+                let offset = position + idx as u64;
+                if buf[idx] != b'\n' || offset == len - 1 {
+                    continue;
+                }
+                seen += 1;
+                if seen == lines {
+                    return Ok(offset + 1);
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    /// Streams that cannot seek only keep the last lines or bytes in memory.
+    fn tail_stream(input: impl Read, count: Count, out: &mut impl Write) -> io::Result<()> {
+        let mut reader = BufReader::new(input);
+        match count {
+            Count::LastLines(lines) => {
+                let mut kept: VecDeque<Vec<u8>> = VecDeque::new();
+                loop {
+                    let mut line = Vec::new();
+                    if reader.read_until(b'\n', &mut line)? == 0 {
+                        break;
+                    }
+                    kept.push_back(line);
+                    if kept.len() as u64 > lines {
+                        kept.pop_front();
+                    }
+                }
+                for line in kept {
+                    out.write_all(&line)?;
+                }
+            }
+            Count::LastBytes(bytes) => {
+                let mut kept: VecDeque<u8> = VecDeque::new();
+                let mut buf = [0; CHUNK_SIZE];
+                loop {
+                    let read = reader.read(&mut buf)?;
+                    if read == 0 {
+                        break;
+                    }
+                    kept.extend(&buf[..read]);
+                    while kept.len() as u64 > bytes {
+                        kept.pop_front();
+                    }
+                }
+                out.write_all(&kept.into_iter().collect::<Vec<u8>>())?;
+            }
+            Count::FromLine(line) => {
+                let mut buf = Vec::new();
+                for _ in 1..line {
+                    buf.clear();
+                    if reader.read_until(b'\n', &mut buf)? == 0 {
+                        return Ok(());
+                    }
+                }
+                io::copy(&mut reader, out)?;
+            }
+            Count::FromByte(byte) => {
+                io::copy(
+                    &mut (&mut reader).take(byte.saturating_sub(1)),
+                    &mut io::sink(),
+                )?;
+                io::copy(&mut reader, out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Polls the files for appended data until Ctrl-C. A file that shrank is
+    /// treated as truncated and reread from the start; a file replaced under
+    /// the same name (log rotation) is reopened.
+    fn follow(followed: &mut [Followed], interval: Duration, show_headers: bool) {
+        let interrupt = Interrupt::catch();
+        let mut last_printed: Option<usize> = None;
+
+        while !interrupt.triggered() {
+            for (idx, state) in followed.iter_mut().enumerate() {
+                let Ok(metadata) = fs::metadata(&state.path) else {
+                    // The file may be missing for a moment while being rotated.
+                    continue;
+                };
+
+                if metadata.ino() != state.inode {
+                    match File::open(&state.path) {
+                        Ok(file) => {
+                            color::on_stderr(|| {
+                                eprintln!(
+                                    "{}",
+                                    Role::Warning.paint(&format!(
+                                        "tail: '{}' has been replaced; following new file",
+                                        state.name
+                                    ))
+                                )
+                            });
+                            state.file = file;
+                            state.inode = metadata.ino();
+                            state.position = 0;
+                        }
+                        Err(_) => continue,
+                    }
+                } else if metadata.len() < state.position {
+                    color::on_stderr(|| {
+                        eprintln!(
+                            "{}",
+                            Role::Warning.paint(&format!("tail: {}: file truncated", state.name))
+                        )
+                    });
+                    state.position = 0;
+                }
+
+                if metadata.len() <= state.position {
+                    continue;
+                }
+
+                let mut out = io::stdout().lock();
+                if show_headers && last_printed != Some(idx) {
+                    let _ = writeln!(out, "\n==> {} <==", state.name);
+                }
+                last_printed = Some(idx);
+
+                let copied = state
+                    .file
+                    .seek(SeekFrom::Start(state.position))
+                    .and_then(|_| {
+                        io::copy(
+                            &mut (&mut state.file).take(metadata.len() - state.position),
+                            &mut out,
+                        )
+                    });
+                if let Ok(copied) = copied {
+                    state.position += copied;
+                }
+                let _ = out.flush();
+            }
+            thread::sleep(interval);
+        }
+        println!();
+    }
+}
@@ -0,0 +1,105 @@
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::theme::{self, Role, Theme as ThemeFile, PRESETS};
+
+use super::{man::Page, Runnable};
+
+pub const SPEC: Spec = Spec::new("theme", "show or switch the colors of the output")
+    .usage(&["theme [-l] [-p] [name | file]"])
+    .options(&[
+        Opt::new(
+            "l",
+            &["list"],
+            "List the themes, the current one marked with *.",
+        ),
+        Opt::new(
+            "p",
+            &["print"],
+            "Print the current theme in the file format.",
+        ),
+    ])
+    .operands(Operands::at_most(1));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Switch to the theme called name, one of the presets dark, light and \
+                  high-contrast or a name.theme file in $XDG_CONFIG_HOME/cli-util/themes, or \
+                  read it from file. Without operands show the current theme and the style of \
+                  every role.\n\n\
+                  Theme files have lines such as 'dir = bold blue' or 'prompt-cwd = black \
+                  on_#87afd7', mapping a role to a foreground color, a background color with on_ \
+                  and attributes. The syntax-* roles color the files cat highlights. Roles left \
+                  out keep their dark style. Set CLI_THEME to pick a theme when the shell \
+                  starts.\n\n\
+                  In a pipeline theme runs on a copy of the shell like every stage, so \
+                  'theme light | cat' leaves the theme as it was.",
+    examples: &[
+        ("theme light", "Switch to the light theme."),
+        (
+            "theme -p",
+            "Print every role of the current theme, a start for a theme of your own.",
+        ),
+    ],
+    see_also: &["ls", "cat"],
+};
+
+pub struct Theme<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Theme<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut list = false;
+        let mut print = false;
+        let mut names = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("list") => list = true,
+                Arg::Opt("print") => print = true,
+                Arg::Value(name) => names.push(name),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        if list {
+            let current = theme::current().name.clone();
+            let presets = PRESETS.iter().map(|(name, _)| name.to_string());
+            for name in presets.chain(theme::installed()) {
+                let marker = if name == current { '*' } else { ' ' };
+                println!("{} {}", marker, name);
+            }
+            return Ok(());
+        }
+
+        if let Some(name) = names.first() {
+            let loaded = ThemeFile::load(name, self.vars.get_current_dir_path())
+                .map_err(|err| Error::failed("theme", err))?;
+            theme::set(loaded);
+        }
+
+        let current = theme::current().clone();
+        if print {
+            print!("{}", current.to_file());
+        } else if names.is_empty() {
+            println!("{}", current.name);
+            let width = Role::ALL.iter().map(|role| role.name().len()).max();
+            for role in Role::ALL {
+                let name = format!("{:<1$}", role.name(), width.unwrap_or_default());
+                println!("  {}  {}", role.paint(&name), current.style(role));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Theme<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
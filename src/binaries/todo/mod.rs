@@ -1,11 +1,17 @@
-use crate::cmd::CMD;
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    output::Format,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+use crate::theme::Role;
 use rusqlite::Connection;
+use serde::Serialize;
 
-use colored::Colorize;
+use super::{man::Page, Runnable};
 
-use super::Runnable;
-
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 struct Activity {
     id: u64,
     name: String,
@@ -15,12 +21,62 @@ struct Activity {
 const TABLE_NAME: &str = "Activity";
 const DB_PATH: &str = "./database.db";
 
+const ADD_SPEC: Spec = Spec::new("add", "add a task per operand")
+    .aliases(&["insert"])
+    .usage(&["todo add task..."])
+    .operands(Operands::at_least(1));
+
+const LIST_SPEC: Spec = Spec::new("list", "print the tasks with their ids")
+    .aliases(&["get"])
+    .usage(&["todo list [--json | --ndjson]"])
+    .options(&[
+        Opt::new("", &["json"], "Print the tasks as JSON records."),
+        Opt::new("", &["ndjson"], "Print one record per line of JSON."),
+    ])
+    .operands(Operands::NONE);
+
+const DO_SPEC: Spec = Spec::new("do", "mark tasks as done")
+    .usage(&["todo do id..."])
+    .operands(Operands::at_least(1));
+
+const UNDO_SPEC: Spec = Spec::new("undo", "mark tasks as not done")
+    .usage(&["todo undo id..."])
+    .operands(Operands::at_least(1));
+
+const REMOVE_SPEC: Spec = Spec::new("remove", "delete tasks")
+    .aliases(&["erase", "delete"])
+    .usage(&["todo remove id..."])
+    .operands(Operands::at_least(1));
+
+pub const SPEC: Spec = Spec::new("todo", "keep a list of tasks")
+    .usage(&[
+        "todo add task...",
+        "todo list [--json | --ndjson]",
+        "todo do id...",
+        "todo undo id...",
+        "todo remove id...",
+    ])
+    .subcommands(&[ADD_SPEC, LIST_SPEC, DO_SPEC, UNDO_SPEC, REMOVE_SPEC]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Keep tasks in the database.db file of the current directory. 'add' (or \
+                  'insert') adds a task per operand, 'list' (or 'get') prints them with their \
+                  ids, done ones struck through, 'do' and 'undo' mark tasks as done or not done \
+                  and 'remove' (or 'erase' or 'delete') deletes them.",
+    examples: &[
+        ("todo add 'buy milk' 'call Sam'", "Add two tasks."),
+        ("todo list | where is_done == false", "Show the tasks left."),
+    ],
+    see_also: &["where"],
+};
+
 pub struct Todo<'a> {
     vars: &'a mut CMD,
 }
 
 impl<'a> Todo<'a> {
-    fn create_table_if_not_exists(conn: &Connection, table_name: &str) -> Result<(), String> {
+    fn create_table_if_not_exists(conn: &Connection, table_name: &str) -> Result<(), Error> {
         if let Err(err) = conn.execute(
             &format!(
                 "CREATE TABLE IF NOT EXISTS {} (
@@ -32,17 +88,15 @@ impl<'a> Todo<'a> {
             ),
             (),
         ) {
-            return Err(err.to_string());
+            return Err(Error::database("todo", err));
         }
         Ok(())
     }
 
-    fn insert(&self, activity: Vec<Activity>) -> Result<(), String> {
+    fn insert(&self, activity: Vec<Activity>) -> Result<(), Error> {
         match Connection::open(DB_PATH) {
             Ok(conn) => {
-                if let Err(err) = Self::create_table_if_not_exists(&conn, TABLE_NAME) {
-                    return Err(err);
-                }
+                Self::create_table_if_not_exists(&conn, TABLE_NAME)?;
 
                 let query = format!("INSERT INTO {} (name) VALUES (?1)", TABLE_NAME);
 
@@ -50,174 +104,190 @@ impl<'a> Todo<'a> {
                     Ok(mut stmt) => {
                         for act in activity.iter() {
                             if let Err(err) = stmt.execute([act.name.to_owned()]) {
-                                return Err(err.to_string());
+                                return Err(Error::database("todo", err));
                             }
                         }
                         Ok(())
                     }
-                    Err(err) => Err(err.to_string()),
+                    Err(err) => Err(Error::database("todo", err)),
                 }
             }
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(Error::database("todo", err)),
         }
     }
-    fn query_all(&self) -> Result<(), String> {
+    fn query_all(&self, format: Format) -> Result<(), Error> {
         match Connection::open(DB_PATH) {
             Ok(conn) => {
-                if let Err(err) = Self::create_table_if_not_exists(&conn, TABLE_NAME) {
-                    return Err(err);
-                }
+                Self::create_table_if_not_exists(&conn, TABLE_NAME)?;
 
-                if let Ok(mut stmt) = conn.prepare(&format!("SELECT * FROM {}", TABLE_NAME)) {
-                    if let Ok(activity_iter) = stmt.query_map([], |row| {
+                match conn.prepare(&format!("SELECT * FROM {}", TABLE_NAME)) {
+                    Ok(mut stmt) => match stmt.query_map([], |row| {
                         Ok(Activity {
                             id: row.get(0)?,
                             name: row.get(1)?,
                             is_done: row.get(2)?,
                         })
                     }) {
-                        for activity in activity_iter {
-                            match activity {
-                                Ok(a) => {
-                                    let name = a.name.red();
-                                    let name_strikethrough = a.name.strikethrough().green();
-                                    println!(
-                                        "{}",
-                                        format!(
-                                            "{}\t{}",
-                                            a.id,
-                                            if a.is_done { name_strikethrough } else { name }
-                                        )
-                                    );
-                                }
-                                Err(_) => {}
+                        Ok(activity_iter) if !format.is_text() => {
+                            let activities: Vec<Activity> = activity_iter.flatten().collect();
+                            format.print("todo", &activities)
+                        }
+                        Ok(activity_iter) => {
+                            for a in activity_iter.flatten() {
+                                let role = if a.is_done {
+                                    Role::TodoDone
+                                } else {
+                                    Role::TodoPending
+                                };
+                                println!("{}\t{}", a.id, role.paint(&a.name));
                             }
+                            Ok(())
                         }
-                        return Ok(());
-                    } else {
-                        return Err("Error: Query Map Failed".to_string());
-                    }
-                } else {
-                    return Err("Error: Query Statement Failed".to_string());
+                        Err(err) => Err(Error::database("todo", err)),
+                    },
+                    Err(err) => Err(Error::database("todo", err)),
                 }
             }
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(Error::database("todo", err)),
         }
     }
-    fn update_done(&self, indices: Vec<u64>) -> Result<(), String> {
+    fn update_done(&self, indices: Vec<u64>) -> Result<(), Error> {
         match Connection::open(DB_PATH) {
             Ok(conn) => {
-                if let Err(err) = Self::create_table_if_not_exists(&conn, TABLE_NAME) {
-                    return Err(err);
-                }
+                Self::create_table_if_not_exists(&conn, TABLE_NAME)?;
 
                 let query = format!("UPDATE {} SET is_done = TRUE WHERE id = ?1", TABLE_NAME);
 
                 match conn.prepare(&query) {
                     Ok(mut stmt) => {
                         for idx in indices.iter() {
-                            if let Err(err) = stmt.execute([idx]) {
-                                return Err(err.to_string());
+                            match stmt.execute([idx]) {
+                                Ok(0) => {
+                                    return Err(Error::not_found(
+                                        "todo",
+                                        format!("no task with id {}", idx),
+                                    ))
+                                }
+                                Ok(_) => {}
+                                Err(err) => return Err(Error::database("todo", err)),
                             }
                         }
                         Ok(())
                     }
-                    Err(err) => Err(err.to_string()),
+                    Err(err) => Err(Error::database("todo", err)),
                 }
             }
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(Error::database("todo", err)),
         }
     }
-    fn update_undone(&self, indices: Vec<u64>) -> Result<(), String> {
+    fn update_undone(&self, indices: Vec<u64>) -> Result<(), Error> {
         match Connection::open(DB_PATH) {
             Ok(conn) => {
-                if let Err(err) = Self::create_table_if_not_exists(&conn, TABLE_NAME) {
-                    return Err(err);
-                }
+                Self::create_table_if_not_exists(&conn, TABLE_NAME)?;
 
                 let query = format!("UPDATE {} SET is_done = FALSE WHERE id = ?1", TABLE_NAME);
 
                 match conn.prepare(&query) {
                     Ok(mut stmt) => {
                         for idx in indices.iter() {
-                            if let Err(err) = stmt.execute([idx]) {
-                                return Err(err.to_string());
+                            match stmt.execute([idx]) {
+                                Ok(0) => {
+                                    return Err(Error::not_found(
+                                        "todo",
+                                        format!("no task with id {}", idx),
+                                    ))
+                                }
+                                Ok(_) => {}
+                                Err(err) => return Err(Error::database("todo", err)),
                             }
                         }
                         Ok(())
                     }
-                    Err(err) => Err(err.to_string()),
+                    Err(err) => Err(Error::database("todo", err)),
                 }
             }
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(Error::database("todo", err)),
         }
     }
-    fn remove(&self, indices: Vec<u64>) -> Result<(), String> {
+    fn remove(&self, indices: Vec<u64>) -> Result<(), Error> {
         match Connection::open(DB_PATH) {
             Ok(conn) => {
-                if let Err(err) = Self::create_table_if_not_exists(&conn, TABLE_NAME) {
-                    return Err(err);
-                }
+                Self::create_table_if_not_exists(&conn, TABLE_NAME)?;
 
                 let query = format!("DELETE FROM {} WHERE id = ?1", TABLE_NAME);
 
                 match conn.prepare(&query) {
                     Ok(mut stmt) => {
                         for idx in indices.iter() {
-                            if let Err(err) = stmt.execute([idx]) {
-                                return Err(err.to_string());
+                            match stmt.execute([idx]) {
+                                Ok(0) => {
+                                    return Err(Error::not_found(
+                                        "todo",
+                                        format!("no task with id {}", idx),
+                                    ))
+                                }
+                                Ok(_) => {}
+                                Err(err) => return Err(Error::database("todo", err)),
                             }
                         }
                         Ok(())
                     }
-                    Err(err) => Err(err.to_string()),
+                    Err(err) => Err(Error::database("todo", err)),
                 }
             }
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(Error::database("todo", err)),
         }
     }
 }
 
 impl<'a> Runnable for Todo<'a> {
-    fn run(&mut self) -> Result<(), String> {
-        if self.vars.get_tokens_length() == 1 {
-            println!("No command entered, see 'man todo' for more detailed information");
-            return Ok(());
+    fn run(&mut self) -> Result<(), Error> {
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        let command = parser.subcommand()?;
+        let mut format = self.vars.output_format();
+        let mut operands = Vec::new();
+        while let Some(arg) = parser.next_arg()? {
+            if let Some(chosen) = Format::from_arg(arg) {
+                format = chosen;
+                continue;
+            }
+            match arg {
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
         }
 
-        match self.vars.get_token(1) {
-            "add" | "insert" => {
-                if self.vars.get_tokens_length() == 2 {
-                    return Err("Error: you need to add some tasks".to_string());
-                }
-                let mut activities_to_add : Vec<Activity> = Vec::new();
-                for idx in 2..self.vars.get_tokens_length() {
-                    activities_to_add.push(Activity{
-                        id: 0, name: self.vars.get_token(idx).to_owned(), is_done: false
-                    });
-                }
-                return self.insert(activities_to_add);
-            },
-            "get" | "list" => self.query_all(),
-            "do" | "undo" => {
-                if self.vars.get_tokens_length() == 2 {
-                    return Err("Error: you need to provide the id of the task to be marked as done".to_string());
-                }
-                let mut activity_ids : Vec<u64> = Vec::new();
-                for idx in 2..self.vars.get_tokens_length() {
-                    match self.vars.get_token(idx).parse::<u64>() {
+        match command.name {
+            "add" => self.insert(
+                operands
+                    .iter()
+                    .map(|name| Activity {
+                        id: 0,
+                        name: name.to_string(),
+                        is_done: false,
+                    })
+                    .collect(),
+            ),
+            "list" => self.query_all(format),
+            name => {
+                let mut activity_ids: Vec<u64> = Vec::new();
+                for operand in operands {
+                    match operand.parse::<u64>() {
                         Ok(idx_int) => activity_ids.push(idx_int),
-                        Err(_) => return Err("Error: id provided is not an integer; not valid".to_string()),
+                        Err(_) => {
+                            return Err(Error::usage(
+                                "todo",
+                                format!("id '{}' is not an integer", operand),
+                            ))
+                        }
                     }
                 }
-                match self.vars.get_token(1) {
+                match name {
                     "do" => self.update_done(activity_ids),
                     "undo" => self.update_undone(activity_ids),
-                    "remove" | "erase" | "delete" => self.remove(activity_ids),
-                    &_ => Err("Error".to_string()),
+                    _ => self.remove(activity_ids),
                 }
             }
-            &_ => Err(format!("Error: Command {} not found for todo. Check 'man todo' for more detailed information.", self.vars.get_token(1)))
         }
     }
 }
@@ -1,57 +1,205 @@
-use crate::cmd::CMD;
-use std::{fs::OpenOptions, path::PathBuf};
+use std::{
+    fs::{self, File, FileTimes, OpenOptions},
+    io,
+    path::Path,
+    time::SystemTime,
+};
 
-use super::Runnable;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+
+use super::{man::Page, Runnable};
+
+/// Where the new timestamps come from.
+enum Source {
+    Now,
+    Fixed(SystemTime),
+    Reference {
+        accessed: SystemTime,
+        modified: SystemTime,
+    },
+}
+
+fn local_time(naive: NaiveDateTime) -> Result<SystemTime, Error> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(SystemTime::from)
+        .ok_or_else(|| Error::usage("touch", format!("invalid local time '{}'", naive)))
+}
+
+/// Parses the `-t` stamp `[[CC]YY]MMDDhhmm[.ss]`.
+fn parse_stamp(stamp: &str) -> Result<SystemTime, Error> {
+    let invalid = || Error::usage("touch", format!("invalid date format '{}'", stamp));
+    let (digits, seconds) = match stamp.split_once('.') {
+        Some((digits, seconds)) if seconds.len() == 2 => (digits, seconds),
+        Some(_) => return Err(invalid()),
+        None => (stamp, "00"),
+    };
+    if !digits
+        .chars()
+        .chain(seconds.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let (year, rest) = match digits.len() {
+        8 => (Local::now().format("%Y").to_string(), digits),
+        10 => {
+            // Two digit years follow POSIX: 69-99 is the 1900s, 00-68 the 2000s.
+            let century = if digits[..2] >= *"69" { "19" } else { "20" };
+            (format!("{}{}", century, &digits[..2]), &digits[2..])
+        }
+        12 => (digits[..4].to_string(), &digits[4..]),
+        _ => return Err(invalid()),
+    };
+    let text = format!("{}{}{}", year, rest, seconds);
+    let naive = NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%S").map_err(|_| invalid())?;
+    local_time(naive)
+}
+
+/// Parses the `-d` date: `now`, `@seconds`, RFC 3339, or a local
+/// `YYYY-MM-DD[ HH:MM[:SS[.frac]]]` with a space or `T` between date and time.
+fn parse_date(date: &str) -> Result<SystemTime, Error> {
+    let invalid = || Error::usage("touch", format!("invalid date format '{}'", date));
+    let trimmed = date.trim();
+    if trimmed == "now" {
+        return Ok(SystemTime::now());
+    }
+    if let Some(seconds) = trimmed.strip_prefix('@') {
+        return seconds
+            .parse::<i64>()
+            .ok()
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+            .map(SystemTime::from)
+            .ok_or_else(invalid);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(trimmed) {
+        return Ok(time.into());
+    }
+
+    let normalized = trimmed.replacen('T', " ", 1);
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%d %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(&normalized, format) {
+            return local_time(naive);
+        }
+    }
+    if let Ok(day) = NaiveDate::parse_from_str(&normalized, "%Y-%m-%d") {
+        return local_time(day.and_time(NaiveTime::MIN));
+    }
+    Err(invalid())
+}
+
+pub const SPEC: Spec = Spec::new("touch", "change file timestamps")
+    .usage(&["touch [-a] [-m] [-c] [-t [[CC]YY]MMDDhhmm[.ss] | -d date | -r file] file..."])
+    .options(&[
+        Opt::new("a", &[], "Change only the access time."),
+        Opt::new("m", &[], "Change only the modification time."),
+        Opt::new("c", &["no-create"], "Never create files."),
+        Opt::new("t", &[], "Use [[CC]YY]MMDDhhmm[.ss] instead of now.").value("stamp"),
+        Opt::new(
+            "d",
+            &["date"],
+            "Use date instead of now: 'YYYY-MM-DD[ HH:MM[:SS]]', RFC 3339 or '@seconds'.",
+        )
+        .value("date"),
+        Opt::new("r", &["reference"], "Use the times of file.").value("file"),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description:
+        "Set the access and modification times of the files to now, creating the missing ones.",
+    examples: &[("touch -d '2024-01-01 12:00' a.txt", "Date a.txt back.")],
+    see_also: &["stat"],
+};
 
 pub struct Touch<'a> {
     vars: &'a CMD,
 }
 
 impl<'a> Runnable for Touch<'a> {
-    fn run(&mut self) -> Result<(), String> {
-        if self.vars.get_tokens_length() == 1 {
-            return Err(
-                "touch: missing file operand. Type 'man touch' for more information".to_string(),
-            );
-        }
-
-        let fpath = self.vars.get_token(1);
-        if fpath.ends_with("/") {
-            return Err("touch: can't create directory with touch".to_string());
-        }
-
-        match fpath {
-            "." | ".." => Ok(()),
-            &_ => {
-                let absolute_path = match fpath {
-                    fpath if fpath.starts_with("/") => PathBuf::from(fpath.to_string()),
-                    &_ => {
-                        let mut abs_path = self.vars.get_current_dir_path().clone();
-                        abs_path.push(fpath.to_string());
-                        abs_path
-                    }
-                };
-                match absolute_path.parent() {
-                    Some(parent_path) => {
-                        if parent_path.exists() {
-                            match OpenOptions::new()
-                                .write(true)
-                                .create(true)
-                                .open(&absolute_path)
-                            {
-                                Ok(_) => Ok(()),
-                                Err(err) => {
-                                    return Err(err.to_string());
-                                }
-                            }
-                        } else {
-                            Err("touch: directory does not exist".to_string())
-                        }
-                    }
-                    None => Err("touch: directory does not exist".to_string()),
+    fn run(&mut self) -> Result<(), Error> {
+        let mut access = false;
+        let mut modification = false;
+        let mut no_create = false;
+        let mut source = Source::Now;
+        let mut sources = 0;
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("a") => access = true,
+                Arg::Opt("m") => modification = true,
+                Arg::Opt("no-create") => no_create = true,
+                Arg::Opt("t") => {
+                    source = Source::Fixed(parse_stamp(parser.value())?);
+                    sources += 1;
                 }
+                Arg::Opt("date") => {
+                    source = Source::Fixed(parse_date(parser.value())?);
+                    sources += 1;
+                }
+                Arg::Opt("reference") => {
+                    let reference = parser.value();
+                    let error = |err| Error::io("touch", reference, err);
+                    let metadata =
+                        fs::metadata(self.vars.resolve_path(reference)).map_err(error)?;
+                    source = Source::Reference {
+                        accessed: metadata.accessed().map_err(error)?,
+                        modified: metadata.modified().map_err(error)?,
+                    };
+                    sources += 1;
+                }
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if sources > 1 {
+            return Err(Error::usage(
+                "touch",
+                "cannot specify times from more than one source",
+            ));
+        }
+        if operands.is_empty() {
+            return Err(Error::usage("touch", "missing file operand"));
+        }
+        // Without a selector both times change.
+        if !access && !modification {
+            access = true;
+            modification = true;
+        }
+
+        let now = SystemTime::now();
+        let (accessed, modified) = match source {
+            Source::Now => (now, now),
+            Source::Fixed(time) => (time, time),
+            Source::Reference { accessed, modified } => (accessed, modified),
+        };
+        let mut times = FileTimes::new();
+        if access {
+            times = times.set_accessed(accessed);
+        }
+        if modification {
+            times = times.set_modified(modified);
+        }
+
+        let mut failures = Failures::default();
+        for operand in operands {
+            let path = self.vars.resolve_path(operand);
+            if let Err(err) = Self::touch(&path, operand, times, no_create) {
+                failures.report(Error::io("touch", operand, err));
             }
         }
+        failures.finish("touch")
     }
 }
 
@@ -59,4 +207,22 @@ impl<'a> Touch<'a> {
     pub fn new(cmd: &'a CMD) -> Self {
         Self { vars: cmd }
     }
+
+    fn touch(path: &Path, operand: &str, times: FileTimes, no_create: bool) -> io::Result<()> {
+        let file = if path.exists() {
+            // Opening for reading is enough to change the times and also works for directories.
+            File::open(path)?
+        } else if no_create {
+            return Ok(());
+        } else if operand.ends_with('/') {
+            return Err(io::Error::other("can't create directory with touch"));
+        } else {
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)?
+        };
+        file.set_times(times)
+    }
 }
@@ -0,0 +1,310 @@
+use std::io::{self, BufRead, BufWriter, Write};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    spec::{Opt, Spec},
+    CMD,
+};
+
+use super::{man::Page, Runnable};
+
+/// Characters named by a character class such as `[:upper:]`.
+fn class_members(name: &str) -> Option<Vec<char>> {
+    let test: fn(&char) -> bool = match name {
+        "alnum" => char::is_ascii_alphanumeric,
+        "alpha" => char::is_ascii_alphabetic,
+        "blank" => |c| *c == ' ' || *c == '\t',
+        "cntrl" => char::is_ascii_control,
+        "digit" => char::is_ascii_digit,
+        "graph" => char::is_ascii_graphic,
+        "lower" => char::is_ascii_lowercase,
+        "print" => |c| c.is_ascii_graphic() || *c == ' ',
+        "punct" => char::is_ascii_punctuation,
+        "space" => |c| c.is_ascii_whitespace() || *c == '\x0b',
+        "upper" => char::is_ascii_uppercase,
+        "xdigit" => char::is_ascii_hexdigit,
+        _ => return None,
+    };
+    Some((0u8..128).map(char::from).filter(test).collect())
+}
+
+/// Expands a SET operand: escapes, `a-z` ranges and `[:class:]` names.
+fn parse_set(spec: &str) -> Result<Vec<char>, Error> {
+    // Resolve escapes first, remembering which characters were literal so
+    // that `\-` does not form a range.
+    let mut chars: Vec<(char, bool)> = Vec::new();
+    let mut iter = spec.chars().peekable();
+    while let Some(c) = iter.next() {
+        if c != '\\' {
+            chars.push((c, false));
+            continue;
+        }
+        let escaped = match iter.next() {
+            None => '\\',
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('a') => '\x07',
+            Some('b') => '\x08',
+            Some('f') => '\x0c',
+            Some('v') => '\x0b',
+            Some(digit @ '0'..='7') => {
+                let mut value = digit.to_digit(8).unwrap_or(0);
+                for _ in 0..2 {
+                    match iter.peek().and_then(|c| c.to_digit(8)) {
+                        Some(next) => {
+                            value = value * 8 + next;
+                            iter.next();
+                        }
+                        None => break,
+                    }
+                }
+                char::from_u32(value).unwrap_or('\0')
+            }
+            Some(other) => other,
+        };
+        chars.push((escaped, true));
+    }
+
+    let mut set = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let (c, escaped) = chars[idx];
+        if c == '[' && !escaped && chars.get(idx + 1) == Some(&(':', false)) {
+            let rest: String = chars[idx + 2..].iter().map(|(c, _)| c).collect();
+            if let Some(end) = rest.find(":]") {
+                let name = &rest[..end];
+                let members = class_members(name).ok_or_else(|| {
+                    Error::usage("tr", format!("invalid character class '{}'", name))
+                })?;
+                set.extend(members);
+                idx += 2 + name.chars().count() + 2;
+                continue;
+            }
+        }
+        if let (Some(&('-', false)), Some(&(end, _))) = (chars.get(idx + 1), chars.get(idx + 2)) {
+            if end < c {
+                return Err(Error::usage(
+                    "tr",
+                    format!(
+                        "range-endpoints of '{}-{}' are in reverse collating sequence order",
+                        c, end
+                    ),
+                ));
+            }
+            set.extend(c..=end);
+            idx += 3;
+            continue;
+        }
+        set.push(c);
+        idx += 1;
+    }
+    Ok(set)
+}
+
+struct Translation {
+    from: Vec<char>,
+    to: Vec<char>,
+    complement: bool,
+    delete: bool,
+    squeeze: Option<Vec<char>>,
+}
+
+impl Translation {
+    fn in_first(&self, c: char) -> bool {
+        self.from.contains(&c) != self.complement
+    }
+
+    fn map(&self, c: char) -> char {
+        if self.to.is_empty() || !self.in_first(c) {
+            return c;
+        }
+        // SET2 is padded with its last character, as in POSIX tr.
+        let last = self.to.len() - 1;
+        if self.complement {
+            return self.to[last];
+        }
+        match self.from.iter().rposition(|&from| from == c) {
+            Some(idx) => self.to[idx.min(last)],
+            None => c,
+        }
+    }
+
+    fn apply(&self, text: &str, previous: &mut Option<char>, out: &mut String) {
+        for c in text.chars() {
+            if self.delete && self.in_first(c) {
+                continue;
+            }
+            let c = self.map(c);
+            if let Some(squeeze) = &self.squeeze {
+                if *previous == Some(c) && squeeze.contains(&c) {
+                    continue;
+                }
+            }
+            *previous = Some(c);
+            out.push(c);
+        }
+    }
+}
+
+pub const SPEC: Spec = Spec::new("tr", "translate or delete characters")
+    .usage(&["tr [-c] [-d] [-s] set1 [set2]"])
+    .options(&[
+        Opt::new("cC", &["complement"], "Use the characters not in set1."),
+        Opt::new("d", &["delete"], "Delete the characters of set1."),
+        Opt::new(
+            "s",
+            &["squeeze-repeats"],
+            "Replace runs of a character of the last set with a single one.",
+        ),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Copy the standard input to the standard output, replacing characters of set1 \
+                  with the ones at the same position in set2. Sets accept ranges like a-z, \
+                  classes like [:upper:] and escapes like \\n.",
+    examples: &[
+        ("tr a-z A-Z", "Turn lowercase letters to uppercase."),
+        ("tr -d '\\r'", "Remove carriage returns."),
+    ],
+    see_also: &["sed", "cut"],
+};
+
+pub struct Tr<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Tr<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut complement = false;
+        let mut delete = false;
+        let mut squeeze = false;
+        let mut sets = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("complement") => complement = true,
+                Arg::Opt("delete") => delete = true,
+                Arg::Opt("squeeze-repeats") => squeeze = true,
+                Arg::Value(set) => sets.push(set),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        let (min, max) = match (delete, squeeze) {
+            (true, false) => (1, 1),
+            (false, true) => (1, 2),
+            _ => (2, 2),
+        };
+        if sets.is_empty() {
+            return Err(Error::usage("tr", "missing operand"));
+        }
+        if sets.len() < min {
+            return Err(Error::usage(
+                "tr",
+                format!("missing operand after '{}'", sets[sets.len() - 1]),
+            ));
+        }
+        if sets.len() > max {
+            return Err(Error::usage("tr", format!("extra operand '{}'", sets[max])));
+        }
+
+        let from = parse_set(sets[0])?;
+        let to = match sets.get(1) {
+            Some(set) => parse_set(set)?,
+            None => Vec::new(),
+        };
+        if !delete && sets.len() == 2 && to.is_empty() {
+            return Err(Error::usage(
+                "tr",
+                "when not truncating set1, string2 must be non-empty",
+            ));
+        }
+        // Repeats are squeezed in the last set that was given.
+        let squeeze = squeeze.then(|| match sets.len() {
+            1 if complement => (0u8..128)
+                .map(char::from)
+                .filter(|c| !from.contains(c))
+                .collect(),
+            1 => from.clone(),
+            _ => to.clone(),
+        });
+        let translation = Translation {
+            to: if delete { Vec::new() } else { to },
+            from,
+            complement,
+            delete,
+            squeeze,
+        };
+
+        let mut input = self.vars.stdin();
+        let mut out = BufWriter::new(io::stdout().lock());
+        let mut line = Vec::new();
+        let mut translated = String::new();
+        let mut previous = None;
+        loop {
+            line.clear();
+            match input.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(err) => return Err(Error::io("tr", "standard input", err)),
+            }
+            translated.clear();
+            translation.apply(
+                &String::from_utf8_lossy(&line),
+                &mut previous,
+                &mut translated,
+            );
+            if out.write_all(translated.as_bytes()).is_err() {
+                break;
+            }
+        }
+        let _ = out.flush();
+        Ok(())
+    }
+}
+
+impl<'a> Tr<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_expand_ranges_classes_and_escapes() {
+        let cases = [
+            ("abc", "abc"),
+            ("a-e", "abcde"),
+            ("0-2x", "012x"),
+            ("a-", "a-"),
+            ("a\\-c", "a-c"),
+            ("[:digit:]", "0123456789"),
+            ("[:xdigit:]", "0123456789ABCDEFabcdef"),
+            ("[:blank:]_", "\t _"),
+            ("[:alpha", "[:alpha"),
+            ("\\n\\t\\101\\\\", "\n\tA\\"),
+        ];
+        for (spec, expected) in cases {
+            let set: String = parse_set(spec).unwrap().into_iter().collect();
+            assert_eq!(set, expected, "set '{}'", spec);
+        }
+        assert_eq!(
+            parse_set("[:upper:]").unwrap(),
+            ('A'..='Z').collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn invalid_sets_are_rejected() {
+        for spec in ["z-a", "[:nope:]"] {
+            assert!(parse_set(spec).is_err(), "'{}' was accepted", spec);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    collections::HashSet,
+    fs::{self, Metadata},
+    os::unix::fs::MetadataExt,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::{ignore::Ignore, walk::Walker};
+
+use super::{ls::colors::Palette, man::Page, Runnable};
+
+#[derive(Default)]
+struct Options {
+    all: bool,
+    dirs_only: bool,
+    max_depth: Option<usize>,
+    gitignore: bool,
+    follow_links: bool,
+    json: bool,
+}
+
+/// A file in the hierarchy. `metadata` never follows symlinks.
+struct Node {
+    name: String,
+    path: PathBuf,
+    metadata: Metadata,
+    children: Vec<Node>,
+    unreadable: bool,
+    recursive_link: bool,
+}
+
+/// The shape of `tree -J`, compatible with the output of the classic `tree` tool.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonNode {
+    Directory {
+        name: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        contents: Vec<JsonNode>,
+    },
+    File {
+        name: String,
+    },
+    Link {
+        name: String,
+        target: String,
+    },
+    Report {
+        directories: usize,
+        files: usize,
+    },
+}
+
+#[derive(Default)]
+struct Counts {
+    directories: usize,
+    files: usize,
+    /// Directories on the current path, to stop symlink loops when following links.
+    ancestors: HashSet<(u64, u64)>,
+}
+
+pub const SPEC: Spec = Spec::new("tree", "show directories as a tree")
+    .usage(&["tree [-a] [-d] [-l] [-L level] [--gitignore] [-J] [path...]"])
+    .options(&[
+        Opt::new("a", &[], "Include hidden files."),
+        Opt::new("d", &[], "List directories only."),
+        Opt::new("l", &[], "Descend into symlinks to directories."),
+        Opt::new("L", &[], "Descend at most level directories deep.").value("level"),
+        Opt::new(
+            "",
+            &["gitignore"],
+            "Skip files ignored by .gitignore files.",
+        ),
+        Opt::new("J", &["json"], "Print the tree as JSON."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "List the contents of the directories, the current one without operands, \
+                  indented as a tree, followed by the number of directories and files.",
+    examples: &[(
+        "tree -L 2 src",
+        "Show src and its subdirectories, two levels deep.",
+    )],
+    see_also: &["ls", "du"],
+};
+
+pub struct Tree<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Tree<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options::default();
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("a") => options.all = true,
+                Arg::Opt("d") => options.dirs_only = true,
+                Arg::Opt("L") => match parser.value().parse::<usize>() {
+                    Ok(level) if level > 0 => options.max_depth = Some(level),
+                    _ => {
+                        return Err(Error::usage(
+                            "tree",
+                            "invalid level, must be greater than 0",
+                        ))
+                    }
+                },
+                Arg::Opt("gitignore") => options.gitignore = true,
+                Arg::Opt("l") => options.follow_links = true,
+                Arg::Opt("json") => options.json = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if operands.is_empty() {
+            operands.push(".");
+        }
+
+        let mut counts = Counts::default();
+        let mut roots = Vec::new();
+        let mut failures = Failures::default();
+        for operand in operands {
+            let mut path = self.vars.get_current_dir_path().clone();
+            path.push(operand);
+            match fs::symlink_metadata(&path) {
+                Ok(metadata) => {
+                    let walker = Walker::new(&path)
+                        .hidden(options.all)
+                        .follow_links(options.follow_links)
+                        .git_ignore(options.gitignore);
+                    let ignore = walker.root_ignore();
+                    let metadata = if options.follow_links {
+                        fs::metadata(&path).unwrap_or(metadata)
+                    } else {
+                        metadata
+                    };
+                    roots.push(Self::build(
+                        &walker,
+                        operand,
+                        path,
+                        metadata,
+                        0,
+                        &ignore,
+                        &options,
+                        &mut counts,
+                    ));
+                }
+                Err(err) => failures.report(Error::io("tree", operand, err)),
+            }
+        }
+
+        if options.json {
+            let mut json: Vec<JsonNode> = roots.iter().map(Self::to_json).collect();
+            json.push(JsonNode::Report {
+                directories: counts.directories,
+                files: counts.files,
+            });
+            let text = serde_json::to_string_pretty(&json)
+                .map_err(|err| Error::failed("tree", err.to_string()))?;
+            println!("{}", text);
+        } else {
+            let palette = Palette::from_env();
+            for root in roots.iter() {
+                println!("{}", Self::decorated_name(root, &palette));
+                Self::render(root, "", &palette);
+            }
+            println!();
+            if options.dirs_only {
+                println!("{} directories", counts.directories);
+            } else {
+                println!("{} directories, {} files", counts.directories, counts.files);
+            }
+        }
+        failures.finish("tree")
+    }
+}
+
+impl<'a> Tree<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build(
+        walker: &Walker,
+        name: &str,
+        path: PathBuf,
+        metadata: Metadata,
+        depth: usize,
+        ignore: &Arc<Ignore>,
+        options: &Options,
+        counts: &mut Counts,
+    ) -> Node {
+        let mut node = Node {
+            name: name.to_string(),
+            path,
+            metadata,
+            children: Vec::new(),
+            unreadable: false,
+            recursive_link: false,
+        };
+        if !node.metadata.is_dir() || options.max_depth.is_some_and(|max| depth >= max) {
+            return node;
+        }
+
+        let id = (node.metadata.dev(), node.metadata.ino());
+        if !counts.ancestors.insert(id) {
+            node.recursive_link = true;
+            return node;
+        }
+
+        let (mut children, ignore) = match walker.read_dir(&node.path, depth, ignore) {
+            Ok(listed) => listed,
+            Err(_) => {
+                node.unreadable = true;
+                counts.ancestors.remove(&id);
+                return node;
+            }
+        };
+        if options.dirs_only {
+            children.retain(|child| child.is_dir());
+        }
+        children.sort_by(|a, b| {
+            a.name
+                .to_lowercase()
+                .cmp(&b.name.to_lowercase())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        for child in children {
+            if child.is_dir() {
+                counts.directories += 1;
+            } else {
+                counts.files += 1;
+            }
+            node.children.push(Self::build(
+                walker,
+                &child.name,
+                child.path,
+                child.metadata,
+                child.depth,
+                &ignore,
+                options,
+                counts,
+            ));
+        }
+        counts.ancestors.remove(&id);
+        node
+    }
+
+    fn decorated_name(node: &Node, palette: &Palette) -> String {
+        let mut out = palette.paint(&node.name, &node.path, &node.metadata);
+        if node.metadata.file_type().is_symlink()
+            || fs::symlink_metadata(&node.path).is_ok_and(|m| m.file_type().is_symlink())
+        {
+            if let Ok(target) = fs::read_link(&node.path) {
+                out.push_str(&format!(" -> {}", target.display()));
+            }
+        }
+        if node.unreadable {
+            out.push_str("  [error opening dir]");
+        }
+        if node.recursive_link {
+            out.push_str("  [recursive, not followed]");
+        }
+        out
+    }
+
+    fn render(node: &Node, prefix: &str, palette: &Palette) {
+        for (idx, child) in node.children.iter().enumerate() {
+            let is_last = idx + 1 == node.children.len();
+            let connector = if is_last { "└── " } else { "├── " };
+            println!(
+                "{}{}{}",
+                prefix,
+                connector,
+                Self::decorated_name(child, palette)
+            );
+
+            let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+            Self::render(child, &child_prefix, palette);
+        }
+    }
+
+    fn to_json(node: &Node) -> JsonNode {
+        if node.metadata.is_dir() {
+            JsonNode::Directory {
+                name: node.name.clone(),
+                contents: node.children.iter().map(Self::to_json).collect(),
+            }
+        } else if node.metadata.file_type().is_symlink() {
+            JsonNode::Link {
+                name: node.name.clone(),
+                target: fs::read_link(&node.path)
+                    .map(|target| target.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            }
+        } else {
+            JsonNode::File {
+                name: node.name.clone(),
+            }
+        }
+    }
+}
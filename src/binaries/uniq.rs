@@ -0,0 +1,130 @@
+use std::io::{self, BufRead, BufWriter, Write};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    spec::{Operands, Opt, Spec},
+    CMD,
+};
+
+use super::{man::Page, Runnable};
+
+#[derive(Default)]
+struct Options {
+    count: bool,
+    repeated: bool,
+    unique: bool,
+    ignore_case: bool,
+}
+
+impl Options {
+    fn same(&self, a: &[u8], b: &[u8]) -> bool {
+        if self.ignore_case {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    }
+
+    fn print(&self, out: &mut impl Write, line: &[u8], count: u64) -> io::Result<()> {
+        if (self.repeated && count == 1) || (self.unique && count > 1) {
+            return Ok(());
+        }
+        if self.count {
+            write!(out, "{:>7} ", count)?;
+        }
+        out.write_all(line)?;
+        out.write_all(b"\n")
+    }
+}
+
+pub const SPEC: Spec = Spec::new("uniq", "report or omit repeated lines")
+    .usage(&["uniq [-c] [-d] [-u] [-i] [file]"])
+    .options(&[
+        Opt::new(
+            "c",
+            &["count"],
+            "Prefix lines with the number of their occurrences.",
+        ),
+        Opt::new("d", &["repeated"], "Print only lines that are repeated."),
+        Opt::new("u", &["unique"], "Print only lines that are not repeated."),
+        Opt::new("i", &["ignore-case"], "Ignore case when comparing lines."),
+    ])
+    .operands(Operands::at_most(1));
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Collapse adjacent equal lines of file, or of the standard input, into one. \
+                  Lines must be sorted for all repeats to be adjacent.",
+    examples: &[("sort words | uniq -c", "Count how often every word occurs.")],
+    see_also: &["sort", "group-by"],
+};
+
+pub struct Uniq<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Uniq<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options::default();
+        let mut input = None;
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("count") => options.count = true,
+                Arg::Opt("repeated") => options.repeated = true,
+                Arg::Opt("unique") => options.unique = true,
+                Arg::Opt("ignore-case") => options.ignore_case = true,
+                Arg::Value(operand) => input = Some(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        let operand = input.unwrap_or("-");
+
+        let mut reader = self
+            .vars
+            .open_input(operand)
+            .map_err(|err| Error::io("uniq", operand, err))?;
+        let mut out = BufWriter::new(io::stdout().lock());
+        let mut previous: Option<Vec<u8>> = None;
+        let mut count = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|err| Error::io("uniq", operand, err))?;
+            if read == 0 {
+                break;
+            }
+            if line.ends_with(b"\n") {
+                line.pop();
+            }
+
+            match &previous {
+                Some(group) if options.same(group, &line) => count += 1,
+                _ => {
+                    if let Some(group) = &previous {
+                        if options.print(&mut out, group, count).is_err() {
+                            return Ok(());
+                        }
+                    }
+                    previous = Some(line.clone());
+                    count = 1;
+                }
+            }
+        }
+        if let Some(group) = &previous {
+            let _ = options.print(&mut out, group, count);
+        }
+        let _ = out.flush();
+        Ok(())
+    }
+}
+
+impl<'a> Uniq<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+}
@@ -0,0 +1,189 @@
+use std::io::{self, BufRead};
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::{Error, Failures},
+    spec::{Opt, Spec},
+    CMD,
+};
+
+use super::{man::Page, Runnable};
+
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    lines: u64,
+    words: u64,
+    chars: u64,
+    bytes: u64,
+}
+
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    lines: bool,
+    words: bool,
+    chars: bool,
+    bytes: bool,
+}
+
+impl Options {
+    fn columns(&self, counts: &Counts) -> Vec<u64> {
+        let mut columns = Vec::new();
+        if self.lines {
+            columns.push(counts.lines);
+        }
+        if self.words {
+            columns.push(counts.words);
+        }
+        if self.chars {
+            columns.push(counts.chars);
+        }
+        if self.bytes {
+            columns.push(counts.bytes);
+        }
+        columns
+    }
+}
+
+pub const SPEC: Spec = Spec::new("wc", "count lines, words and bytes")
+    .usage(&["wc [-l] [-w] [-m] [-c] [file...]"])
+    .options(&[
+        Opt::new("l", &["lines"], "Print the newline count."),
+        Opt::new("w", &["words"], "Print the word count."),
+        Opt::new("m", &["chars"], "Print the character count."),
+        Opt::new("c", &["bytes"], "Print the byte count."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Print the newline, word and byte counts of each file, or of the standard \
+                  input, with a total line for several files. The options choose which counts \
+                  are printed.",
+    examples: &[(
+        "ls -1 | wc -l",
+        "Count the entries of the current directory.",
+    )],
+    see_also: &["sort", "uniq"],
+};
+
+pub struct Wc<'a> {
+    vars: &'a CMD,
+}
+
+impl<'a> Runnable for Wc<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut options = Options::default();
+        let mut operands = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("lines") => options.lines = true,
+                Arg::Opt("words") => options.words = true,
+                Arg::Opt("chars") => options.chars = true,
+                Arg::Opt("bytes") => options.bytes = true,
+                Arg::Value(operand) => operands.push(operand),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+        if !(options.lines || options.words || options.chars || options.bytes) {
+            options.lines = true;
+            options.words = true;
+            options.bytes = true;
+        }
+        let named = !operands.is_empty();
+        if operands.is_empty() {
+            operands.push("-");
+        }
+
+        let mut results = Vec::new();
+        let mut total = Counts::default();
+        let mut failures = Failures::default();
+        for operand in &operands {
+            let counts = self
+                .vars
+                .open_input(operand)
+                .and_then(|input| Self::count(input));
+            match counts {
+                Ok(counts) => {
+                    total.add(&counts);
+                    results.push((counts, named.then_some(*operand)));
+                }
+                Err(err) => failures.report(Error::io("wc", *operand, err)),
+            }
+        }
+        if operands.len() > 1 {
+            results.push((total, Some("total")));
+        }
+
+        // Like GNU wc, all columns share the width of the largest number.
+        let width = if operands.len() == 1 && options.columns(&total).len() == 1 {
+            1
+        } else {
+            options
+                .columns(&total)
+                .iter()
+                .map(|count| count.to_string().len())
+                .max()
+                .unwrap_or(1)
+        };
+        for (counts, name) in results {
+            let mut line = options
+                .columns(&counts)
+                .iter()
+                .map(|count| format!("{:>width$}", count, width = width))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if let Some(name) = name {
+                line.push(' ');
+                line.push_str(name);
+            }
+            println!("{}", line);
+        }
+        failures.finish("wc")
+    }
+}
+
+impl<'a> Wc<'a> {
+    pub fn new(cmd: &'a CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    fn count(mut input: Box<dyn BufRead + '_>) -> io::Result<Counts> {
+        let mut counts = Counts::default();
+        let mut in_word = false;
+        loop {
+            let buffer = input.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+            for &byte in buffer {
+                counts.bytes += 1;
+                // Every byte that does not continue a UTF-8 sequence starts a character.
+                if byte & 0xC0 != 0x80 {
+                    counts.chars += 1;
+                }
+                if byte == b'\n' {
+                    counts.lines += 1;
+                }
+                if byte.is_ascii_whitespace() {
+                    in_word = false;
+                } else if !in_word {
+                    in_word = true;
+                    counts.words += 1;
+                }
+            }
+            let consumed = buffer.len();
+            input.consume(consumed);
+        }
+        Ok(counts)
+    }
+}
@@ -0,0 +1,309 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::Connection;
+
+use crate::cmd::{
+    args::{Arg, Parser},
+    error::Error,
+    spec::{Opt, Spec},
+    CMD,
+};
+use crate::fs::path::expand_tilde;
+
+use super::{cd::locate_dir, man::Page, Runnable};
+
+const TABLE_NAME: &str = "Directory";
+/// Once the ranks add up to this much, all of them decay so old entries fade out.
+const MAX_TOTAL_RANK: f64 = 10000.0;
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+
+struct Entry {
+    path: String,
+    rank: f64,
+    last_access: i64,
+}
+
+impl Entry {
+    /// Visits weighted by how recently the directory was used.
+    fn score(&self, now: i64) -> f64 {
+        let age = now - self.last_access;
+        let factor = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * factor
+    }
+
+    /// Every keyword has to appear in the path in order, and the last one in
+    /// its final component, so `z pro api` finds `~/projects/api-server`.
+    fn matches(&self, keywords: &[String]) -> bool {
+        let path = self.path.to_lowercase();
+        let mut rest = path.as_str();
+        for keyword in keywords {
+            match rest.find(keyword.as_str()) {
+                Some(idx) => rest = &rest[idx + keyword.len()..],
+                None => return false,
+            }
+        }
+        match keywords.last() {
+            Some(last) if !last.contains('/') => {
+                let name = path.rsplit('/').next().unwrap_or_default();
+                name.contains(last.as_str())
+            }
+            _ => true,
+        }
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// `$XDG_DATA_HOME/cli-util/z.db`, or `~/.local/share/cli-util/z.db`.
+fn database_path() -> Option<PathBuf> {
+    let data_home = match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".local/share"),
+    };
+    Some(data_home.join("cli-util").join("z.db"))
+}
+
+fn open() -> Result<Connection, Error> {
+    let path = database_path()
+        .ok_or_else(|| Error::failed("z", "cannot locate the database, HOME is not set"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| Error::io("z", parent, err))?;
+    }
+    let conn = Connection::open(&path).map_err(|err| Error::database("z", err))?;
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                path        TEXT PRIMARY KEY,
+                rank        REAL NOT NULL,
+                last_access INTEGER NOT NULL
+            )",
+            TABLE_NAME
+        ),
+        (),
+    )
+    .map_err(|err| Error::database("z", err))?;
+    Ok(conn)
+}
+
+fn record(path: &Path) -> Result<(), Error> {
+    let conn = open()?;
+    let path = path.to_string_lossy();
+    conn.execute(
+        &format!(
+            "INSERT INTO {} (path, rank, last_access) VALUES (?1, 1, ?2)
+             ON CONFLICT(path) DO UPDATE SET rank = rank + 1, last_access = ?2",
+            TABLE_NAME
+        ),
+        (path.as_ref(), now()),
+    )
+    .map_err(|err| Error::database("z", err))?;
+
+    let total: f64 = conn
+        .query_row(
+            &format!("SELECT COALESCE(SUM(rank), 0) FROM {}", TABLE_NAME),
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|err| Error::database("z", err))?;
+    if total > MAX_TOTAL_RANK {
+        conn.execute_batch(&format!(
+            "UPDATE {table} SET rank = rank * 0.9; DELETE FROM {table} WHERE rank < 1;",
+            table = TABLE_NAME
+        ))
+        .map_err(|err| Error::database("z", err))?;
+    }
+    Ok(())
+}
+
+/// Set once a visit could not be recorded, which was reported then.
+static VISIT_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Adds a visit to `path` to the frecency database. Called for every
+/// directory change, which must not fail because of it: a broken database
+/// is reported on the first change only.
+pub(crate) fn record_visit(path: &Path) {
+    if database_path().is_none() {
+        return;
+    }
+    if let Err(err) = record(path) {
+        if !VISIT_FAILED.swap(true, Ordering::Relaxed) {
+            err.report();
+        }
+    }
+}
+
+fn entries(conn: &Connection) -> Result<Vec<Entry>, Error> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT path, rank, last_access FROM {}",
+            TABLE_NAME
+        ))
+        .map_err(|err| Error::database("z", err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Entry {
+                path: row.get(0)?,
+                rank: row.get(1)?,
+                last_access: row.get(2)?,
+            })
+        })
+        .map_err(|err| Error::database("z", err))?;
+    Ok(rows.flatten().collect())
+}
+
+fn remove(conn: &Connection, path: &str) -> Result<(), Error> {
+    conn.execute(
+        &format!("DELETE FROM {} WHERE path = ?1", TABLE_NAME),
+        [path],
+    )
+    .map_err(|err| Error::database("z", err))?;
+    Ok(())
+}
+
+pub const SPEC: Spec = Spec::new("z", "jump to a frequently used directory")
+    .usage(&["z [-l] [-x] [keyword...]"])
+    .options(&[
+        Opt::new(
+            "l",
+            &["list"],
+            "List the matching directories with their scores instead.",
+        ),
+        Opt::new("x", &["remove"], "Forget the current directory."),
+    ]);
+
+pub const MANUAL: Page = Page {
+    spec: &SPEC,
+    description: "Change to the most frecent directory whose path contains the keywords in \
+                  order, the last one in its final component. Frecency grows with every visit \
+                  and fades with time.\n\n\
+                  Every cd is recorded in $XDG_DATA_HOME/cli-util/z.db.",
+    examples: &[(
+        "z src cli",
+        "Go to the most used directory like ~/src/cli-util.",
+    )],
+    see_also: &["cd", "pushd"],
+};
+
+pub struct Z<'a> {
+    vars: &'a mut CMD,
+}
+
+impl<'a> Runnable for Z<'a> {
+    fn run(&mut self) -> Result<(), Error> {
+        let mut list = false;
+        let mut forget = false;
+        let mut keywords = Vec::new();
+
+        let mut parser = Parser::new(&SPEC, self.vars.get_args());
+        while let Some(arg) = parser.next_arg()? {
+            match arg {
+                Arg::Opt("list") => list = true,
+                Arg::Opt("remove") => forget = true,
+                Arg::Value(keyword) => keywords.push(keyword.to_string()),
+                _ => return Err(parser.unexpected(arg)),
+            }
+        }
+
+        if forget {
+            let current = self
+                .vars
+                .get_current_dir_path()
+                .to_string_lossy()
+                .into_owned();
+            return remove(&open()?, &current);
+        }
+
+        if !list {
+            let direct = match keywords.as_slice() {
+                [] => Some(env::var("HOME").map_err(|_| Error::failed("z", "HOME not set"))?),
+                [only] if only == "-" => match self.vars.get_previous_dir_path() {
+                    Some(previous) => Some(previous.display().to_string()),
+                    None => return Err(Error::failed("z", "OLDPWD not set")),
+                },
+                // A path to an existing directory is used as is, like `cd`.
+                [only] if self.vars.resolve_path(&expand_tilde(only)).is_dir() => {
+                    Some(expand_tilde(only))
+                }
+                _ => None,
+            };
+            if let Some(target) = direct {
+                let (path, _) = locate_dir(self.vars, "z", &target, false)?;
+                return self.jump(path);
+            }
+        }
+
+        let conn = open()?;
+        let now = now();
+        let current = self
+            .vars
+            .get_current_dir_path()
+            .to_string_lossy()
+            .into_owned();
+        let keywords: Vec<String> = keywords.iter().map(|k| k.to_lowercase()).collect();
+        let mut candidates = Vec::new();
+        for entry in entries(&conn)? {
+            if !Path::new(&entry.path).is_dir() {
+                // Directories that no longer exist are forgotten on the way.
+                remove(&conn, &entry.path)?;
+                continue;
+            }
+            if entry.matches(&keywords) {
+                candidates.push(entry);
+            }
+        }
+        candidates.sort_by(|a, b| b.score(now).total_cmp(&a.score(now)));
+
+        if list {
+            for entry in candidates.iter().rev() {
+                println!("{:>8.1}  {}", entry.score(now), entry.path);
+            }
+            return Ok(());
+        }
+        match candidates.into_iter().find(|entry| entry.path != current) {
+            Some(entry) => {
+                let path = PathBuf::from(entry.path);
+                println!("{}", path.display());
+                self.jump(path)
+            }
+            None => Err(Error::not_found(
+                "z",
+                format!("no match found for '{}'", keywords.join(" ")),
+            )),
+        }
+    }
+}
+
+impl<'a> Z<'a> {
+    pub fn new(cmd: &'a mut CMD) -> Self {
+        Self { vars: cmd }
+    }
+
+    fn jump(&mut self, path: PathBuf) -> Result<(), Error> {
+        self.vars
+            .set_current_dir_path(path.clone())
+            .map_err(|err| Error::io("z", &path, err))?;
+        record_visit(&path);
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use super::{
+    color::{self, ColorChoice},
+    error::Error,
+    spec::{Spec, Value},
+};
+
+/// A single item produced by the argument `Parser`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arg<'a> {
+    /// An option of the spec by its name, whichever of its names was given:
+    /// `-a` and `--all` both are `Opt("all")`, and `ls -l` is `Opt("l")`.
+    /// Clusters like `-la` yield one `Opt` per letter.
+    Opt(&'static str),
+    /// An operand. Everything after `--` and a lone `-` are operands as well.
+    Value(&'a str),
+}
+
+/// A token split by the parser, before the spec is looked at.
+enum Token<'a> {
+    Flag(Flag<'a>),
+    Operand(&'a str),
+}
+
+/// An option as it was typed, for the error messages.
+#[derive(Debug, Clone, Copy)]
+enum Flag<'a> {
+    Short(char),
+    Long(&'a str),
+}
+
+/// Getopt-style parser over the tokens of a command.
+///
+/// The parser splits the tokens, rejects the options and operand counts
+/// the `Spec` of the command does not allow and takes the values the spec
+/// declares, which the binaries get with `value`. `--color[=when]` is
+/// applied by the parser itself, and `--help` returns the help of the spec
+/// as an `Error::Help`.
+pub struct Parser<'a> {
+    spec: &'static Spec,
+    /// The command of the current subcommand, for its help.
+    parent: Option<&'static str>,
+    tokens: &'a [String],
+    pos: usize,
+    shorts: Option<&'a str>,
+    long_value: Option<&'a str>,
+    only_operands: bool,
+    operands: usize,
+    last_operand: Option<&'a str>,
+    /// The last option returned and its value.
+    flag: Option<Flag<'a>>,
+    value: Option<&'a str>,
+}
+
+impl<'a> Parser<'a> {
+    /// `tokens` must not contain the command name itself.
+    pub fn new(spec: &'static Spec, tokens: &'a [String]) -> Self {
+        Self {
+            spec,
+            parent: None,
+            tokens,
+            pos: 0,
+            shorts: None,
+            long_value: None,
+            only_operands: false,
+            operands: 0,
+            last_operand: None,
+            flag: None,
+            value: None,
+        }
+    }
+
+    /// The command name used in errors, the parent one for subcommands.
+    fn command(&self) -> &'static str {
+        self.parent.unwrap_or(self.spec.name)
+    }
+
+    pub fn next_arg(&mut self) -> Result<Option<Arg<'a>>, Error> {
+        let flag = match self.split()? {
+            None if self.operands < self.spec.operands.min => {
+                let message = match self.last_operand {
+                    Some(last) => format!("missing operand after '{}'", last),
+                    None => "missing operand".to_string(),
+                };
+                return Err(Error::usage(self.command(), message));
+            }
+            None => return Ok(None),
+            Some(Token::Operand(value)) => {
+                self.operands += 1;
+                if self
+                    .spec
+                    .operands
+                    .max
+                    .is_some_and(|max| self.operands > max)
+                {
+                    return Err(self.unexpected(Arg::Value(value)));
+                }
+                self.last_operand = Some(value);
+                return Ok(Some(Arg::Value(value)));
+            }
+            Some(Token::Flag(flag)) => flag,
+        };
+
+        self.flag = Some(flag);
+        let opt = match flag {
+            Flag::Long("help") if self.spec.long("help").is_none() => {
+                return Err(Error::help(self.spec.help(self.parent)))
+            }
+            Flag::Short(c) => self.spec.short(c),
+            Flag::Long(name) => self.spec.long(name),
+        };
+        let Some(opt) = opt else {
+            return Err(self.invalid(flag));
+        };
+        self.value = match opt.value {
+            Value::None if self.long_value.take().is_some() => {
+                let message = format!("option '{}' doesn't allow an argument", describe(flag));
+                return Err(Error::usage(self.command(), message));
+            }
+            Value::None => None,
+            Value::Required(_) => Some(self.take_value()?),
+            Value::Optional(_) => self.long_value.take().or_else(|| self.shorts.take()),
+        };
+        Ok(Some(Arg::Opt(opt.name())))
+    }
+
+    /// Takes the next token as the name of a subcommand, whose options and
+    /// operands apply from then on.
+    pub fn subcommand(&mut self) -> Result<&'static Spec, Error> {
+        let names: Vec<&str> = self.spec.subcommands.iter().map(|sub| sub.name).collect();
+        let expected = format!("expected one of {}", names.join(", "));
+        match self.split()? {
+            Some(Token::Operand(name)) => {
+                let Some(sub) = self.spec.subcommand(name) else {
+                    return Err(Error::usage(
+                        self.command(),
+                        format!("unknown command '{}', {}", name, expected),
+                    ));
+                };
+                self.parent = Some(self.spec.name);
+                self.spec = sub;
+                Ok(sub)
+            }
+            Some(Token::Flag(Flag::Long("help"))) => Err(Error::help(self.spec.help(self.parent))),
+            Some(Token::Flag(flag)) => Err(self.invalid(flag)),
+            None => Err(Error::usage(
+                self.command(),
+                format!("missing command, {}", expected),
+            )),
+        }
+    }
+
+    /// Splits the next token, without looking at the spec.
+    fn split(&mut self) -> Result<Option<Token<'a>>, Error> {
+        if let Some(rest) = self.shorts {
+            let mut chars = rest.chars();
+            if let Some(c) = chars.next() {
+                let remaining = chars.as_str();
+                self.shorts = if remaining.is_empty() {
+                    None
+                } else {
+                    Some(remaining)
+                };
+                return Ok(Some(Token::Flag(Flag::Short(c))));
+            }
+            self.shorts = None;
+        }
+
+        let Some(token) = self.tokens.get(self.pos) else {
+            return Ok(None);
+        };
+        self.pos += 1;
+
+        if self.only_operands || token == "-" || !token.starts_with('-') {
+            return Ok(Some(Token::Operand(token)));
+        }
+
+        if token == "--" {
+            self.only_operands = true;
+            return self.split();
+        }
+
+        if let Some(long) = token.strip_prefix("--") {
+            let (name, value) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (long, None),
+            };
+            if name == "color" {
+                color::set(ColorChoice::from_value(self.command(), value)?);
+                return self.split();
+            }
+            self.long_value = value;
+            return Ok(Some(Token::Flag(Flag::Long(name))));
+        }
+
+        self.shorts = Some(&token[1..]);
+        self.split()
+    }
+
+    /// Takes the value of an option: the rest of a short cluster (`-n5`),
+    /// the part after `=` (`--lines=5`) or the next token.
+    fn take_value(&mut self) -> Result<&'a str, Error> {
+        if let Some(value) = self.long_value.take() {
+            return Ok(value);
+        }
+        if let Some(rest) = self.shorts.take() {
+            return Ok(rest);
+        }
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(token)
+            }
+            None => {
+                let message = match self.flag {
+                    Some(Flag::Short(c)) => format!("option requires an argument -- '{}'", c),
+                    Some(flag) => format!("option '{}' requires an argument", describe(flag)),
+                    None => "option requires an argument".to_string(),
+                };
+                Err(Error::usage(self.command(), message))
+            }
+        }
+    }
+
+    /// The value of the option that was just returned, which the spec says
+    /// it takes.
+    pub fn value(&mut self) -> &'a str {
+        self.value.take().unwrap_or_default()
+    }
+
+    /// The value of an option whose argument is optional, which then has to
+    /// be attached (`--color=always`, `-i.bak`), as in GNU tools.
+    pub fn optional_value(&mut self) -> Option<&'a str> {
+        self.value.take()
+    }
+
+    /// Builds the error for an argument the binary does not know about.
+    pub fn unexpected(&self, arg: Arg) -> Error {
+        match (arg, self.flag) {
+            (Arg::Opt(_), Some(flag)) => self.invalid(flag),
+            (Arg::Opt(name), None) => self.invalid(Flag::Long(name)),
+            (Arg::Value(value), _) => {
+                Error::usage(self.command(), format!("extra operand '{}'", value))
+            }
+        }
+    }
+
+    fn invalid(&self, flag: Flag) -> Error {
+        let message = match flag {
+            Flag::Short(c) => format!("invalid option -- '{}'", c),
+            Flag::Long(_) => format!("unrecognized option '{}'", describe(flag)),
+        };
+        Error::usage(self.command(), message)
+    }
+}
+
+/// The option as it was typed, `-n` or `--lines`.
+fn describe(flag: Flag) -> String {
+    match flag {
+        Flag::Short(c) => format!("-{}", c),
+        Flag::Long(name) => format!("--{}", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::spec::Opt;
+
+    const SPEC: Spec = Spec::new("demo", "try the parser").options(&[
+        Opt::new("a", &["all"], "Show everything."),
+        Opt::new("n", &["lines"], "Show that many lines.").value("count"),
+        Opt::new("i", &["in-place"], "Edit the files.").optional_value("suffix"),
+    ]);
+
+    /// The options and values parsed from `words`, or the error message.
+    fn parse(words: &[&str]) -> Result<Vec<String>, String> {
+        let tokens: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+        let mut parser = Parser::new(&SPEC, &tokens);
+        let mut parsed = Vec::new();
+        while let Some(arg) = parser.next_arg().map_err(|err| err.to_string())? {
+            parsed.push(match arg {
+                Arg::Opt("lines") => format!("lines={}", parser.value()),
+                Arg::Opt("in-place") => format!("in-place={:?}", parser.optional_value()),
+                Arg::Opt(name) => name.to_string(),
+                Arg::Value(value) => value.to_string(),
+            });
+        }
+        Ok(parsed)
+    }
+
+    #[test]
+    fn aliases_and_values_are_resolved() {
+        let cases: [(&[&str], &[&str]); 6] = [
+            (&["-a", "--all"], &["all", "all"]),
+            (&["-an5", "x"], &["all", "lines=5", "x"]),
+            (
+                &["-n", "5", "--lines=6", "--lines", "7"],
+                &["lines=5", "lines=6", "lines=7"],
+            ),
+            (
+                &["-i.bak", "--in-place", "f"],
+                &["in-place=Some(\".bak\")", "in-place=None", "f"],
+            ),
+            (&["--", "-a"], &["-a"]),
+            (&["-", "-n-"], &["-", "lines=-"]),
+        ];
+        for (words, expected) in cases {
+            assert_eq!(parse(words).unwrap(), expected, "{:?}", words);
+        }
+    }
+
+    #[test]
+    fn errors_name_the_option() {
+        let cases: [(&[&str], &str); 5] = [
+            (&["-x"], "invalid option -- 'x'"),
+            (&["--none"], "unrecognized option '--none'"),
+            (&["-n"], "option requires an argument -- 'n'"),
+            (&["--lines"], "option '--lines' requires an argument"),
+            (&["--all=yes"], "option '--all' doesn't allow an argument"),
+        ];
+        for (words, expected) in cases {
+            let message = parse(words).unwrap_err();
+            assert!(
+                message.ends_with(expected),
+                "{:?} gave '{}'",
+                words,
+                message
+            );
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Whether escape codes are written, decided here for every command.
+//!
+//! The binaries keep using `colored`; before each command the shell turns
+//! its global override on or off, so nothing else has to check.
+
+use std::{
+    env,
+    io::{self, IsTerminal},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use super::error::Error;
+
+/// The choice of the command running now, see `set`.
+static CHOICE: AtomicU8 = AtomicU8::new(ColorChoice::Auto as u8);
+
+/// The values of `--color`, accepted by every command and by the shell itself.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color terminals, unless `NO_COLOR` or `CLICOLOR_FORCE` say otherwise.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Reads the value of `--color`, which defaults to `always` like in GNU tools.
+    pub fn from_value(command: &str, value: Option<&str>) -> Result<Self, Error> {
+        match value {
+            None | Some("always") | Some("yes") | Some("force") => Ok(Self::Always),
+            Some("auto") | Some("tty") | Some("if-tty") => Ok(Self::Auto),
+            Some("never") | Some("no") | Some("none") => Ok(Self::Never),
+            Some(value) => Err(Error::usage(
+                command,
+                format!(
+                    "invalid argument '{}' for '--color', valid arguments are 'auto', \
+                     'always' and 'never'",
+                    value
+                ),
+            )),
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Always,
+            2 => Self::Never,
+            _ => Self::Auto,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// `NO_COLOR` disables colors whatever its value, as long as it has one.
+fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// `CLICOLOR_FORCE` forces colors unless it is empty or `0`.
+fn clicolor_force() -> bool {
+    env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Whether `stream` gets colors: an explicit `--color` wins, then
+/// `NO_COLOR`, then `CLICOLOR_FORCE`, and otherwise whether the stream is a
+/// terminal. A pipeline stage writes to a file, so it stays plain.
+pub fn enabled(stream: Stream) -> bool {
+    match ColorChoice::from_u8(CHOICE.load(Ordering::Relaxed)) {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto if no_color() => false,
+        ColorChoice::Auto if clicolor_force() => true,
+        ColorChoice::Auto => match stream {
+            Stream::Stdout => io::stdout().is_terminal(),
+            Stream::Stderr => io::stderr().is_terminal(),
+        },
+    }
+}
+
+/// Applies `choice` to the standard output until the next call. The shell
+/// calls it before every command, and the parser again for `--color`.
+pub fn set(choice: ColorChoice) {
+    CHOICE.store(choice as u8, Ordering::Relaxed);
+    colored::control::set_override(enabled(Stream::Stdout));
+}
+
+/// Runs `print` with the policy of the standard error, for messages that
+/// go there while a command writes to the standard output.
+pub fn on_stderr<T>(print: impl FnOnce() -> T) -> T {
+    colored::control::set_override(enabled(Stream::Stderr));
+    let result = print();
+    colored::control::set_override(enabled(Stream::Stdout));
+    result
+}
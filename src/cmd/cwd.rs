@@ -0,0 +1,104 @@
+//! The working directory of the shell, kept in step with the one of the process.
+
+use std::{
+    env, fs, io,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+/// Whether the shell's directory still matches the process one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CwdState {
+    Current,
+    /// The directory was removed, so relative paths lead nowhere until the
+    /// next `cd`.
+    Deleted,
+}
+
+/// The directory shown in the prompt and used for relative paths.
+///
+/// The path is logical: it keeps the symlinks `cd` went through, while the
+/// process directory is what std and the kernel resolve relative paths
+/// against. Every change goes through `change`, so both always agree.
+#[derive(Debug, Clone)]
+pub struct Cwd {
+    path: PathBuf,
+    previous: Option<PathBuf>,
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+impl Cwd {
+    /// Starts in the directory the shell was launched from, preferring
+    /// `$PWD` when it names the same directory. If that directory is already
+    /// gone, `$PWD` is kept so the prompt can report it. `$OLDPWD` is the
+    /// previous directory, as it was for the parent shell.
+    pub fn new() -> Result<Self, String> {
+        let pwd = env::var("PWD").ok().map(PathBuf::from);
+        let path = match env::current_dir() {
+            Ok(current) => match pwd {
+                Some(pwd) if pwd.is_absolute() && same_dir(&pwd, &current) => pwd,
+                _ => current,
+            },
+            Err(err) => pwd
+                .filter(|pwd| pwd.is_absolute())
+                .ok_or_else(|| format!("cannot determine the current directory: {}", err))?,
+        };
+        let previous = env::var_os("OLDPWD")
+            .map(PathBuf::from)
+            .filter(|previous| previous.is_absolute());
+        let cwd = Self { path, previous };
+        cwd.export();
+        Ok(cwd)
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// The directory before the last change, used by `cd -`.
+    pub fn previous(&self) -> Option<&PathBuf> {
+        self.previous.as_ref()
+    }
+
+    /// Moves the process into `path` and records it, remembering the old directory.
+    pub fn change(&mut self, path: PathBuf) -> io::Result<()> {
+        env::set_current_dir(&path)?;
+        let previous = std::mem::replace(&mut self.path, path);
+        self.previous = Some(previous);
+        self.export();
+        Ok(())
+    }
+
+    /// Sets `$PWD` and `$OLDPWD` for the programs the shell starts.
+    fn export(&self) {
+        env::set_var("PWD", &self.path);
+        if let Some(previous) = &self.previous {
+            env::set_var("OLDPWD", previous);
+        }
+    }
+
+    /// Brings the process directory and its variables back to the shell's,
+    /// which is needed when a pipeline stage changed them or the directory
+    /// was replaced by a new one with the same name.
+    pub fn sync(&self) -> CwdState {
+        self.export();
+        if same_dir(&self.path, Path::new(".")) {
+            return CwdState::Current;
+        }
+        if self.path.is_dir() && env::set_current_dir(&self.path).is_ok() {
+            return CwdState::Current;
+        }
+        CwdState::Deleted
+    }
+
+    /// The closest ancestor of the current directory that still exists.
+    pub fn nearest_existing(&self) -> Option<&Path> {
+        self.path.ancestors().skip(1).find(|dir| dir.is_dir())
+    }
+}
@@ -0,0 +1,270 @@
+//! The error every command returns, and how the shell reports it.
+
+use std::{env, fmt, io, path::PathBuf};
+
+use serde_json::{json, Value};
+
+use super::color;
+use crate::theme::Role;
+
+/// Set to `json` to get errors as one JSON object per line on stderr.
+const FORMAT_VAR: &str = "CLI_ERROR_FORMAT";
+
+#[derive(Debug)]
+pub enum Error {
+    /// Unknown flags, missing or extra operands and malformed values.
+    Usage { command: String, message: String },
+    /// A file operation failed on `path`.
+    Io {
+        command: String,
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// A task, user, match or similar the command was asked about does not exist.
+    NotFound { command: String, message: String },
+    /// A query on one of the sqlite databases failed.
+    Database {
+        command: String,
+        source: rusqlite::Error,
+    },
+    /// Any other failure of the command.
+    Failed { command: String, message: String },
+    /// Some operands failed and were reported one by one while the command
+    /// went on with the others. `status` is the one of the worst of them.
+    Partial { command: String, status: i32 },
+    /// Not a failure: the files `diff` compared differ. Nothing is printed,
+    /// only the status tells.
+    Differ { command: String },
+    /// No command has this name.
+    UnknownCommand { name: String },
+    /// Not a failure: `--help` was given, and the help is printed on the
+    /// standard output instead of running the command.
+    Help { text: String },
+}
+
+impl Error {
+    pub fn usage(command: &str, message: impl Into<String>) -> Self {
+        Self::Usage {
+            command: command.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn io(command: &str, path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self::Io {
+            command: command.to_string(),
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn not_found(command: &str, message: impl Into<String>) -> Self {
+        Self::NotFound {
+            command: command.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn database(command: &str, source: rusqlite::Error) -> Self {
+        Self::Database {
+            command: command.to_string(),
+            source,
+        }
+    }
+
+    pub fn failed(command: &str, message: impl Into<String>) -> Self {
+        Self::Failed {
+            command: command.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn differ(command: &str) -> Self {
+        Self::Differ {
+            command: command.to_string(),
+        }
+    }
+
+    pub fn help(text: String) -> Self {
+        Self::Help { text }
+    }
+
+    pub fn command(&self) -> &str {
+        match self {
+            Self::Usage { command, .. }
+            | Self::Io { command, .. }
+            | Self::NotFound { command, .. }
+            | Self::Database { command, .. }
+            | Self::Failed { command, .. }
+            | Self::Partial { command, .. }
+            | Self::Differ { command } => command,
+            Self::UnknownCommand { name } => name,
+            Self::Help { .. } => "",
+        }
+    }
+
+    /// The exit status stored in `$?`.
+    pub fn status(&self) -> i32 {
+        match self {
+            Self::Help { .. } => 0,
+            Self::Failed { .. } | Self::Differ { .. } => 1,
+            Self::Partial { status, .. } => *status,
+            Self::Usage { .. } => 2,
+            Self::Io { .. } => 3,
+            Self::NotFound { .. } => 4,
+            Self::Database { .. } => 5,
+            Self::UnknownCommand { .. } => 127,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Usage { .. } => "usage",
+            Self::Io { .. } => "io",
+            Self::NotFound { .. } => "not_found",
+            Self::Database { .. } => "database",
+            Self::Failed { .. } => "failed",
+            Self::Partial { .. } => "partial",
+            Self::Differ { .. } => "differ",
+            Self::UnknownCommand { .. } => "unknown_command",
+            Self::Help { .. } => "help",
+        }
+    }
+
+    /// Where to look next, for the errors where the manual helps.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            // Syntax errors of the command line, whose notes close the list of commands.
+            Self::Usage { command, .. } if command == "sh" => {
+                Some("see 'man' for the syntax of the shell".to_string())
+            }
+            Self::Usage { command, .. } => Some(format!("see 'man {}'", command)),
+            Self::UnknownCommand { .. } => Some("see 'man' for the list of commands".to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "error": self.kind(),
+            "command": self.command(),
+            "message": self.to_string(),
+            "status": self.status(),
+        });
+        if let Self::Io { path, .. } = self {
+            value["path"] = json!(path.display().to_string());
+        }
+        if let Some(hint) = self.hint() {
+            value["hint"] = json!(hint);
+        }
+        value
+    }
+
+    /// Prints the error on stderr, in red or as JSON when `CLI_ERROR_FORMAT`
+    /// is `json`. A `Partial` error prints nothing, its operands were
+    /// reported already, neither does `Differ`, and `Help` prints its text
+    /// on stdout.
+    pub fn report(&self) {
+        match self {
+            Self::Partial { .. } | Self::Differ { .. } => return,
+            Self::Help { text } => {
+                print!("{}", text);
+                return;
+            }
+            _ => {}
+        }
+        if env::var(FORMAT_VAR).is_ok_and(|format| format == "json") {
+            eprintln!("{}", self.to_json());
+            return;
+        }
+        color::on_stderr(|| {
+            eprintln!("Error: {}", Role::Error.paint(&self.to_string()));
+            if let Some(hint) = self.hint() {
+                eprintln!("{}", Role::Hint.paint(&format!("Hint: {}", hint)));
+            }
+        });
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage { command, message }
+            | Self::NotFound { command, message }
+            | Self::Failed { command, message } => write!(f, "{}: {}", command, message),
+            Self::Io {
+                command,
+                path,
+                source,
+            } => write!(f, "{}: {}: {}", command, path.display(), source),
+            Self::Database { command, source } => {
+                write!(f, "{}: database error: {}", command, source)
+            }
+            Self::Partial { command, .. } => write!(f, "{}: some operands failed", command),
+            Self::Differ { command } => write!(f, "{}: the files differ", command),
+            Self::UnknownCommand { name } => write!(f, "command not found: {}", name),
+            Self::Help { text } => write!(f, "{}", text),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Database { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// The errors of the operands a command went on after. Each one is
+/// reported as it comes, and `finish` sums them up as a `Partial` error
+/// with the status of the worst.
+#[derive(Debug, Default)]
+pub struct Failures {
+    status: i32,
+}
+
+impl Failures {
+    pub fn report(&mut self, err: Error) {
+        err.report();
+        self.count(&err);
+    }
+
+    /// Counts `err` without printing it, for options like `chmod -f`.
+    pub fn count(&mut self, err: &Error) {
+        self.status = self.status.max(err.status());
+    }
+
+    pub fn merge(&mut self, other: Failures) {
+        self.status = self.status.max(other.status);
+    }
+
+    pub fn finish(self, command: &str) -> Result<(), Error> {
+        match self.status {
+            0 => Ok(()),
+            status => Err(Error::Partial {
+                command: command.to_string(),
+                status,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_keep_the_worst_status() {
+        assert!(Failures::default().finish("cat").is_ok());
+
+        let mut failures = Failures::default();
+        failures.count(&Error::io("cat", "nosuch", io::ErrorKind::NotFound.into()));
+        failures.count(&Error::failed("cat", "failed"));
+        let err = failures.finish("cat").unwrap_err();
+        assert_eq!(err.status(), 3);
+        assert_eq!(err.command(), "cat");
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    mem, ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sigint(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Catches Ctrl-C while alive, so a long running built-in such as `tail -f`
+/// can stop cleanly instead of taking the whole shell down with it.
+/// The previous SIGINT disposition is restored on drop.
+pub struct Interrupt {
+    previous: libc::sigaction,
+}
+
+impl Interrupt {
+    pub fn catch() -> Self {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        // SAFETY: both sigaction structs are fully initialised before use and
+        // the handler only touches an atomic, which is async-signal-safe.
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_sigint as *const () as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            let mut previous: libc::sigaction = mem::zeroed();
+            libc::sigaction(libc::SIGINT, &action, &mut previous);
+            Self { previous }
+        }
+    }
+
+    pub fn triggered(&self) -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Interrupt {
+    fn drop(&mut self) {
+        // SAFETY: `previous` was filled in by the kernel in `catch`.
+        unsafe {
+            libc::sigaction(libc::SIGINT, &self.previous, ptr::null_mut());
+        }
+    }
+}
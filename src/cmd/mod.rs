@@ -1,20 +1,49 @@
-use std::env;
-use std::io::{self};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
 use std::path::PathBuf;
 
 use std::io::Write;
 
-use colored::*;
-
 use chrono::Local;
 
-use crate::binaries::{BinEnum, Runnable};
+use crate::binaries::{self, BinEnum, Runnable};
+use crate::theme::{self, Role, Theme};
+
+use self::color::ColorChoice;
+use self::cwd::{Cwd, CwdState};
+use self::error::Error;
+use self::output::Format;
+use self::parse::Stage;
+use self::pipe::Capture;
+
+pub mod args;
+pub mod color;
+pub mod cwd;
+pub mod error;
+pub mod interrupt;
+pub mod output;
+pub mod parse;
+pub mod pipe;
+pub mod spec;
+
+/// Commands the shell runs itself instead of creating a binary.
+pub const BUILTINS: [&str; 2] = ["exit", "quit"];
 
 pub struct CMD {
     raw_command: String,
     tokens: Vec<String>,
-    chars: Vec<char>,
-    current_dir_path: PathBuf,
+    cwd: Cwd,
+    /// Directories saved by `pushd`, the most recent first. The current
+    /// directory is the implicit top of the stack.
+    dir_stack: Vec<PathBuf>,
+    stages: Vec<Stage>,
+    piped_input: Option<Vec<u8>>,
+    /// Set when the next pipeline stage reads records, e.g. `where`.
+    feeds_records: bool,
+    /// Exit status of the last command, expanded by `$?`.
+    status: i32,
+    /// The color policy of the shell, which `--color` changes for one command.
+    color: ColorChoice,
 }
 
 impl Clone for CMD {
@@ -22,23 +51,39 @@ impl Clone for CMD {
         Self {
             raw_command: self.raw_command.clone(),
             tokens: self.tokens.clone(),
-            chars: self.chars.clone(),
-            current_dir_path: self.current_dir_path.clone(),
+            cwd: self.cwd.clone(),
+            dir_stack: self.dir_stack.clone(),
+            stages: self.stages.clone(),
+            piped_input: self.piped_input.clone(),
+            feeds_records: self.feeds_records,
+            status: self.status,
+            color: self.color,
         }
     }
 }
 
 impl CMD {
     pub fn new() -> Result<Self, String> {
-        match env::current_dir() {
-            Ok(cur) => Ok(Self {
-                raw_command: String::new(),
-                tokens: Vec::new(),
-                chars: Vec::new(),
-                current_dir_path: cur,
-            }),
-            Err(err) => Err(err.to_string()),
-        }
+        Ok(Self {
+            raw_command: String::new(),
+            tokens: Vec::new(),
+            cwd: Cwd::new()?,
+            dir_stack: Vec::new(),
+            stages: Vec::new(),
+            piped_input: None,
+            feeds_records: false,
+            status: 0,
+            color: ColorChoice::Auto,
+        })
+    }
+
+    /// A shell about to run `line`, for tests.
+    #[cfg(test)]
+    pub fn with_line(line: &str) -> Self {
+        let mut cmd = Self::new().unwrap();
+        cmd.stages = parse::split_pipeline(line, 0).unwrap();
+        cmd.tokens = cmd.stages[0].words.clone();
+        cmd
     }
 
     pub fn register_binaries() {
@@ -55,85 +100,241 @@ impl CMD {
             }
         };
 
-        self.raw_command = self
-            .raw_command
-            .trim_start_matches(|c| c == ' ')
-            .to_string();
+        self.raw_command = self.raw_command.trim_start_matches(' ').to_string();
 
-        self.tokens = self
-            .raw_command
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .iter()
-            .map(|&s| s.to_owned())
-            .collect();
-
-        self.chars = self.raw_command.chars().collect();
+        self.stages = match parse::split_pipeline(&self.raw_command, self.status) {
+            Ok(stages) => stages,
+            Err(err) => {
+                err.report();
+                self.status = err.status();
+                Vec::new()
+            }
+        };
+        self.tokens = match self.stages.first() {
+            Some(stage) if self.stages.len() == 1 => stage.words.clone(),
+            _ => Vec::new(),
+        };
     }
 
     pub fn empty(&self) -> bool {
-        return self.tokens.len() == 0;
+        self.tokens.is_empty()
     }
 
     pub fn get_first_token(&self) -> &str {
-        return &self.tokens[0];
+        &self.tokens[0]
+    }
+
+    /// Tokens following the command name.
+    pub fn get_args(&self) -> &[String] {
+        &self.tokens[1..]
     }
 
     pub fn get_tokens_length(&self) -> usize {
-        return self.tokens.len();
+        self.tokens.len()
     }
 
-    pub fn get_chars(&self) -> &Vec<char> {
-        return &self.chars;
+    /// Sets the color policy of the shell, from its `--color` option.
+    pub fn set_color(&mut self, choice: ColorChoice) {
+        self.color = choice;
     }
 
     pub fn display_header(&self) {
+        color::set(self.color);
+        if self.cwd.sync() == CwdState::Deleted {
+            let hint = match self.cwd.nearest_existing() {
+                Some(parent) => format!(", 'cd {}' to leave it", parent.display()),
+                None => String::new(),
+            };
+            color::on_stderr(|| {
+                eprintln!(
+                    "{}",
+                    Role::Warning.paint(&format!(
+                        "Warning: the current directory no longer exists{}",
+                        hint
+                    ))
+                )
+            });
+        }
         print!(
             "{}",
-            format!(" {} ", Local::now().format("%Y-%m-%d %H:%M:%S").to_string())
-                .black()
-                .on_bright_yellow()
+            Role::PromptTime.paint(&format!(" {} ", Local::now().format("%Y-%m-%d %H:%M:%S")))
         );
         print!(
             "{}",
-            format!(" {}$ ", self.current_dir_path.display())
-                .white()
-                .bold()
-                .on_green()
+            Role::PromptCwd.paint(&format!(" {}$ ", self.cwd.path().display()))
         );
         print!("  ");
         io::stdout().flush().unwrap();
     }
 
     pub fn get_token(&self, idx: usize) -> &str {
-        return &self.tokens[idx];
+        &self.tokens[idx]
     }
 
     pub fn get_current_dir_path(&self) -> &PathBuf {
-        return &self.current_dir_path;
+        self.cwd.path()
+    }
+
+    /// Moves the shell and the process to `path_buf`, remembering the old
+    /// directory for `cd -`.
+    pub fn set_current_dir_path(&mut self, path_buf: PathBuf) -> io::Result<()> {
+        self.cwd.change(path_buf)
+    }
+
+    pub fn get_previous_dir_path(&self) -> Option<&PathBuf> {
+        self.cwd.previous()
+    }
+
+    pub fn get_dir_stack(&self) -> &Vec<PathBuf> {
+        &self.dir_stack
+    }
+
+    pub fn get_dir_stack_mut(&mut self) -> &mut Vec<PathBuf> {
+        &mut self.dir_stack
+    }
+
+    /// Resolves an operand against the current directory of the shell.
+    pub fn resolve_path(&self, operand: &str) -> PathBuf {
+        self.cwd.path().join(operand)
+    }
+
+    /// Whether this command is a later stage of a pipeline.
+    pub fn has_piped_input(&self) -> bool {
+        self.piped_input.is_some()
     }
 
-    pub fn set_current_dir_path(&mut self, path_buf: PathBuf) {
-        self.current_dir_path = path_buf;
+    /// Standard input of the command: the output of the previous pipeline
+    /// stage, or the terminal otherwise.
+    pub fn stdin(&self) -> Box<dyn BufRead + '_> {
+        match &self.piped_input {
+            Some(bytes) => Box::new(&bytes[..]),
+            None => Box::new(io::stdin().lock()),
+        }
+    }
+
+    /// The format commands with structured output use unless told otherwise:
+    /// records for a stage that reads them, `CLI_OUTPUT_FORMAT` otherwise.
+    pub fn output_format(&self) -> Format {
+        if self.feeds_records {
+            Format::Ndjson
+        } else {
+            Format::from_env()
+        }
+    }
+
+    /// Opens a file operand for reading, `-` meaning standard input.
+    pub fn open_input(&self, operand: &str) -> io::Result<Box<dyn BufRead + '_>> {
+        if operand == "-" {
+            return Ok(self.stdin());
+        }
+        let path = self.resolve_path(operand);
+        if path.is_dir() {
+            return Err(io::Error::other("Is a directory"));
+        }
+        Ok(Box::new(BufReader::new(File::open(path)?)))
     }
 
     pub fn run_binary(&mut self) {
+        if self.stages.len() > 1 {
+            self.run_pipeline();
+            return;
+        }
         if self.empty() {
             return;
         }
 
         let command = self.get_first_token().to_owned();
-        if &command == "exit" || &command == "quit" {
-            println!("Exiting CLI");
-            std::process::exit(0);
+        if BUILTINS.contains(&command.as_str()) {
+            self.exit();
         }
 
-        match BinEnum::create(&command, self) {
-            Ok(ref mut bin) => match bin.run() {
-                Ok(_) => {}
-                Err(err) => eprintln!("Error: {}", format!("{}", err).red()),
+        // Decided again for every command, since a pipeline stage writes to a file.
+        color::set(self.color);
+        let result = BinEnum::create(&command, self).and_then(|mut bin| bin.run());
+        self.status = match result {
+            Ok(()) => 0,
+            Err(err) => {
+                err.report();
+                err.status()
+            }
+        };
+    }
+
+    /// Leaves the shell with the status given to `exit`, or the one of the
+    /// last command.
+    fn exit(&mut self) {
+        let status = match self.get_args() {
+            [] => self.status,
+            [status] => match status.parse() {
+                Ok(status) => status,
+                Err(_) => {
+                    Error::usage("exit", format!("{}: numeric argument required", status)).report();
+                    2
+                }
             },
-            Err(err) => eprintln!("Error: {}", format!("{}", err).red()),
+            _ => {
+                let err = Error::usage("exit", "too many arguments");
+                err.report();
+                self.status = err.status();
+                return;
+            }
+        };
+        println!("Exiting CLI");
+        std::process::exit(status);
+    }
+
+    /// Runs every stage on its own copy of the shell state, like a subshell,
+    /// feeding the captured output of each stage to the next one.
+    fn run_pipeline(&mut self) {
+        // The theme is global, so a stage switching it is undone afterwards.
+        let theme = theme::current().clone();
+        self.run_stages(&theme);
+        theme::set(theme);
+    }
+
+    fn run_stages(&mut self, theme: &Theme) {
+        let stages = std::mem::take(&mut self.stages);
+        let mut input = None;
+        for (idx, stage) in stages.iter().enumerate() {
+            // A `cd` or `theme` in an earlier stage only changed the process,
+            // not the shell.
+            self.cwd.sync();
+            theme::set(theme.clone());
+            let mut cmd = self.clone();
+            cmd.raw_command = format!("{}\n", stage.raw);
+            cmd.tokens = stage.words.clone();
+            cmd.piped_input = input.take();
+            cmd.feeds_records = stages
+                .get(idx + 1)
+                .and_then(|next| next.words.first())
+                .is_some_and(|next| binaries::reads_records(next));
+
+            if idx + 1 == stages.len() {
+                cmd.run_binary();
+                // Like a shell, the pipeline reports the status of its last stage.
+                self.status = cmd.status;
+                break;
+            }
+
+            let capture = match Capture::start() {
+                Ok(capture) => capture,
+                Err(err) => {
+                    let err = Error::failed("pipe", err.to_string());
+                    err.report();
+                    self.status = err.status();
+                    return;
+                }
+            };
+            cmd.run_binary();
+            match capture.finish() {
+                Ok(bytes) => input = Some(bytes),
+                Err(err) => {
+                    let err = Error::failed("pipe", err.to_string());
+                    err.report();
+                    self.status = err.status();
+                    return;
+                }
+            }
         }
     }
 
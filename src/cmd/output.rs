@@ -0,0 +1,81 @@
+//! Structured output for commands whose results tools want to consume.
+
+use std::env;
+
+use serde::Serialize;
+
+use super::{args::Arg, error::Error};
+
+/// Set to `json` or `ndjson` to make every command that supports it print
+/// records instead of text, as if `--json` or `--ndjson` was given.
+const FORMAT_VAR: &str = "CLI_OUTPUT_FORMAT";
+
+/// How a command prints its results.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The usual human readable, possibly colored, output.
+    #[default]
+    Text,
+    /// One pretty printed JSON array, or a single object for commands that
+    /// only ever print one record.
+    Json,
+    /// One compact JSON object per line.
+    Ndjson,
+}
+
+impl Format {
+    /// The format chosen by `CLI_OUTPUT_FORMAT`, text when unset or unknown.
+    pub fn from_env() -> Self {
+        match env::var(FORMAT_VAR).as_deref() {
+            Ok("json") => Self::Json,
+            Ok("ndjson") => Self::Ndjson,
+            _ => Self::Text,
+        }
+    }
+
+    /// Recognizes the `--json` and `--ndjson` flags.
+    pub fn from_arg(arg: Arg) -> Option<Self> {
+        match arg {
+            Arg::Opt("json") => Some(Self::Json),
+            Arg::Opt("ndjson") => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+
+    pub fn is_text(self) -> bool {
+        self == Self::Text
+    }
+
+    /// Prints `records`, which must not be called in the text format.
+    pub fn print<T: Serialize>(self, command: &str, records: &[T]) -> Result<(), Error> {
+        let encoding_failed = |err: serde_json::Error| Error::failed(command, err.to_string());
+        match self {
+            Self::Ndjson => {
+                for record in records {
+                    println!(
+                        "{}",
+                        serde_json::to_string(record).map_err(encoding_failed)?
+                    );
+                }
+            }
+            _ => println!(
+                "{}",
+                serde_json::to_string_pretty(records).map_err(encoding_failed)?
+            ),
+        }
+        Ok(())
+    }
+
+    /// Prints the only record of a command such as `pwd`.
+    pub fn print_one<T: Serialize>(self, command: &str, record: &T) -> Result<(), Error> {
+        let text = match self {
+            Self::Ndjson => serde_json::to_string(record),
+            _ => serde_json::to_string_pretty(record),
+        };
+        println!(
+            "{}",
+            text.map_err(|err| Error::failed(command, err.to_string()))?
+        );
+        Ok(())
+    }
+}
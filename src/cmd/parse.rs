@@ -0,0 +1,169 @@
+use super::error::Error;
+
+/// One command of a pipeline: its words and the text it was parsed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stage {
+    pub raw: String,
+    pub words: Vec<String>,
+}
+
+/// Splits a command line into the stages of a pipeline.
+///
+/// Words are separated by whitespace. Single quotes keep everything
+/// literal, double quotes only treat `\"`, `\\` and `\$` as escapes, and a
+/// backslash outside quotes escapes the next character. An unquoted `|`
+/// ends the current stage, so `ls|wc -l` needs no spaces around it.
+/// Outside single quotes `$?` expands to `status`, the exit status of the
+/// previous command.
+pub fn split_pipeline(line: &str, status: i32) -> Result<Vec<Stage>, Error> {
+    let mut stages = Vec::new();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut stage_start = 0;
+
+    let mut chars = line.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '|' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                if words.is_empty() {
+                    return Err(Error::usage("sh", "syntax error near unexpected token '|'"));
+                }
+                stages.push(Stage {
+                    raw: line[stage_start..idx].trim().to_string(),
+                    words: std::mem::take(&mut words),
+                });
+                stage_start = idx + 1;
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => word.push(c),
+                        None => {
+                            return Err(Error::usage("sh", "unexpected end of line: missing '"))
+                        }
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.peek() {
+                            Some(&(_, next @ ('"' | '\\' | '$'))) => {
+                                word.push(next);
+                                chars.next();
+                            }
+                            _ => word.push('\\'),
+                        },
+                        Some((_, '$')) if matches!(chars.peek(), Some((_, '?'))) => {
+                            chars.next();
+                            word.push_str(&status.to_string());
+                        }
+                        Some((_, c)) => word.push(c),
+                        None => {
+                            return Err(Error::usage("sh", "unexpected end of line: missing \""))
+                        }
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some((_, next)) = chars.next() {
+                    word.push(next);
+                }
+            }
+            '$' if matches!(chars.peek(), Some((_, '?'))) => {
+                in_word = true;
+                chars.next();
+                word.push_str(&status.to_string());
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+    if words.is_empty() {
+        if !stages.is_empty() {
+            return Err(Error::usage(
+                "sh",
+                "syntax error: missing command after '|'",
+            ));
+        }
+        return Ok(stages);
+    }
+    stages.push(Stage {
+        raw: line[stage_start..].trim().to_string(),
+        words,
+    });
+    Ok(stages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<Vec<String>> {
+        split_pipeline(line, 3)
+            .unwrap()
+            .into_iter()
+            .map(|stage| stage.words)
+            .collect()
+    }
+
+    #[test]
+    fn lines_are_split_into_stages_and_words() {
+        let cases: [(&str, &[&[&str]]); 9] = [
+            ("", &[]),
+            ("   ", &[]),
+            ("ls  -l", &[&["ls", "-l"]]),
+            ("ls|wc -l", &[&["ls"], &["wc", "-l"]]),
+            ("echo 'a | b' \"c|d\"", &[&["echo", "a | b", "c|d"]]),
+            ("echo a\\|b | cat", &[&["echo", "a|b"], &["cat"]]),
+            (
+                "echo 'it''s' \"a\\\"b\\\\c\\n\"",
+                &[&["echo", "its", "a\"b\\c\\n"]],
+            ),
+            (
+                "echo $? \"$?\" '$?' \\$?",
+                &[&["echo", "3", "3", "$?", "$?"]],
+            ),
+            ("echo '' \"\"", &[&["echo", "", ""]]),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(words(line), expected, "line '{}'", line);
+        }
+    }
+
+    #[test]
+    fn stages_keep_their_text() {
+        let stages = split_pipeline("ls -l  |  sort -k5n", 0).unwrap();
+        let raw: Vec<&str> = stages.iter().map(|stage| stage.raw.as_str()).collect();
+        assert_eq!(raw, ["ls -l", "sort -k5n"]);
+    }
+
+    #[test]
+    fn empty_stages_and_open_quotes_are_rejected() {
+        for line in ["| wc", "ls | | wc", "ls |", "ls ||", "echo 'a", "echo \"a"] {
+            assert!(split_pipeline(line, 0).is_err(), "'{}' was accepted", line);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::fd::AsRawFd,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Redirects the standard output of the process into an anonymous temporary
+/// file, so the output of one pipeline stage can become the input of the next.
+///
+/// The binaries keep writing to `io::stdout()` as usual; `finish` restores
+/// the original descriptor and returns everything that was written meanwhile.
+pub struct Capture {
+    file: File,
+    saved_fd: libc::c_int,
+}
+
+impl Capture {
+    pub fn start() -> io::Result<Self> {
+        let path = env::temp_dir().join(format!(
+            "cli-pipe-{}-{}",
+            process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // The descriptor stays usable after the name is gone.
+        fs::remove_file(&path)?;
+
+        io::stdout().flush()?;
+        let saved_fd = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if saved_fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if unsafe { libc::dup2(file.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            let err = io::Error::last_os_error();
+            unsafe { libc::close(saved_fd) };
+            return Err(err);
+        }
+        Ok(Self { file, saved_fd })
+    }
+
+    pub fn finish(mut self) -> io::Result<Vec<u8>> {
+        self.restore()?;
+        let mut bytes = Vec::new();
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn restore(&mut self) -> io::Result<()> {
+        if self.saved_fd < 0 {
+            return Ok(());
+        }
+        let flushed = io::stdout().flush();
+        let restored = unsafe { libc::dup2(self.saved_fd, libc::STDOUT_FILENO) };
+        unsafe { libc::close(self.saved_fd) };
+        self.saved_fd = -1;
+        if restored < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        flushed
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
@@ -0,0 +1,361 @@
+//! What a command accepts: its options, how many operands it takes and its
+//! subcommands, declared once next to the binary.
+//!
+//! The `Parser` checks the tokens against the spec, so unknown options and
+//! wrong operand counts get the same message everywhere, and the same spec
+//! gives `--help`, the OPTIONS of the manual page and the candidates of
+//! `complete`.
+
+/// Whether an option takes a value, named for the help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    None,
+    /// `-n 5`, `-n5`, `--lines 5` or `--lines=5`.
+    Required(&'static str),
+    /// Only attached, as in `-i.bak` or `--color=always`.
+    Optional(&'static str),
+}
+
+#[derive(Debug)]
+pub struct Opt {
+    /// Every short name, e.g. "fF" for `-f` and `-F`.
+    pub shorts: &'static str,
+    pub longs: &'static [&'static str],
+    pub value: Value,
+    pub help: &'static str,
+}
+
+impl Opt {
+    pub const fn new(
+        shorts: &'static str,
+        longs: &'static [&'static str],
+        help: &'static str,
+    ) -> Self {
+        Self {
+            shorts,
+            longs,
+            value: Value::None,
+            help,
+        }
+    }
+
+    pub const fn value(mut self, name: &'static str) -> Self {
+        self.value = Value::Required(name);
+        self
+    }
+
+    pub const fn optional_value(mut self, name: &'static str) -> Self {
+        self.value = Value::Optional(name);
+        self
+    }
+
+    /// The name the parser reports the option by, whichever of its names was
+    /// given: the first long one, or the first short one without any.
+    pub fn name(&self) -> &'static str {
+        match self.longs.first() {
+            Some(long) => long,
+            None => {
+                let shorts = self.shorts;
+                let end = shorts.chars().next().map_or(0, char::len_utf8);
+                &shorts[..end]
+            }
+        }
+    }
+
+    fn requires_value(&self) -> bool {
+        matches!(self.value, Value::Required(_))
+    }
+
+    /// Every name with its dashes, shorts first.
+    pub fn flags(&self) -> Vec<String> {
+        let shorts = self.shorts.chars().map(|c| format!("-{}", c));
+        let longs = self.longs.iter().map(|long| format!("--{}", long));
+        shorts.chain(longs).collect()
+    }
+
+    /// The names as help and manual pages show them, e.g. `-n, --lines lines`.
+    pub fn synopsis(&self) -> String {
+        let mut flags = self.flags();
+        match self.value {
+            Value::None => {}
+            Value::Required(name) => {
+                if let Some(last) = flags.last_mut() {
+                    last.push(' ');
+                    last.push_str(name);
+                }
+            }
+            Value::Optional(name) => {
+                for flag in flags.iter_mut() {
+                    let separator = if flag.starts_with("--") { "=" } else { "" };
+                    flag.push_str(&format!("[{}{}]", separator, name));
+                }
+            }
+        }
+        flags.join(", ")
+    }
+}
+
+/// How many operands a command takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operands {
+    pub min: usize,
+    /// `None` for no limit.
+    pub max: Option<usize>,
+}
+
+impl Operands {
+    pub const ANY: Self = Self { min: 0, max: None };
+    pub const NONE: Self = Self::exactly(0);
+
+    pub const fn at_least(min: usize) -> Self {
+        Self { min, max: None }
+    }
+
+    pub const fn at_most(max: usize) -> Self {
+        Self {
+            min: 0,
+            max: Some(max),
+        }
+    }
+
+    pub const fn exactly(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Spec {
+    pub name: &'static str,
+    /// Other names of a subcommand, such as `insert` for `todo add`.
+    pub aliases: &'static [&'static str],
+    /// A few words on what the command does.
+    pub summary: &'static str,
+    /// One line per form of the command, each starting with its name.
+    pub usage: &'static [&'static str],
+    pub options: &'static [Opt],
+    pub operands: Operands,
+    /// Chosen by the first operand, as in `todo add`.
+    pub subcommands: &'static [Spec],
+}
+
+/// What the word being completed can be.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Completion {
+    /// One of these options or subcommands.
+    Words(Vec<String>),
+    /// An operand, usually a file.
+    Operand,
+    /// The value of an option, which nothing is known about.
+    Value,
+}
+
+/// Help lines are filled to this width.
+const HELP_WIDTH: usize = 80;
+/// Where the explanations of options start in the help.
+const HELP_COLUMN: usize = 26;
+
+impl Spec {
+    pub const fn new(name: &'static str, summary: &'static str) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            summary,
+            usage: &[],
+            options: &[],
+            operands: Operands::ANY,
+            subcommands: &[],
+        }
+    }
+
+    pub const fn aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    pub const fn usage(mut self, usage: &'static [&'static str]) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub const fn options(mut self, options: &'static [Opt]) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub const fn operands(mut self, operands: Operands) -> Self {
+        self.operands = operands;
+        self
+    }
+
+    pub const fn subcommands(mut self, subcommands: &'static [Spec]) -> Self {
+        self.subcommands = subcommands;
+        self
+    }
+
+    /// The name followed by the aliases.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        [self.name].into_iter().chain(self.aliases.iter().copied())
+    }
+
+    pub fn short(&self, c: char) -> Option<&Opt> {
+        self.options.iter().find(|opt| opt.shorts.contains(c))
+    }
+
+    pub fn long(&self, name: &str) -> Option<&Opt> {
+        self.options.iter().find(|opt| opt.longs.contains(&name))
+    }
+
+    pub fn subcommand(&self, name: &str) -> Option<&Spec> {
+        self.subcommands
+            .iter()
+            .find(|sub| sub.name == name || sub.aliases.contains(&name))
+    }
+
+    /// The text printed for `--help`, `parent` being the command a
+    /// subcommand belongs to.
+    pub fn help(&self, parent: Option<&str>) -> String {
+        let name = match parent {
+            Some(parent) => format!("{} {}", parent, self.name),
+            None => self.name.to_string(),
+        };
+        let mut out = format!("{} - {}\n\n", name, self.summary);
+        for (idx, line) in self.usage.iter().enumerate() {
+            let label = if idx == 0 { "Usage:" } else { "" };
+            out.push_str(&format!("{:<7}{}\n", label, line));
+        }
+
+        if !self.subcommands.is_empty() {
+            out.push_str("\nCommands:\n");
+            for sub in self.subcommands {
+                let names = sub.names().collect::<Vec<_>>().join(", ");
+                item(&mut out, &names, sub.summary);
+            }
+        }
+
+        out.push_str("\nOptions:\n");
+        for opt in self.options {
+            item(&mut out, &opt.synopsis(), opt.help);
+        }
+        item(
+            &mut out,
+            "--color[=when]",
+            "Use colors always, never or on terminals (auto).",
+        );
+        item(&mut out, "--help", "Show this help.");
+        out.push_str(&format!(
+            "\nSee 'man {}' for more.\n",
+            parent.unwrap_or(self.name)
+        ));
+        out
+    }
+
+    /// Candidates for the last of `words`, the operands typed so far
+    /// after the command name.
+    pub fn complete(&self, words: &[&str]) -> Completion {
+        let Some((word, before)) = words.split_last() else {
+            return Completion::Operand;
+        };
+
+        let mut operands = Vec::new();
+        let mut only_operands = false;
+        let mut idx = 0;
+        while idx < before.len() {
+            let token = before[idx];
+            idx += 1;
+            if only_operands || token == "-" || !token.starts_with('-') {
+                if operands.is_empty() {
+                    if let Some(sub) = self.subcommand(token) {
+                        return sub.complete(&words[idx..]);
+                    }
+                }
+                operands.push(token);
+                continue;
+            }
+            if token == "--" {
+                only_operands = true;
+                continue;
+            }
+            // Whether the next token is the value of this option.
+            let takes_next = match token.strip_prefix("--") {
+                Some(long) => {
+                    !long.contains('=') && self.long(long).is_some_and(Opt::requires_value)
+                }
+                None => {
+                    let shorts = &token[1..];
+                    shorts
+                        .char_indices()
+                        .find(|&(_, c)| self.short(c).is_some_and(Opt::requires_value))
+                        .is_some_and(|(pos, c)| pos + c.len_utf8() == shorts.len())
+                }
+            };
+            if takes_next {
+                if idx == before.len() {
+                    return Completion::Value;
+                }
+                idx += 1;
+            }
+        }
+
+        if !only_operands && word.starts_with('-') {
+            let mut flags: Vec<String> = self.options.iter().flat_map(Opt::flags).collect();
+            flags.push("--color".to_string());
+            flags.push("--help".to_string());
+            flags.retain(|flag| flag.starts_with(word));
+            return Completion::Words(flags);
+        }
+        if operands.is_empty() && !self.subcommands.is_empty() {
+            let names = self
+                .subcommands
+                .iter()
+                .flat_map(Spec::names)
+                .filter(|name| name.starts_with(word))
+                .map(String::from)
+                .collect();
+            return Completion::Words(names);
+        }
+        Completion::Operand
+    }
+}
+
+/// An option or subcommand of the help, with its explanation in a column.
+fn item(out: &mut String, names: &str, help: &str) {
+    let first = if names.len() + 4 <= HELP_COLUMN {
+        format!("  {:<1$}", names, HELP_COLUMN - 2)
+    } else {
+        out.push_str(&format!("  {}\n", names));
+        " ".repeat(HELP_COLUMN)
+    };
+    fill(out, &first, help, HELP_COLUMN, HELP_WIDTH);
+}
+
+/// Appends `text` with its words filled into lines of `width` columns, the
+/// first line starting with `first` and the others indented by `indent`.
+pub fn fill(out: &mut String, first: &str, text: &str, indent: usize, width: usize) {
+    let mut line = first.to_string();
+    let mut length = first.chars().count();
+    let mut empty = true;
+    for word in text.split_whitespace() {
+        let extra = word.chars().count() + usize::from(!empty);
+        if !empty && length + extra > width {
+            out.push_str(&line);
+            out.push('\n');
+            line = " ".repeat(indent);
+            length = indent;
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+            length += 1;
+        }
+        line.push_str(word);
+        length += word.chars().count();
+        empty = false;
+    }
+    if !empty {
+        out.push_str(&line);
+        out.push('\n');
+    }
+}
@@ -0,0 +1,196 @@
+//! Line diffing shared by the `diff` and `patch` binaries.
+
+pub mod unified;
+
+/// One step of an edit script turning `old` into `new`, holding indexes into
+/// the two sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Computes a shortest edit script with Myers' algorithm, using the linear
+/// space variant that recursively splits the problem at a middle snake.
+pub fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    diff_range(old, new, 0, 0, &mut edits);
+    // Within a block of changes, list the deletions before the insertions.
+    for block in edits.split_mut(|edit| matches!(edit, Edit::Equal(..))) {
+        block.sort_by_key(|edit| matches!(edit, Edit::Insert(_)));
+    }
+    edits
+}
+
+fn diff_range<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    old_offset: usize,
+    new_offset: usize,
+    edits: &mut Vec<Edit>,
+) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    for idx in 0..prefix {
+        edits.push(Edit::Equal(old_offset + idx, new_offset + idx));
+    }
+    let (old_rest, new_rest) = (&old[prefix..], &new[prefix..]);
+    let suffix = old_rest
+        .iter()
+        .rev()
+        .zip(new_rest.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_rest[..old_rest.len() - suffix];
+    let new_mid = &new_rest[..new_rest.len() - suffix];
+    let (old_start, new_start) = (old_offset + prefix, new_offset + prefix);
+
+    if old_mid.is_empty() {
+        edits.extend((0..new_mid.len()).map(|idx| Edit::Insert(new_start + idx)));
+    } else if new_mid.is_empty() {
+        edits.extend((0..old_mid.len()).map(|idx| Edit::Delete(old_start + idx)));
+    } else {
+        let (x, y) = middle_snake(old_mid, new_mid);
+        if (x, y) == (0, 0) || (x, y) == (old_mid.len(), new_mid.len()) {
+            // Cannot happen for a correct split, but never recurse forever.
+            edits.extend((0..old_mid.len()).map(|idx| Edit::Delete(old_start + idx)));
+            edits.extend((0..new_mid.len()).map(|idx| Edit::Insert(new_start + idx)));
+        } else {
+            diff_range(&old_mid[..x], &new_mid[..y], old_start, new_start, edits);
+            diff_range(
+                &old_mid[x..],
+                &new_mid[y..],
+                old_start + x,
+                new_start + y,
+                edits,
+            );
+        }
+    }
+
+    let (old_end, new_end) = (old_start + old_mid.len(), new_start + new_mid.len());
+    for idx in 0..suffix {
+        edits.push(Edit::Equal(old_end + idx, new_end + idx));
+    }
+}
+
+/// Finds a point on an optimal edit path by running the greedy search from
+/// both ends until the paths overlap.
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> (usize, usize) {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    let at = |k: isize| (k + offset) as usize;
+    // Furthest x reached on each diagonal, going forward from (0, 0) and
+    // backward from (n, m) in reversed coordinates.
+    let mut forward = vec![0isize; (2 * offset + 1) as usize];
+    let mut backward = vec![0isize; (2 * offset + 1) as usize];
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y >= 0 && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            if odd && (delta - k).abs() < d && x + backward[at(delta - k)] >= n {
+                return (x as usize, y as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y >= 0 && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize]
+            {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            if !odd && (delta - k).abs() <= d && x + forward[at(delta - k)] >= n {
+                return ((n - x) as usize, (m - y) as usize);
+            }
+        }
+    }
+    (old.len(), new.len())
+}
+
+/// A group of nearby changes with the surrounding context lines.
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    /// Zero-based index of the first old line covered by the hunk.
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub edits: Vec<Edit>,
+}
+
+/// Groups an edit script into hunks, keeping `context` equal lines around
+/// each change and merging changes whose context would overlap.
+pub fn hunks(edits: &[Edit], context: usize) -> Vec<Hunk> {
+    // Number of old and new lines consumed before each edit.
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for edit in edits {
+        positions.push((old_pos, new_pos));
+        match edit {
+            Edit::Equal(..) => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Edit::Delete(_) => old_pos += 1,
+            Edit::Insert(_) => new_pos += 1,
+        }
+    }
+    positions.push((old_pos, new_pos));
+
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(..)))
+        .map(|(idx, _)| idx)
+        .collect();
+
+    let mut hunks = Vec::new();
+    let mut idx = 0;
+    while idx < changes.len() {
+        let first = changes[idx];
+        let mut last = first;
+        idx += 1;
+        while idx < changes.len() && changes[idx] - last - 1 <= 2 * context {
+            last = changes[idx];
+            idx += 1;
+        }
+
+        let start = first.saturating_sub(context);
+        let end = (last + context + 1).min(edits.len());
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        hunks.push(Hunk {
+            old_start,
+            old_len: old_end - old_start,
+            new_start,
+            new_len: new_end - new_start,
+            edits: edits[start..end].to_vec(),
+        });
+    }
+    hunks
+}
+
+/// Splits text into lines that keep their terminating newline, so a missing
+/// newline at the end of a file shows up as a difference.
+pub fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
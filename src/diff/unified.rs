@@ -0,0 +1,238 @@
+//! The unified diff format: hunk headers for `diff -u` and parsing for `patch`.
+
+use super::Hunk;
+
+/// `start,len` as printed in a hunk header. An empty range names the line
+/// before it, and a length of one is left out.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+pub fn hunk_header(hunk: &Hunk) -> String {
+    format!(
+        "@@ -{} +{} @@",
+        range(hunk.old_start, hunk.old_len),
+        range(hunk.new_start, hunk.new_len)
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+impl PatchLine {
+    fn text_mut(&mut self) -> &mut String {
+        match self {
+            PatchLine::Context(text) | PatchLine::Remove(text) | PatchLine::Add(text) => text,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PatchHunk {
+    /// One-based line numbers from the header.
+    pub old_start: usize,
+    pub new_start: usize,
+    pub lines: Vec<PatchLine>,
+}
+
+impl PatchHunk {
+    /// Lines the hunk expects to find in the file, with their newlines.
+    pub fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                PatchLine::Context(text) | PatchLine::Remove(text) => Some(text.as_str()),
+                PatchLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                PatchLine::Context(text) | PatchLine::Add(text) => Some(text.as_str()),
+                PatchLine::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn reversed(&self) -> Self {
+        Self {
+            old_start: self.new_start,
+            new_start: self.old_start,
+            lines: self
+                .lines
+                .iter()
+                .map(|line| match line {
+                    PatchLine::Context(text) => PatchLine::Context(text.clone()),
+                    PatchLine::Remove(text) => PatchLine::Add(text.clone()),
+                    PatchLine::Add(text) => PatchLine::Remove(text.clone()),
+                })
+                .collect(),
+        }
+    }
+
+    /// The hunk in unified format, as written to a reject file.
+    pub fn render(&self) -> String {
+        let old_len = self.old_lines().len();
+        let new_len = self.new_lines().len();
+        let mut text = format!(
+            "@@ -{} +{} @@\n",
+            range(self.old_start.saturating_sub(1), old_len),
+            range(self.new_start.saturating_sub(1), new_len)
+        );
+        for line in &self.lines {
+            let (marker, content) = match line {
+                PatchLine::Context(content) => (' ', content),
+                PatchLine::Remove(content) => ('-', content),
+                PatchLine::Add(content) => ('+', content),
+            };
+            text.push(marker);
+            text.push_str(content);
+            if !content.ends_with('\n') {
+                text.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        text
+    }
+}
+
+/// The changes to one file.
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    pub old_name: String,
+    pub new_name: String,
+    pub hunks: Vec<PatchHunk>,
+}
+
+fn file_name(header: &str) -> String {
+    let name = header.trim_end_matches(['\n', '\r']);
+    // A timestamp may follow the name, separated by a tab.
+    let name = name.split('\t').next().unwrap_or(name);
+    name.trim_end().to_string()
+}
+
+/// Parses `-a[,b] +c[,d]` out of a hunk header.
+fn parse_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, _) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let parse = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = parse(old)?;
+    let (new_start, new_len) = parse(new)?;
+    Some((old_start, old_len, new_start, new_len))
+}
+
+/// Parses every file section of a unified diff. Text between sections, such
+/// as `diff -r` command lines, is skipped.
+pub fn parse(text: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        if line.starts_with("--- ")
+            && lines
+                .get(idx + 1)
+                .is_some_and(|next| next.starts_with("+++ "))
+        {
+            patches.push(FilePatch {
+                old_name: file_name(&line[4..]),
+                new_name: file_name(&lines[idx + 1][4..]),
+                hunks: Vec::new(),
+            });
+            idx += 2;
+            continue;
+        }
+
+        if line.starts_with("@@ ") {
+            let Some(patch) = patches.last_mut() else {
+                return Err(format!("hunk without file header at line {}", idx + 1));
+            };
+            let (old_start, mut old_left, new_start, mut new_left) = parse_header(line)
+                .ok_or_else(|| format!("malformed hunk header at line {}", idx + 1))?;
+            let mut hunk = PatchHunk {
+                old_start,
+                new_start,
+                lines: Vec::new(),
+            };
+            idx += 1;
+            while old_left > 0 || new_left > 0 {
+                let Some(&line) = lines.get(idx) else {
+                    return Err(format!("unexpected end of hunk at line {}", idx + 1));
+                };
+                let (marker, content) = match line.char_indices().nth(1) {
+                    Some((split, _)) => (&line[..split], &line[split..]),
+                    None => (line.trim_end_matches('\n'), "\n"),
+                };
+                let parsed = match marker {
+                    // Some editors strip the space of empty context lines.
+                    " " | "" | "\n" => {
+                        old_left = old_left.saturating_sub(1);
+                        new_left = new_left.saturating_sub(1);
+                        PatchLine::Context(content.to_string())
+                    }
+                    "-" if old_left > 0 => {
+                        old_left -= 1;
+                        PatchLine::Remove(content.to_string())
+                    }
+                    "+" if new_left > 0 => {
+                        new_left -= 1;
+                        PatchLine::Add(content.to_string())
+                    }
+                    "\\" => {
+                        if let Some(previous) = hunk.lines.last_mut() {
+                            let text = previous.text_mut();
+                            if text.ends_with('\n') {
+                                text.pop();
+                            }
+                        }
+                        idx += 1;
+                        continue;
+                    }
+                    _ => {
+                        return Err(format!(
+                            "malformed patch at line {}: {}",
+                            idx + 1,
+                            line.trim_end()
+                        ))
+                    }
+                };
+                hunk.lines.push(parsed);
+                idx += 1;
+            }
+            // The marker for the last line comes after the counts ran out.
+            if lines.get(idx).is_some_and(|line| line.starts_with('\\')) {
+                if let Some(previous) = hunk.lines.last_mut() {
+                    let text = previous.text_mut();
+                    if text.ends_with('\n') {
+                        text.pop();
+                    }
+                }
+                idx += 1;
+            }
+            patch.hunks.push(hunk);
+            continue;
+        }
+        idx += 1;
+    }
+
+    if patches.is_empty() {
+        return Err("only garbage was found in the patch input".to_string());
+    }
+    Ok(patches)
+}
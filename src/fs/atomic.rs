@@ -0,0 +1,39 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::Path,
+    process,
+};
+
+/// Replaces the contents of `path` without ever leaving it truncated: the new
+/// contents are written to a temporary file in the same directory, synced,
+/// and renamed over the original. Permissions of an existing file are kept.
+pub fn replace<T>(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<T>,
+) -> io::Result<T> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = dir.join(format!(".{}.tmp{}", name, process::id()));
+    let temp = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)?;
+
+    let result = (|| {
+        let mut out = BufWriter::new(temp);
+        let value = write(&mut out)?;
+        out.flush()?;
+        let temp = out.into_inner().map_err(|err| err.into_error())?;
+        if let Ok(metadata) = fs::metadata(path) {
+            temp.set_permissions(metadata.permissions())?;
+        }
+        temp.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(value)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
@@ -0,0 +1,92 @@
+use std::time::SystemTime;
+
+use chrono::{DateTime, Duration, Local};
+
+const SIZE_UNITS: [char; 6] = ['K', 'M', 'G', 'T', 'P', 'E'];
+
+/// Formats a byte count the way `ls -h` does: `512`, `4.0K`, `13M`.
+pub fn human_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if size < 10.0 {
+        format!("{:.1}{}", (size * 10.0).ceil() / 10.0, SIZE_UNITS[unit])
+    } else {
+        format!("{}{}", size.ceil() as u64, SIZE_UNITS[unit])
+    }
+}
+
+/// Parses a size argument such as `20`, `4K`, `2M` or `1G` (powers of 1024).
+/// A `b` suffix counts 512 byte blocks, like `head -c` accepts.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let (digits, multiplier) = match text.char_indices().last()? {
+        (idx, 'b') => (&text[..idx], 512),
+        (idx, unit) if unit.is_ascii_alphabetic() => {
+            let power = SIZE_UNITS
+                .iter()
+                .position(|&u| u == unit.to_ascii_uppercase())?;
+            (&text[..idx], 1024u64.pow(power as u32 + 1))
+        }
+        _ => (text, 1),
+    };
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Renders `st_mode` as the ten character string shown by `ls -l`, e.g. `drwxr-xr-x`.
+pub fn mode_string(mode: u32) -> String {
+    let file_type = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '-',
+    };
+
+    let mut out = String::with_capacity(10);
+    out.push(file_type);
+
+    let special = [(0o4000, 's', 'S'), (0o2000, 's', 'S'), (0o1000, 't', 'T')];
+    for (idx, shift) in [6, 3, 0].iter().enumerate() {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+
+        let (flag, with_exec, without_exec) = special[idx];
+        let exec = bits & 0o1 != 0;
+        out.push(match (mode & flag != 0, exec) {
+            (true, true) => with_exec,
+            (true, false) => without_exec,
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+/// Formats a timestamp like `ls -l`: the time of day for recent files, the year otherwise.
+pub fn listing_time(time: SystemTime) -> String {
+    let time: DateTime<Local> = time.into();
+    let now = Local::now();
+    if now.signed_duration_since(time) > Duration::days(182) || time > now {
+        time.format("%b %e  %Y").to_string()
+    } else {
+        time.format("%b %e %H:%M").to_string()
+    }
+}
+
+/// Formats a timestamp with nanoseconds and the UTC offset, as `stat` and
+/// `diff -u` print them: `2024-03-01 14:05:09.123456789 +0100`.
+pub fn full_time(time: SystemTime) -> String {
+    let time: DateTime<Local> = time.into();
+    time.format("%Y-%m-%d %H:%M:%S%.9f %z").to_string()
+}
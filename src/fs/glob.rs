@@ -0,0 +1,84 @@
+/// Matches `text` against a shell glob.
+///
+/// Supports `*` and `?` (which never match `/`), `**` (which does), character
+/// classes such as `[abc]`, `[a-z]` and `[!a]`, and `\` to escape the next character.
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text)
+}
+
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` may also stand for no directory at all.
+            if rest.first() == Some(&'/') && match_from(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|idx| match_from(rest, &text[idx..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for idx in 0..=text.len() {
+                if match_from(rest, &text[idx..]) {
+                    return true;
+                }
+                if idx < text.len() && text[idx] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => match text.first() {
+            Some(&c) if c != '/' => match_from(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some('[') => match (match_class(pattern, text.first()), text.first()) {
+            (Some((matched, len)), Some(_)) => matched && match_from(&pattern[len..], &text[1..]),
+            (Some(_), None) => false,
+            // An unterminated class is taken literally.
+            (None, _) => text.first() == Some(&'[') && match_from(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && match_from(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && match_from(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Evaluates the class starting at `pattern[0] == '['` against `c`.
+/// Returns whether it matched and how many pattern characters the class spans.
+fn match_class(pattern: &[char], c: Option<&char>) -> Option<(bool, usize)> {
+    let mut idx = 1;
+    let negated = matches!(pattern.get(idx), Some('!') | Some('^'));
+    if negated {
+        idx += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let current = *pattern.get(idx)?;
+        if current == ']' && !first {
+            break;
+        }
+        first = false;
+
+        if pattern.get(idx + 1) == Some(&'-') && pattern.get(idx + 2).is_some_and(|&end| end != ']')
+        {
+            let end = pattern[idx + 2];
+            if c.is_some_and(|&c| current <= c && c <= end) {
+                matched = true;
+            }
+            idx += 3;
+        } else {
+            if c == Some(&current) {
+                matched = true;
+            }
+            idx += 1;
+        }
+    }
+    Some((matched != negated, idx + 1))
+}
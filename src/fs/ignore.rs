@@ -0,0 +1,191 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::glob;
+
+/// A single line of an ignore file.
+#[derive(Debug)]
+struct Rule {
+    /// Directory the pattern is relative to.
+    base: PathBuf,
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    /// Patterns containing a `/` (other than a trailing one) only match
+    /// relative to `base`; the others match a name at any depth.
+    anchored: bool,
+}
+
+impl Rule {
+    /// Parses one line with gitignore semantics. Returns `None` for blank lines and comments.
+    fn parse(base: &Path, line: &str) -> Option<Self> {
+        let mut line = line.trim_end_matches(['\r', '\n']);
+        // Trailing spaces are dropped unless escaped with a backslash.
+        while line.ends_with(' ') && !line.ends_with("\\ ") {
+            line = &line[..line.len() - 1];
+        }
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let negated = line.starts_with('!');
+        let mut pattern = if negated { &line[1..] } else { line };
+        if pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+            pattern = &pattern[1..];
+        }
+
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+
+        Some(Self {
+            base: base.to_path_buf(),
+            pattern: pattern.to_string(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            match path.strip_prefix(&self.base) {
+                Ok(relative) => glob::matches(&self.pattern, &relative.to_string_lossy()),
+                Err(_) => false,
+            }
+        } else {
+            match path.file_name() {
+                Some(name) => glob::matches(&self.pattern, &name.to_string_lossy()),
+                None => false,
+            }
+        }
+    }
+}
+
+/// Outcome of checking a path against the ignore rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Match {
+    None,
+    Ignore,
+    /// Re-included by a `!pattern`.
+    Whitelist,
+}
+
+/// Ignore rules in effect for one directory.
+///
+/// Every directory level gets its own `Ignore` that links to the one of its
+/// parent, so rules from deeper files take precedence like they do in git.
+/// Within a level `.ignore` wins over `.gitignore`, and the global excludes
+/// file has the lowest precedence of all.
+#[derive(Debug, Default)]
+pub struct Ignore {
+    parent: Option<Arc<Ignore>>,
+    rules: Vec<Rule>,
+}
+
+impl Ignore {
+    /// Rules that apply everywhere: the user's global excludes file, i.e.
+    /// `core.excludesFile` or `$XDG_CONFIG_HOME/git/ignore`.
+    pub fn global() -> Arc<Self> {
+        let mut ignore = Self::default();
+        if let Some(path) = Self::global_excludes_path() {
+            if let Some(base) = path.parent() {
+                // Anchored global patterns are relative to each repository root,
+                // which is not tracked here, so only name patterns are kept.
+                if let Ok(data) = fs::read_to_string(&path) {
+                    for line in data.lines() {
+                        if let Some(mut rule) = Rule::parse(base, line) {
+                            rule.anchored = false;
+                            rule.pattern = rule.pattern.trim_start_matches("**/").to_string();
+                            if !rule.pattern.contains('/') {
+                                ignore.rules.push(rule);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Arc::new(ignore)
+    }
+
+    fn global_excludes_path() -> Option<PathBuf> {
+        let home = env::var("HOME").ok().map(PathBuf::from);
+        if let Some(home) = home.as_ref() {
+            if let Ok(config) = fs::read_to_string(home.join(".gitconfig")) {
+                let mut in_core = false;
+                for line in config.lines() {
+                    let line = line.trim();
+                    if line.starts_with('[') {
+                        in_core = line.eq_ignore_ascii_case("[core]");
+                    } else if in_core {
+                        if let Some((key, value)) = line.split_once('=') {
+                            if key.trim().eq_ignore_ascii_case("excludesfile") {
+                                let value = value.trim().trim_matches('"');
+                                return Some(match value.strip_prefix("~/") {
+                                    Some(rest) => home.join(rest),
+                                    None => PathBuf::from(value),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        match env::var("XDG_CONFIG_HOME") {
+            Ok(config) if !config.is_empty() => Some(PathBuf::from(config).join("git/ignore")),
+            _ => home.map(|home| home.join(".config/git/ignore")),
+        }
+    }
+
+    /// Rules for `dir`: the parent's plus the ones from `dir/.gitignore`,
+    /// `dir/.ignore` and, at a repository root, `.git/info/exclude`.
+    pub fn child(parent: &Arc<Self>, dir: &Path) -> Arc<Self> {
+        let mut ignore = Self {
+            parent: Some(Arc::clone(parent)),
+            rules: Vec::new(),
+        };
+        for file in [".git/info/exclude", ".gitignore", ".ignore"] {
+            if let Ok(data) = fs::read_to_string(dir.join(file)) {
+                ignore
+                    .rules
+                    .extend(data.lines().filter_map(|line| Rule::parse(dir, line)));
+            }
+        }
+        Arc::new(ignore)
+    }
+
+    /// The last matching rule decides, starting from the deepest level.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Match {
+        if let Some(rule) = self
+            .rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+        {
+            return if rule.negated {
+                Match::Whitelist
+            } else {
+                Match::Ignore
+            };
+        }
+        match self.parent.as_ref() {
+            Some(parent) => parent.matched(path, is_dir),
+            None => Match::None,
+        }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.matched(path, is_dir) == Match::Ignore
+    }
+}
@@ -0,0 +1,10 @@
+pub mod atomic;
+pub mod format;
+pub mod glob;
+pub mod ignore;
+pub mod path;
+pub mod record;
+#[cfg(test)]
+pub mod temp;
+pub mod users;
+pub mod walk;
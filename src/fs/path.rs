@@ -0,0 +1,141 @@
+use std::{
+    collections::VecDeque,
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use super::users::Users;
+
+/// Symlinks followed before giving up, the same limit the kernel uses.
+const MAX_LINKS: usize = 40;
+
+/// Which components may be missing when canonicalizing, matching the
+/// `-e`, `-f` and `-m` flags of `readlink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Missing {
+    /// Every component must exist.
+    None,
+    /// Every component but the last must exist.
+    Last,
+    /// Components may be missing anywhere.
+    Any,
+}
+
+/// Resolves every symlink, `.` and `..` in an absolute path.
+pub fn canonicalize(path: &Path, missing: Missing) -> io::Result<PathBuf> {
+    let mut pending: VecDeque<OsString> = VecDeque::new();
+    push_components(&mut pending, path);
+    let mut resolved = PathBuf::from("/");
+    let mut links = 0;
+    // Once a component is missing nothing below it can be looked up.
+    let mut found_missing = false;
+
+    while let Some(name) = pending.pop_front() {
+        if name == "/" {
+            resolved = PathBuf::from("/");
+            continue;
+        }
+        if name == ".." {
+            resolved.pop();
+            continue;
+        }
+        let candidate = resolved.join(&name);
+        if found_missing {
+            resolved = candidate;
+            continue;
+        }
+
+        match fs::symlink_metadata(&candidate) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                links += 1;
+                if links > MAX_LINKS {
+                    return Err(io::Error::other("Too many levels of symbolic links"));
+                }
+                let target = fs::read_link(&candidate)?;
+                let mut expanded = VecDeque::new();
+                push_components(&mut expanded, &target);
+                expanded.extend(pending);
+                pending = expanded;
+            }
+            Ok(metadata) => {
+                if !metadata.is_dir() && !pending.is_empty() && missing != Missing::Any {
+                    return Err(io::Error::from(io::ErrorKind::NotADirectory));
+                }
+                resolved = candidate;
+            }
+            Err(err) => {
+                let allowed = match missing {
+                    Missing::None => false,
+                    Missing::Last => pending.is_empty() && err.kind() == io::ErrorKind::NotFound,
+                    Missing::Any => true,
+                };
+                if !allowed {
+                    return Err(err);
+                }
+                found_missing = true;
+                resolved = candidate;
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+fn push_components(queue: &mut VecDeque<OsString>, path: &Path) {
+    for component in path.components() {
+        match component {
+            Component::RootDir => queue.push_back("/".into()),
+            Component::ParentDir => queue.push_back("..".into()),
+            Component::Normal(name) => queue.push_back(name.to_os_string()),
+            Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+}
+
+/// Removes `.` and resolves `..` textually, without looking at the file
+/// system, the way `cd` treats paths by default.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                match normalized.components().next_back() {
+                    Some(Component::Normal(_)) => {
+                        normalized.pop();
+                    }
+                    // `..` of the root is the root itself.
+                    Some(Component::RootDir) => {}
+                    _ => normalized.push(".."),
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// Expands a leading `~` to `$HOME` and `~user` to the home directory of
+/// that user. Words naming an unknown user are returned unchanged.
+pub fn expand_tilde(word: &str) -> String {
+    let Some(rest) = word.strip_prefix('~') else {
+        return word.to_string();
+    };
+    let (user, tail) = match rest.find('/') {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, ""),
+    };
+    let home = if user.is_empty() {
+        env::var("HOME").ok().map(PathBuf::from)
+    } else {
+        Users::load().home_dir(user).cloned()
+    };
+    match home {
+        Some(home) => format!("{}{}", home.display(), tail),
+        None => word.to_string(),
+    }
+}
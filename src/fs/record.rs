@@ -0,0 +1,91 @@
+//! Files described as records for `--json` output.
+
+use std::{
+    fs::{self, Metadata},
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    path::Path,
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::Serialize;
+
+use super::{format::mode_string, users::Users};
+
+/// What `ls --json` prints for every entry, and what `stat --json` extends.
+#[derive(Debug, Serialize)]
+pub struct FileRecord {
+    pub name: String,
+    /// The path as given on the command line, joined with the listed directories.
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub size: u64,
+    /// Permission bits in octal, e.g. `0644`.
+    pub mode: String,
+    /// The mode as `ls -l` shows it, e.g. `-rw-r--r--`.
+    pub permissions: String,
+    pub owner: String,
+    pub group: String,
+    pub mtime: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+impl FileRecord {
+    /// `metadata` decides whether the record is a symlink; its target is read
+    /// from `real_path`.
+    pub fn new(
+        name: &str,
+        path: &str,
+        real_path: &Path,
+        metadata: &Metadata,
+        users: &Users,
+    ) -> Self {
+        let target = if metadata.file_type().is_symlink() {
+            fs::read_link(real_path)
+                .ok()
+                .map(|target| target.to_string_lossy().into_owned())
+        } else {
+            None
+        };
+        Self {
+            name: name.to_string(),
+            path: path.to_string(),
+            kind: kind(metadata),
+            size: metadata.len(),
+            mode: format!("{:04o}", metadata.mode() & 0o7777),
+            permissions: mode_string(metadata.mode()),
+            owner: users.user_name(metadata.uid()),
+            group: users.group_name(metadata.gid()),
+            mtime: metadata.modified().ok().map(timestamp),
+            target,
+        }
+    }
+}
+
+/// The file type as a single lowercase word.
+pub fn kind(metadata: &Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        "symlink"
+    } else if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_char_device() {
+        "char_device"
+    } else if file_type.is_block_device() {
+        "block_device"
+    } else {
+        "file"
+    }
+}
+
+/// RFC 3339 in local time, with as many fractional digits as needed.
+pub fn timestamp(time: SystemTime) -> String {
+    let time: DateTime<Local> = time.into();
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+}
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+const PASSWD_PATH: &str = "/etc/passwd";
+const GROUP_PATH: &str = "/etc/group";
+
+/// User and group names read from `/etc/passwd` and `/etc/group`.
+pub struct Users {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+    /// Primary group of each user, from the fourth field of `/etc/passwd`.
+    login_groups: HashMap<u32, u32>,
+    /// Home directories by user name, from the sixth field of `/etc/passwd`.
+    homes: HashMap<String, PathBuf>,
+}
+
+impl Users {
+    /// Loads both databases. Missing or unreadable files simply leave the
+    /// tables empty, in which case ids are shown numerically.
+    pub fn load() -> Self {
+        let passwd = Self::parse(PASSWD_PATH);
+        let group = Self::parse(GROUP_PATH);
+
+        let mut users = HashMap::new();
+        let mut login_groups = HashMap::new();
+        let mut homes = HashMap::new();
+        for (uid, fields) in passwd {
+            users.entry(uid).or_insert_with(|| fields[0].clone());
+            if let Some(gid) = fields.get(3).and_then(|gid| gid.parse().ok()) {
+                login_groups.entry(uid).or_insert(gid);
+            }
+            if let Some(home) = fields.get(5).filter(|home| !home.is_empty()) {
+                homes
+                    .entry(fields[0].clone())
+                    .or_insert_with(|| PathBuf::from(home));
+            }
+        }
+        let mut groups = HashMap::new();
+        for (gid, fields) in group {
+            groups.entry(gid).or_insert_with(|| fields[0].clone());
+        }
+
+        Self {
+            users,
+            groups,
+            login_groups,
+            homes,
+        }
+    }
+
+    /// Both files share the `name:password:id:...` layout for the fields we
+    /// need. Returns the records in file order, keyed by their id.
+    fn parse(path: &str) -> Vec<(u32, Vec<String>)> {
+        let mut records = Vec::new();
+        if let Ok(data) = fs::read_to_string(path) {
+            for line in data.lines() {
+                if line.starts_with('#') {
+                    continue;
+                }
+                let fields: Vec<String> = line.split(':').map(str::to_string).collect();
+                if fields.len() < 3 {
+                    continue;
+                }
+                if let Ok(id) = fields[2].parse::<u32>() {
+                    records.push((id, fields));
+                }
+            }
+        }
+        records
+    }
+
+    pub fn user_name(&self, uid: u32) -> String {
+        match self.users.get(&uid) {
+            Some(name) => name.clone(),
+            None => uid.to_string(),
+        }
+    }
+
+    pub fn group_name(&self, gid: u32) -> String {
+        match self.groups.get(&gid) {
+            Some(name) => name.clone(),
+            None => gid.to_string(),
+        }
+    }
+
+    /// Looks a user up by name. Numeric ids are accepted as they are, like
+    /// `chown` does.
+    pub fn user_id(&self, name: &str) -> Option<u32> {
+        Self::find(&self.users, name).or_else(|| name.parse().ok())
+    }
+
+    pub fn group_id(&self, name: &str) -> Option<u32> {
+        Self::find(&self.groups, name).or_else(|| name.parse().ok())
+    }
+
+    pub fn login_group(&self, uid: u32) -> Option<u32> {
+        self.login_groups.get(&uid).copied()
+    }
+
+    pub fn home_dir(&self, name: &str) -> Option<&PathBuf> {
+        self.homes.get(name)
+    }
+
+    fn find(table: &HashMap<u32, String>, name: &str) -> Option<u32> {
+        table
+            .iter()
+            .filter(|(_, entry)| entry.as_str() == name)
+            .map(|(id, _)| *id)
+            .min()
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::HashSet,
+    fs::{self, Metadata},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::ignore::Ignore;
+
+/// A file found while walking.
+pub struct DirEntry {
+    pub name: String,
+    pub path: PathBuf,
+    /// Follows symlinks only when the walker does.
+    pub metadata: Metadata,
+    /// Zero for the root, one for its children and so on.
+    pub depth: usize,
+}
+
+impl DirEntry {
+    pub fn is_dir(&self) -> bool {
+        self.metadata.is_dir()
+    }
+}
+
+/// Directory traversal shared by the built-ins.
+///
+/// `read_dir` lists a single directory with the filters applied, for commands
+/// that render levels themselves (`ls -R`, `tree`). `walk` returns every entry
+/// below the root depth first, for commands that only need a flat list.
+pub struct Walker {
+    root: PathBuf,
+    hidden: bool,
+    follow_links: bool,
+    git_ignore: bool,
+    max_depth: Option<usize>,
+}
+
+impl Walker {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            hidden: false,
+            follow_links: false,
+            git_ignore: false,
+            max_depth: None,
+        }
+    }
+
+    /// Include dot files.
+    pub fn hidden(mut self, yes: bool) -> Self {
+        self.hidden = yes;
+        self
+    }
+
+    /// Report and descend into the targets of symlinks instead of the links themselves.
+    pub fn follow_links(mut self, yes: bool) -> Self {
+        self.follow_links = yes;
+        self
+    }
+
+    /// Skip files matched by `.gitignore`, `.ignore` and the global excludes, and `.git` itself.
+    pub fn git_ignore(mut self, yes: bool) -> Self {
+        self.git_ignore = yes;
+        self
+    }
+
+    pub fn max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Ignore rules for the root, to be passed to `read_dir` for the first level.
+    pub fn root_ignore(&self) -> Arc<Ignore> {
+        self.ignore_above(&self.root)
+    }
+
+    /// Ignore rules in effect above `dir`: the global ones and, inside a
+    /// repository, the ones of every directory from its root down to the
+    /// parent of `dir`, as git applies them.
+    pub fn ignore_above(&self, dir: &Path) -> Arc<Ignore> {
+        if !self.git_ignore {
+            return Arc::new(Ignore::default());
+        }
+        let mut ignore = Ignore::global();
+        let parents: Vec<&Path> = dir.ancestors().skip(1).collect();
+        if let Some(repo) = parents.iter().position(|dir| dir.join(".git").exists()) {
+            for parent in parents[..=repo].iter().rev() {
+                ignore = Ignore::child(&ignore, parent);
+            }
+        }
+        ignore
+    }
+
+    fn metadata(&self, path: &Path) -> Option<Metadata> {
+        if self.follow_links {
+            fs::metadata(path)
+                .or_else(|_| fs::symlink_metadata(path))
+                .ok()
+        } else {
+            fs::symlink_metadata(path).ok()
+        }
+    }
+
+    /// Lists the entries of `dir`, unsorted, with hidden and ignored files removed.
+    /// Returns the rules to use for the subdirectories alongside.
+    pub fn read_dir(
+        &self,
+        dir: &Path,
+        depth: usize,
+        ignore: &Arc<Ignore>,
+    ) -> Result<(Vec<DirEntry>, Arc<Ignore>), String> {
+        let entries = fs::read_dir(dir).map_err(|err| err.to_string())?;
+        let ignore = if self.git_ignore {
+            Ignore::child(ignore, dir)
+        } else {
+            Arc::clone(ignore)
+        };
+
+        let mut listed = Vec::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !self.hidden && name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let Some(metadata) = self.metadata(&path) else {
+                continue;
+            };
+            if self.git_ignore && (name == ".git" || ignore.is_ignored(&path, metadata.is_dir())) {
+                continue;
+            }
+            listed.push(DirEntry {
+                name,
+                path,
+                metadata,
+                depth: depth + 1,
+            });
+        }
+        Ok((listed, ignore))
+    }
+
+    /// Every entry below the root, depth first, sorted by name within a directory.
+    /// Unreadable directories are reported as errors and skipped.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            walker: self,
+            stack: Vec::new(),
+            visited: HashSet::new(),
+            started: false,
+            pending_error: None,
+        }
+    }
+}
+
+struct PendingDir {
+    entries: std::vec::IntoIter<DirEntry>,
+    ignore: Arc<Ignore>,
+}
+
+pub struct Walk<'a> {
+    walker: &'a Walker,
+    stack: Vec<PendingDir>,
+    /// Directories already entered, to avoid symlink loops when following links.
+    visited: HashSet<(u64, u64)>,
+    started: bool,
+    /// Failure to read a directory, reported after the directory entry itself.
+    pending_error: Option<String>,
+}
+
+impl<'a> Walk<'a> {
+    fn enter(&mut self, dir: &Path, depth: usize, ignore: &Arc<Ignore>) -> Result<(), String> {
+        if self.walker.max_depth.is_some_and(|max| depth >= max) {
+            return Ok(());
+        }
+        if let Ok(metadata) = fs::metadata(dir) {
+            if !self.visited.insert((metadata.dev(), metadata.ino())) {
+                return Ok(());
+            }
+        }
+        let (mut entries, ignore) = self
+            .walker
+            .read_dir(dir, depth, ignore)
+            .map_err(|err| format!("{}: {}", dir.display(), err))?;
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        self.stack.push(PendingDir {
+            entries: entries.into_iter(),
+            ignore,
+        });
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Result<DirEntry, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            let root = self.walker.root.clone();
+            if let Err(err) = self.enter(&root, 0, &self.walker.root_ignore()) {
+                return Some(Err(err));
+            }
+        }
+
+        if let Some(err) = self.pending_error.take() {
+            return Some(Err(err));
+        }
+
+        loop {
+            let pending = self.stack.last_mut()?;
+            let Some(entry) = pending.entries.next() else {
+                self.stack.pop();
+                continue;
+            };
+            if entry.is_dir() {
+                let ignore = Arc::clone(&pending.ignore);
+                if let Err(err) = self.enter(&entry.path, entry.depth, &ignore) {
+                    self.pending_error = Some(err);
+                }
+            }
+            return Some(Ok(entry));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp::TempDir;
+
+    fn names(walker: &Walker) -> Vec<String> {
+        walker
+            .walk()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let path = entry.path.strip_prefix(walker.root()).unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ignore_files_above_the_root_apply_inside_a_repository() {
+        let dir = TempDir::new("walk-ignore");
+        dir.write("repo/.gitignore", "*.log\n/src/gen/\n");
+        dir.write("repo/.git/info/exclude", "secret\n");
+        dir.write("repo/src/.gitignore", "!keep.log\n");
+        for name in [
+            "a.rs",
+            "b.log",
+            "keep.log",
+            "secret",
+            "gen/x.rs",
+            "sub/gen/y.rs",
+        ] {
+            dir.write(&format!("repo/src/{}", name), "");
+        }
+
+        let walker = Walker::new(dir.path().join("repo/src")).git_ignore(true);
+        assert_eq!(
+            names(&walker),
+            ["a.rs", "keep.log", "sub", "sub/gen", "sub/gen/y.rs"]
+        );
+        let walker = Walker::new(dir.path().join("repo/src/sub")).git_ignore(true);
+        assert_eq!(names(&walker), ["gen", "gen/y.rs"]);
+    }
+
+    #[test]
+    fn ignore_files_outside_a_repository_stay_out() {
+        let dir = TempDir::new("walk-no-repo");
+        dir.write("top/.gitignore", "*.log\n");
+        dir.write("top/src/a.log", "");
+
+        let walker = Walker::new(dir.path().join("top/src")).git_ignore(true);
+        assert_eq!(names(&walker), ["a.log"]);
+    }
+}
@@ -0,0 +1,126 @@
+use super::{Highlighter, Language, Token};
+
+/// Line based Markdown highlighting: headings, quotes, list markers, inline
+/// code, emphasis, links and fenced code blocks, which are highlighted with
+/// the language named after the opening fence when it is a known one.
+pub(super) struct Markdown {
+    fence: Option<Fence>,
+}
+
+struct Fence {
+    marker: String,
+    code: Option<Box<Highlighter>>,
+}
+
+impl Markdown {
+    pub fn new() -> Self {
+        Self { fence: None }
+    }
+
+    pub fn line(&mut self, line: &str) -> Vec<(Token, String)> {
+        let trimmed = line.trim_start();
+
+        if let Some(fence) = self.fence.as_mut() {
+            if trimmed.starts_with(&fence.marker) {
+                self.fence = None;
+                return vec![(Token::Punctuation, line.to_string())];
+            }
+            return match fence.code.as_mut() {
+                // Already painted, pass it through untouched.
+                Some(code) => vec![(Token::Plain, code.highlight_line(line))],
+                None => vec![(Token::String, line.to_string())],
+            };
+        }
+
+        for marker in ["```", "~~~"] {
+            if let Some(info) = trimmed.strip_prefix(marker) {
+                let code = Language::from_name(info.trim())
+                    .map(|language| Box::new(Highlighter::new(language)));
+                self.fence = Some(Fence {
+                    marker: marker.to_string(),
+                    code,
+                });
+                return vec![(Token::Punctuation, line.to_string())];
+            }
+        }
+
+        if trimmed.starts_with('#') {
+            return vec![(Token::Heading, line.to_string())];
+        }
+        if trimmed.starts_with('>') {
+            return vec![(Token::Comment, line.to_string())];
+        }
+
+        let mut spans = Vec::new();
+        let indent = &line[..line.len() - trimmed.len()];
+        let body = match list_marker(trimmed) {
+            Some(marker_len) => {
+                spans.push((Token::Plain, indent.to_string()));
+                spans.push((Token::Keyword, trimmed[..marker_len].to_string()));
+                &trimmed[marker_len..]
+            }
+            None => line,
+        };
+        inline(body, &mut spans);
+        spans
+    }
+}
+
+/// Length of a `- `, `* `, `+ ` or `1. ` marker at the start of `text`.
+fn list_marker(text: &str) -> Option<usize> {
+    if text.starts_with("- ") || text.starts_with("* ") || text.starts_with("+ ") {
+        return Some(2);
+    }
+    let digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && text[digits..].starts_with(". ") {
+        return Some(digits + 2);
+    }
+    None
+}
+
+/// Inline `code`, **strong**/*emphasis* and [links](target). Underscores are
+/// left alone since they show up in identifiers far more often than as emphasis.
+fn inline(text: &str, spans: &mut Vec<(Token, String)>) {
+    let mut rest = text;
+    while !rest.is_empty() {
+        let next = rest.find(['`', '*', '[']).unwrap_or(rest.len());
+        if next > 0 {
+            spans.push((Token::Plain, rest[..next].to_string()));
+            rest = &rest[next..];
+            continue;
+        }
+
+        let span = if rest.starts_with('`') {
+            closing(rest, "`").map(|end| (Token::String, end))
+        } else if rest.starts_with("**") {
+            closing(rest, "**").map(|end| (Token::Emphasis, end))
+        } else if rest.starts_with('*') {
+            closing(rest, "*").map(|end| (Token::Emphasis, end))
+        } else {
+            rest.find("](")
+                .and_then(|mid| rest[mid..].find(')').map(|close| mid + close + 1))
+                .map(|end| (Token::Link, end))
+        };
+
+        match span {
+            Some((token, end)) => {
+                spans.push((token, rest[..end].to_string()));
+                rest = &rest[end..];
+            }
+            None => {
+                let width = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+                spans.push((Token::Plain, rest[..width].to_string()));
+                rest = &rest[width..];
+            }
+        }
+    }
+}
+
+/// End offset (exclusive) of a span opened by `delimiter` at the start of `text`.
+fn closing(text: &str, delimiter: &str) -> Option<usize> {
+    let body = &text[delimiter.len()..];
+    match body.find(delimiter) {
+        Some(0) | None => None,
+        Some(pos) => Some(delimiter.len() + pos + delimiter.len()),
+    }
+}
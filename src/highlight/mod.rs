@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use colored::{ColoredString, Colorize};
+
+use crate::theme::Role;
+
+use self::{markdown::Markdown, syntax::Lexer};
+
+mod markdown;
+mod syntax;
+
+/// Languages `cat` knows how to colorize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Toml,
+    Json,
+    Markdown,
+    Shell,
+    Python,
+}
+
+impl Language {
+    /// Picks the language from the file extension, falling back to the
+    /// interpreter named in a `#!` line.
+    pub fn detect(path: Option<&Path>, first_line: &str) -> Option<Self> {
+        let by_extension = path
+            .and_then(|path| path.extension())
+            .and_then(|ext| Self::from_name(&ext.to_string_lossy()));
+        if by_extension.is_some() {
+            return by_extension;
+        }
+
+        let file_name = path
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match file_name.as_str() {
+            "Cargo.lock" => return Some(Self::Toml),
+            ".bashrc" | ".bash_profile" | ".profile" | ".zshrc" => return Some(Self::Shell),
+            _ => {}
+        }
+
+        let interpreter = first_line.strip_prefix("#!")?.trim();
+        let mut words = interpreter.split_whitespace();
+        let program = words.next()?;
+        let program = match program.rsplit('/').next() {
+            Some("env") => words.find(|word| !word.starts_with('-'))?,
+            Some(name) => name,
+            None => program,
+        };
+        match program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+            "sh" | "bash" | "zsh" | "dash" | "ksh" => Some(Self::Shell),
+            "python" => Some(Self::Python),
+            _ => None,
+        }
+    }
+
+    /// Accepts extensions as well as names given with `cat --language`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rs" | "rust" => Some(Self::Rust),
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "md" | "markdown" => Some(Self::Markdown),
+            "sh" | "bash" | "zsh" | "shell" => Some(Self::Shell),
+            "py" | "python" => Some(Self::Python),
+            _ => None,
+        }
+    }
+}
+
+/// What a piece of source text is, independent of how it ends up colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token {
+    Plain,
+    Keyword,
+    Literal,
+    String,
+    Number,
+    Comment,
+    Type,
+    Function,
+    Macro,
+    Key,
+    Variable,
+    Heading,
+    Emphasis,
+    Link,
+    Punctuation,
+}
+
+impl Token {
+    fn paint(self, text: &str) -> ColoredString {
+        let role = match self {
+            Token::Plain => return text.normal(),
+            Token::Keyword => Role::SyntaxKeyword,
+            Token::Literal => Role::SyntaxLiteral,
+            Token::String => Role::SyntaxString,
+            Token::Number => Role::SyntaxNumber,
+            Token::Comment => Role::SyntaxComment,
+            Token::Type => Role::SyntaxType,
+            Token::Function => Role::SyntaxFunction,
+            Token::Macro => Role::SyntaxMacro,
+            Token::Key => Role::SyntaxKey,
+            Token::Variable => Role::SyntaxVariable,
+            Token::Heading => Role::SyntaxHeading,
+            Token::Emphasis => Role::SyntaxEmphasis,
+            Token::Link => Role::SyntaxLink,
+            Token::Punctuation => Role::SyntaxPunctuation,
+        };
+        role.paint(text)
+    }
+}
+
+/// Highlights a file line by line, keeping the state (open block comments,
+/// multi-line strings, fenced code) that carries over between lines.
+pub struct Highlighter {
+    inner: Inner,
+}
+
+enum Inner {
+    Code(Lexer),
+    Markdown(Markdown),
+}
+
+impl Highlighter {
+    pub fn new(language: Language) -> Self {
+        let inner = match language {
+            Language::Markdown => Inner::Markdown(Markdown::new()),
+            _ => Inner::Code(Lexer::new(language)),
+        };
+        Self { inner }
+    }
+
+    /// Returns `line` (without its line break) with color escapes added.
+    pub fn highlight_line(&mut self, line: &str) -> String {
+        let spans = match &mut self.inner {
+            Inner::Code(lexer) => lexer.line(line),
+            Inner::Markdown(markdown) => markdown.line(line),
+        };
+        spans
+            .iter()
+            .map(|(token, text)| token.paint(text).to_string())
+            .collect()
+    }
+}
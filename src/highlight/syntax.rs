@@ -0,0 +1,461 @@
+use super::{Language, Token};
+
+/// The lexical rules of a programming or configuration language.
+struct Syntax {
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    triple_quotes: bool,
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+}
+
+const RUST: Syntax = Syntax {
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    triple_quotes: false,
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while",
+    ],
+    literals: &["true", "false"],
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comment: Some("#"),
+    block_comment: None,
+    quotes: &['"', '\''],
+    triple_quotes: true,
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with",
+        "yield", "self",
+    ],
+    literals: &["True", "False", "None"],
+};
+
+const SHELL: Syntax = Syntax {
+    line_comment: Some("#"),
+    block_comment: None,
+    quotes: &['"', '\''],
+    triple_quotes: false,
+    keywords: &[
+        "if", "then", "else", "elif", "fi", "case", "esac", "for", "while", "until", "do", "done",
+        "in", "function", "return", "local", "export", "readonly", "select", "time", "exit",
+    ],
+    literals: &["true", "false"],
+};
+
+const TOML: Syntax = Syntax {
+    line_comment: Some("#"),
+    block_comment: None,
+    quotes: &['"', '\''],
+    triple_quotes: true,
+    keywords: &[],
+    literals: &["true", "false", "inf", "nan"],
+};
+
+const JSON: Syntax = Syntax {
+    line_comment: None,
+    block_comment: None,
+    quotes: &['"'],
+    triple_quotes: false,
+    keywords: &[],
+    literals: &["true", "false", "null"],
+};
+
+enum State {
+    Normal,
+    /// Nesting depth, Rust block comments nest.
+    BlockComment(usize),
+    String {
+        delimiter: String,
+        escapes: bool,
+    },
+}
+
+/// Tokenizer shared by all the code-like languages.
+pub(super) struct Lexer {
+    language: Language,
+    syntax: &'static Syntax,
+    state: State,
+}
+
+impl Lexer {
+    pub fn new(language: Language) -> Self {
+        let syntax = match language {
+            Language::Rust => &RUST,
+            Language::Python => &PYTHON,
+            Language::Shell => &SHELL,
+            Language::Toml => &TOML,
+            Language::Json | Language::Markdown => &JSON,
+        };
+        Self {
+            language,
+            syntax,
+            state: State::Normal,
+        }
+    }
+
+    pub fn line(&mut self, line: &str) -> Vec<(Token, String)> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut spans = Vec::new();
+        let mut idx = 0;
+
+        while idx < chars.len() {
+            idx = match &self.state {
+                State::BlockComment(depth) => self.block_comment(&chars, idx, *depth, &mut spans),
+                State::String { delimiter, escapes } => {
+                    let (delimiter, escapes) = (delimiter.clone(), *escapes);
+                    self.string(&chars, idx, &delimiter, escapes, &mut spans)
+                }
+                State::Normal => self.normal(&chars, idx, &mut spans),
+            };
+        }
+        spans
+    }
+
+    fn normal(&mut self, chars: &[char], idx: usize, spans: &mut Vec<(Token, String)>) -> usize {
+        let c = chars[idx];
+        let at_word_start = idx == 0 || chars[idx - 1].is_whitespace();
+
+        if let Some(marker) = self.syntax.line_comment {
+            // A `#` in the middle of a shell word such as `$#` or `a#b` is not a comment.
+            if starts_with(chars, idx, marker)
+                && (self.language != Language::Shell || at_word_start)
+            {
+                push(
+                    spans,
+                    Token::Comment,
+                    &chars[idx..].iter().collect::<String>(),
+                );
+                return chars.len();
+            }
+        }
+
+        if let Some((open, _)) = self.syntax.block_comment {
+            if starts_with(chars, idx, open) {
+                push(spans, Token::Comment, open);
+                self.state = State::BlockComment(1);
+                return idx + open.chars().count();
+            }
+        }
+
+        if self.language == Language::Toml && idx == leading_spaces(chars) && c == '[' {
+            push(spans, Token::Type, &chars[idx..].iter().collect::<String>());
+            return chars.len();
+        }
+
+        if self.syntax.triple_quotes {
+            for triple in ["\"\"\"", "'''"] {
+                if starts_with(chars, idx, triple) {
+                    push(spans, Token::String, triple);
+                    self.state = State::String {
+                        delimiter: triple.to_string(),
+                        escapes: !(self.language == Language::Toml && triple == "'''"),
+                    };
+                    return idx + 3;
+                }
+            }
+        }
+
+        if self.language == Language::Rust {
+            if let Some(end) = rust_raw_string_start(chars, idx) {
+                let prefix: String = chars[idx..end].iter().collect();
+                push(spans, Token::String, &prefix);
+                let hashes = prefix.chars().filter(|&c| c == '#').count();
+                self.state = State::String {
+                    delimiter: format!("\"{}", "#".repeat(hashes)),
+                    escapes: false,
+                };
+                return end;
+            }
+            if c == '\'' {
+                return rust_quote(chars, idx, spans);
+            }
+        }
+
+        if self.syntax.quotes.contains(&c) {
+            push(spans, Token::String, &c.to_string());
+            // Single quotes never process escapes in shell scripts and TOML.
+            let literal = c == '\'' && matches!(self.language, Language::Shell | Language::Toml);
+            self.state = State::String {
+                delimiter: c.to_string(),
+                escapes: !literal,
+            };
+            return idx + 1;
+        }
+
+        if self.language == Language::Shell && c == '$' {
+            let end = shell_variable_end(chars, idx);
+            if end > idx + 1 {
+                push(
+                    spans,
+                    Token::Variable,
+                    &chars[idx..end].iter().collect::<String>(),
+                );
+                return end;
+            }
+        }
+
+        let after_identifier = idx > 0 && is_identifier_char(chars[idx - 1]);
+        if c.is_ascii_digit() && !after_identifier {
+            let mut end = idx;
+            while end < chars.len()
+                && (chars[end].is_ascii_alphanumeric() || matches!(chars[end], '.' | '_'))
+            {
+                end += 1;
+            }
+            push(
+                spans,
+                Token::Number,
+                &chars[idx..end].iter().collect::<String>(),
+            );
+            return end;
+        }
+
+        if is_identifier_start(c) && !after_identifier {
+            let mut end = idx;
+            while end < chars.len() && is_identifier_char(chars[end]) {
+                end += 1;
+            }
+            let word: String = chars[idx..end].iter().collect();
+            let token = self.classify(&word, chars, idx, end);
+            if token == Token::Macro {
+                push(spans, token, &format!("{}!", word));
+                return end + 1;
+            }
+            push(spans, token, &word);
+            return end;
+        }
+
+        let token = if c.is_ascii_punctuation() && self.language == Language::Json {
+            Token::Punctuation
+        } else {
+            Token::Plain
+        };
+        push(spans, token, &c.to_string());
+        idx + 1
+    }
+
+    fn classify(&self, word: &str, chars: &[char], start: usize, end: usize) -> Token {
+        if self.syntax.keywords.contains(&word) {
+            return Token::Keyword;
+        }
+        if self.syntax.literals.contains(&word) {
+            return Token::Literal;
+        }
+
+        let next = chars[end..].iter().find(|c| !c.is_whitespace());
+        match self.language {
+            Language::Toml
+                if start == leading_spaces(chars) && matches!(next, Some('=') | Some('.')) =>
+            {
+                Token::Key
+            }
+            Language::Shell if chars.get(end) == Some(&'=') => Token::Variable,
+            Language::Rust if chars.get(end) == Some(&'!') && chars.get(end + 1) != Some(&'=') => {
+                Token::Macro
+            }
+            Language::Rust if word.starts_with(|c: char| c.is_ascii_uppercase()) => Token::Type,
+            Language::Rust | Language::Python if next == Some(&'(') => Token::Function,
+            _ => Token::Plain,
+        }
+    }
+
+    fn block_comment(
+        &mut self,
+        chars: &[char],
+        idx: usize,
+        depth: usize,
+        spans: &mut Vec<(Token, String)>,
+    ) -> usize {
+        let Some((open, close)) = self.syntax.block_comment else {
+            self.state = State::Normal;
+            return idx;
+        };
+        let mut depth = depth;
+        let mut end = idx;
+        while end < chars.len() {
+            if starts_with(chars, end, close) {
+                end += close.chars().count();
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            } else if starts_with(chars, end, open) {
+                end += open.chars().count();
+                depth += 1;
+            } else {
+                end += 1;
+            }
+        }
+        push(
+            spans,
+            Token::Comment,
+            &chars[idx..end].iter().collect::<String>(),
+        );
+        self.state = if depth == 0 {
+            State::Normal
+        } else {
+            State::BlockComment(depth)
+        };
+        end
+    }
+
+    fn string(
+        &mut self,
+        chars: &[char],
+        idx: usize,
+        delimiter: &str,
+        escapes: bool,
+        spans: &mut Vec<(Token, String)>,
+    ) -> usize {
+        let delimiter: Vec<char> = delimiter.chars().collect();
+        let mut end = idx;
+        let mut closed = false;
+        while end < chars.len() {
+            if escapes && chars[end] == '\\' {
+                end += 2;
+                continue;
+            }
+            if chars[end..].starts_with(&delimiter) {
+                end += delimiter.len();
+                closed = true;
+                break;
+            }
+            end += 1;
+        }
+        let end = end.min(chars.len());
+        let text: String = chars[idx..end].iter().collect();
+
+        // JSON object keys are strings followed by a colon.
+        let is_key = closed
+            && self.language == Language::Json
+            && chars[end..].iter().find(|c| !c.is_whitespace()) == Some(&':');
+        if is_key {
+            if let Some((token, opening)) = spans.last_mut() {
+                if *token == Token::String && opening == "\"" {
+                    *token = Token::Key;
+                }
+            }
+            push(spans, Token::Key, &text);
+        } else {
+            push(spans, Token::String, &text);
+        }
+
+        if closed {
+            self.state = State::Normal;
+        }
+        end
+    }
+}
+
+/// Appends `text`, merging it into the previous span when the token matches.
+fn push(spans: &mut Vec<(Token, String)>, token: Token, text: &str) {
+    match spans.last_mut() {
+        Some((last, existing)) if *last == token => existing.push_str(text),
+        _ => spans.push((token, text.to_string())),
+    }
+}
+
+fn starts_with(chars: &[char], idx: usize, pattern: &str) -> bool {
+    (idx..)
+        .zip(pattern.chars())
+        .all(|(pos, expected)| chars.get(pos) == Some(&expected))
+}
+
+fn leading_spaces(chars: &[char]) -> usize {
+    chars.iter().take_while(|c| c.is_whitespace()).count()
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the end of a `r"`, `r#"`, `br"`, ... prefix starting at `idx`.
+fn rust_raw_string_start(chars: &[char], idx: usize) -> Option<usize> {
+    if idx > 0 && is_identifier_char(chars[idx - 1]) {
+        return None;
+    }
+    let mut end = idx;
+    if chars.get(end) == Some(&'b') {
+        end += 1;
+    }
+    if chars.get(end) != Some(&'r') {
+        return None;
+    }
+    end += 1;
+    while chars.get(end) == Some(&'#') {
+        end += 1;
+    }
+    if chars.get(end) == Some(&'"') {
+        Some(end + 1)
+    } else {
+        None
+    }
+}
+
+/// A quote in Rust is either a char literal (`'a'`, `'\n'`) or a lifetime (`'a`).
+fn rust_quote(chars: &[char], idx: usize, spans: &mut Vec<(Token, String)>) -> usize {
+    let literal_end = if chars.get(idx + 1) == Some(&'\\') {
+        chars[idx + 2..]
+            .iter()
+            .position(|&c| c == '\'')
+            .map(|pos| idx + 2 + pos + 1)
+    } else if chars.get(idx + 2) == Some(&'\'') {
+        Some(idx + 3)
+    } else {
+        None
+    };
+
+    match literal_end {
+        Some(end) => {
+            push(
+                spans,
+                Token::String,
+                &chars[idx..end].iter().collect::<String>(),
+            );
+            end
+        }
+        None => {
+            let mut end = idx + 1;
+            while end < chars.len() && is_identifier_char(chars[end]) {
+                end += 1;
+            }
+            push(
+                spans,
+                Token::Type,
+                &chars[idx..end].iter().collect::<String>(),
+            );
+            end
+        }
+    }
+}
+
+/// Handles `$name`, `${...}`, `$1` and special parameters such as `$?`.
+fn shell_variable_end(chars: &[char], idx: usize) -> usize {
+    match chars.get(idx + 1) {
+        Some('{') => match chars[idx..].iter().position(|&c| c == '}') {
+            Some(pos) => idx + pos + 1,
+            None => chars.len(),
+        },
+        Some(c) if "?#@*$!-0123456789".contains(*c) => idx + 2,
+        Some(&c) if is_identifier_start(c) => {
+            let mut end = idx + 1;
+            while end < chars.len() && is_identifier_char(chars[end]) {
+                end += 1;
+            }
+            end
+        }
+        _ => idx + 1,
+    }
+}
@@ -1,9 +1,32 @@
-use crate::cmd::CMD;
+use std::env;
+
+use crate::cmd::{color::ColorChoice, CMD};
 
 pub mod binaries;
 pub mod cmd;
+pub mod diff;
+pub mod fs;
+pub mod highlight;
+pub mod pager;
+pub mod table;
+pub mod theme;
+
+/// Reads the options of the shell itself, only `--color[=when]` for now.
+fn color_option() -> Result<ColorChoice, String> {
+    let mut choice = ColorChoice::Auto;
+    for arg in env::args().skip(1) {
+        let value = match arg.strip_prefix("--color") {
+            Some("") => None,
+            Some(rest) if rest.starts_with('=') => Some(&rest[1..]),
+            _ => return Err(format!("unrecognized option '{}'", arg)),
+        };
+        choice = ColorChoice::from_value("cli-util", value).map_err(|err| err.to_string())?;
+    }
+    Ok(choice)
+}
 
 fn main() -> Result<(), String> {
+    let color = color_option()?;
     println!(
         r#"
     _____ _      _____   _                 _             _               _
@@ -20,6 +43,10 @@ fn main() -> Result<(), String> {
     println!("Type 'man' (without the quote) for getting the list of commands");
 
     let mut cmd = CMD::new()?;
+    cmd.set_color(color);
+    if let Err(err) = theme::load_from_env(cmd.get_current_dir_path()) {
+        eprintln!("Warning: CLI_THEME: {}", err);
+    }
     cmd.run();
     Ok(())
 }
@@ -0,0 +1,131 @@
+use ratatui::style::{Color, Modifier, Style};
+
+/// Splits text containing SGR escape sequences (as produced by `colored`)
+/// into styled runs. Other escape sequences are dropped.
+pub(super) fn parse(text: &str) -> Vec<(Style, String)> {
+    let mut runs: Vec<(Style, String)> = Vec::new();
+    let mut style = Style::default();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            match runs.last_mut() {
+                Some((last, run)) if *last == style => run.push(c),
+                _ => runs.push((style, c.to_string())),
+            }
+            continue;
+        }
+        if chars.peek() != Some(&'[') {
+            continue;
+        }
+        chars.next();
+
+        let mut params = String::new();
+        let mut command = None;
+        for c in chars.by_ref() {
+            if c.is_ascii_digit() || c == ';' {
+                params.push(c);
+            } else {
+                command = Some(c);
+                break;
+            }
+        }
+        if command == Some('m') {
+            style = apply(style, &params);
+        }
+    }
+    runs
+}
+
+fn apply(mut style: Style, params: &str) -> Style {
+    let codes: Vec<u16> = if params.is_empty() {
+        vec![0]
+    } else {
+        params
+            .split(';')
+            .map(|code| code.parse().unwrap_or(0))
+            .collect()
+    };
+
+    let mut idx = 0;
+    while idx < codes.len() {
+        match codes[idx] {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            8 => style = style.add_modifier(Modifier::HIDDEN),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            29 => style = style.remove_modifier(Modifier::CROSSED_OUT),
+            code @ 30..=37 => style = style.fg(basic_color(code - 30)),
+            code @ 40..=47 => style = style.bg(basic_color(code - 40)),
+            code @ 90..=97 => style = style.fg(bright_color(code - 90)),
+            code @ 100..=107 => style = style.bg(bright_color(code - 100)),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            code @ (38 | 48) => {
+                let color = match codes.get(idx + 1) {
+                    Some(5) => {
+                        let color = codes.get(idx + 2).map(|&n| Color::Indexed(n as u8));
+                        idx += 2;
+                        color
+                    }
+                    Some(2) => {
+                        let rgb = (codes.get(idx + 2), codes.get(idx + 3), codes.get(idx + 4));
+                        idx += 4;
+                        match rgb {
+                            (Some(&r), Some(&g), Some(&b)) => {
+                                Some(Color::Rgb(r as u8, g as u8, b as u8))
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                if let Some(color) = color {
+                    style = if code == 38 {
+                        style.fg(color)
+                    } else {
+                        style.bg(color)
+                    };
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+    style
+}
+
+fn basic_color(offset: u16) -> Color {
+    match offset {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        _ => Color::Gray,
+    }
+}
+
+fn bright_color(offset: u16) -> Color {
+    match offset {
+        0 => Color::DarkGray,
+        1 => Color::LightRed,
+        2 => Color::LightGreen,
+        3 => Color::LightYellow,
+        4 => Color::LightBlue,
+        5 => Color::LightMagenta,
+        6 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
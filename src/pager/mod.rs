@@ -0,0 +1,716 @@
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Terminal,
+};
+use unicode_width::UnicodeWidthChar;
+
+use crate::theme::{self, Role};
+
+mod ansi;
+
+const TAB_WIDTH: usize = 8;
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Prints `text` directly, or opens it in the pager when stdout is a terminal
+/// and the text does not fit on one screen.
+pub fn page_or_print(title: &str, text: &str) -> io::Result<()> {
+    let fits = match terminal::size() {
+        Ok((_, rows)) => text.lines().count() < rows as usize,
+        Err(_) => true,
+    };
+    if fits || !io::stdout().is_terminal() {
+        print!("{}", text);
+        return Ok(());
+    }
+    Pager::new(title, text).run()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PromptKind {
+    SearchForward,
+    SearchBackward,
+    GotoLine,
+}
+
+struct Search {
+    pattern: String,
+    backwards: bool,
+    /// Smart case: only case-sensitive when the pattern has an uppercase letter.
+    ignore_case: bool,
+}
+
+/// The file being shown, so follow mode can pick up appended data.
+struct Source {
+    path: PathBuf,
+    position: u64,
+    /// Bytes after the last line break, not shown until the line is complete.
+    partial: Vec<u8>,
+}
+
+/// Full-screen viewer for text that may contain color escapes, modelled after `less`.
+pub struct Pager {
+    title: String,
+    lines: Vec<String>,
+    /// `lines` without escape sequences, used for searching.
+    plain: Vec<String>,
+    top: usize,
+    left: usize,
+    wrap: bool,
+    search: Option<Search>,
+    prompt: Option<(PromptKind, String)>,
+    count: String,
+    message: Option<String>,
+    source: Option<Source>,
+    following: bool,
+    view_height: usize,
+    view_width: usize,
+}
+
+impl Pager {
+    pub fn new(title: &str, text: &str) -> Self {
+        let mut pager = Self {
+            title: title.to_string(),
+            lines: Vec::new(),
+            plain: Vec::new(),
+            top: 0,
+            left: 0,
+            wrap: true,
+            search: None,
+            prompt: None,
+            count: String::new(),
+            message: None,
+            source: None,
+            following: false,
+            view_height: 1,
+            view_width: 0,
+        };
+        pager.push_text(text);
+        pager
+    }
+
+    /// Opens a file, remembering where it ends so `F` can follow it.
+    pub fn from_file(title: &str, path: &Path) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        let mut pager = Self::new(title, &String::from_utf8_lossy(&bytes));
+        pager.source = Some(Source {
+            path: path.to_path_buf(),
+            position: bytes.len() as u64,
+            partial: Vec::new(),
+        });
+        Ok(pager)
+    }
+
+    /// Long lines are cut at the screen edge instead of wrapped, like `less -S`.
+    pub fn chop_long_lines(mut self, chop: bool) -> Self {
+        self.wrap = !chop;
+        self
+    }
+
+    /// Start in follow mode, like `less +F`.
+    pub fn follow(mut self, follow: bool) -> Self {
+        self.following = follow && self.source.is_some();
+        self
+    }
+
+    /// Jumps to the first match of `pattern`, like `less -p`.
+    pub fn with_search(mut self, pattern: &str) -> Self {
+        self.start_search(pattern, false);
+        self
+    }
+
+    fn push_text(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = expand_tabs(line);
+            self.plain
+                .push(console::strip_ansi_codes(&line).to_string());
+            self.lines.push(line);
+        }
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        if let Err(err) = execute!(stdout, EnterAlternateScreen) {
+            let _ = terminal::disable_raw_mode();
+            return Err(err);
+        }
+
+        let result = self.event_loop();
+
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+        result
+    }
+
+    fn event_loop(&mut self) -> io::Result<()> {
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        loop {
+            if self.following {
+                self.read_appended();
+                self.scroll_to_end();
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(FOLLOW_INTERVAL)? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Returns `false` when the pager should close.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            if self.following || self.prompt.is_some() {
+                self.following = false;
+                self.prompt = None;
+                return true;
+            }
+            return false;
+        }
+        if self.following {
+            self.following = false;
+            self.message = None;
+            return true;
+        }
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key);
+            return true;
+        }
+
+        self.message = None;
+        let count = self.count.parse::<usize>().ok();
+        if let KeyCode::Char(c) = key.code {
+            if c.is_ascii_digit() {
+                self.count.push(c);
+                return true;
+            }
+        }
+        self.count.clear();
+        let times = count.unwrap_or(1);
+        let page = self.view_height.max(1);
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return false,
+            KeyCode::Char('j') | KeyCode::Char('e') | KeyCode::Down | KeyCode::Enter => {
+                self.scroll_down(times)
+            }
+            KeyCode::Char('k') | KeyCode::Char('y') | KeyCode::Up => self.scroll_up(times),
+            KeyCode::Char(' ') | KeyCode::Char('f') | KeyCode::PageDown => {
+                self.scroll_down(page * times)
+            }
+            KeyCode::Char('b') | KeyCode::PageUp => self.scroll_up(page * times),
+            KeyCode::Char('d') => self.scroll_down(page / 2),
+            KeyCode::Char('u') => self.scroll_up(page / 2),
+            KeyCode::Char('g') | KeyCode::Char('<') | KeyCode::Home => {
+                self.goto_line(count.unwrap_or(1))
+            }
+            KeyCode::Char('G') | KeyCode::Char('>') | KeyCode::End => match count {
+                Some(line) => self.goto_line(line),
+                None => self.scroll_to_end(),
+            },
+            KeyCode::Left if !self.wrap => self.left = self.left.saturating_sub(8 * times),
+            KeyCode::Right if !self.wrap => self.left += 8 * times,
+            KeyCode::Char('/') => self.prompt = Some((PromptKind::SearchForward, String::new())),
+            KeyCode::Char('?') => self.prompt = Some((PromptKind::SearchBackward, String::new())),
+            KeyCode::Char(':') => self.prompt = Some((PromptKind::GotoLine, String::new())),
+            KeyCode::Char('n') => self.next_match(false),
+            KeyCode::Char('N') => self.next_match(true),
+            KeyCode::Char('w') => {
+                self.wrap = !self.wrap;
+                self.left = 0;
+                self.message = Some(if self.wrap {
+                    "Wrapping long lines".to_string()
+                } else {
+                    "Chopping long lines".to_string()
+                });
+            }
+            KeyCode::Char('F') => match self.source {
+                Some(_) => {
+                    self.following = true;
+                    self.message = Some("Waiting for data... (press any key to stop)".to_string());
+                }
+                None => self.message = Some("Nothing to follow".to_string()),
+            },
+            KeyCode::Char('h') => {
+                self.message = Some(
+                    "q quit  j/k line  space/b page  g/G top/end  /? search  \
+                     n/N next  :N line  w wrap  F follow"
+                        .to_string(),
+                )
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some((kind, mut input)) = self.prompt.take() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {}
+            KeyCode::Backspace => {
+                if input.pop().is_some() {
+                    self.prompt = Some((kind, input));
+                }
+            }
+            KeyCode::Enter => match kind {
+                PromptKind::GotoLine => match input.trim().parse::<usize>() {
+                    Ok(line) => self.goto_line(line),
+                    Err(_) => self.message = Some(format!("Invalid line number: {}", input)),
+                },
+                PromptKind::SearchForward | PromptKind::SearchBackward => {
+                    if input.is_empty() {
+                        // An empty pattern repeats the previous search.
+                        self.next_match(kind == PromptKind::SearchBackward);
+                    } else {
+                        self.start_search(&input, kind == PromptKind::SearchBackward);
+                    }
+                }
+            },
+            KeyCode::Char(c) => {
+                input.push(c);
+                self.prompt = Some((kind, input));
+            }
+            _ => self.prompt = Some((kind, input)),
+        }
+    }
+
+    /// The rows line `idx` takes on the screen.
+    fn rows_of(&self, idx: usize) -> usize {
+        if self.wrap {
+            row_count(&self.plain[idx], self.view_width)
+        } else {
+            1
+        }
+    }
+
+    /// The top line that shows the end of the text, counting wrapped rows.
+    /// A last line taller than the screen is shown from its start.
+    fn max_top(&self) -> usize {
+        let mut top = self.lines.len();
+        let mut rows = 0;
+        while top > 0 {
+            rows += self.rows_of(top - 1);
+            if rows > self.view_height && top < self.lines.len() {
+                break;
+            }
+            top -= 1;
+        }
+        top
+    }
+
+    fn scroll_down(&mut self, lines: usize) {
+        self.top = (self.top + lines).min(self.max_top());
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        self.top = self.top.saturating_sub(lines);
+    }
+
+    fn scroll_to_end(&mut self) {
+        self.top = self.max_top();
+    }
+
+    fn goto_line(&mut self, line: usize) {
+        self.top = line.saturating_sub(1).min(self.max_top());
+    }
+
+    fn start_search(&mut self, pattern: &str, backwards: bool) {
+        self.search = Some(Search {
+            pattern: pattern.to_string(),
+            backwards,
+            ignore_case: !pattern.chars().any(char::is_uppercase),
+        });
+        // The first search may match the top line itself.
+        self.find_from(self.top, backwards);
+    }
+
+    /// `reverse` flips the direction of the last search, like `N` in less.
+    fn next_match(&mut self, reverse: bool) {
+        let Some(search) = self.search.as_ref() else {
+            self.message = Some("No previous search".to_string());
+            return;
+        };
+        let backwards = search.backwards != reverse;
+        if backwards {
+            match self.top.checked_sub(1) {
+                Some(start) => self.find_from(start, true),
+                None => self.message = Some("Pattern not found".to_string()),
+            }
+        } else {
+            self.find_from(self.top + 1, false);
+        }
+    }
+
+    fn find_from(&mut self, start: usize, backwards: bool) {
+        let Some(search) = self.search.as_ref() else {
+            return;
+        };
+        let found = if backwards {
+            (0..=start.min(self.plain.len().saturating_sub(1)))
+                .rev()
+                .find(|&idx| !find_matches(&self.plain[idx], search).is_empty())
+        } else {
+            (start..self.plain.len())
+                .find(|&idx| !find_matches(&self.plain[idx], search).is_empty())
+        };
+        match found {
+            Some(idx) => self.top = idx,
+            None => self.message = Some("Pattern not found".to_string()),
+        }
+    }
+
+    /// Picks up data appended to the file since the last read. A file that
+    /// shrank was truncated and is read again from the beginning.
+    fn read_appended(&mut self) {
+        let Some(source) = self.source.as_mut() else {
+            return;
+        };
+        let Ok(mut file) = File::open(&source.path) else {
+            return;
+        };
+        let Ok(len) = file.metadata().map(|metadata| metadata.len()) else {
+            return;
+        };
+
+        if len < source.position {
+            source.position = 0;
+            source.partial.clear();
+            self.lines.clear();
+            self.plain.clear();
+        }
+        if len == source.position || file.seek(SeekFrom::Start(source.position)).is_err() {
+            return;
+        }
+
+        let mut bytes = std::mem::take(&mut source.partial);
+        if let Ok(read) = file.take(len - source.position).read_to_end(&mut bytes) {
+            source.position += read as u64;
+        }
+        let complete = bytes
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |pos| pos + 1);
+        source.partial = bytes.split_off(complete);
+        let text = String::from_utf8_lossy(&bytes).to_string();
+        self.push_text(&text);
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame) {
+        let area = frame.size();
+        if area.height == 0 {
+            return;
+        }
+        let body = Rect {
+            height: area.height - 1,
+            ..area
+        };
+        let status = Rect {
+            y: area.y + area.height - 1,
+            height: 1,
+            ..area
+        };
+        self.view_height = body.height as usize;
+        self.view_width = body.width as usize;
+        self.top = self.top.min(self.max_top());
+
+        let width = body.width as usize;
+        let mut rows: Vec<Line> = Vec::new();
+        let mut idx = self.top;
+        while rows.len() < body.height as usize && idx < self.lines.len() {
+            let mut runs = ansi::parse(&self.lines[idx]);
+            if let Some(search) = self.search.as_ref() {
+                runs = highlight(runs, &find_matches(&self.plain[idx], search));
+            }
+            if self.wrap {
+                rows.extend(wrap(runs, width));
+            } else {
+                rows.push(chop(runs, self.left, width));
+            }
+            idx += 1;
+        }
+        // A line cut at the bottom of the screen is not shown yet.
+        let bottom = match rows.len() > body.height as usize {
+            true => idx - 1,
+            false => idx,
+        };
+        while rows.len() < body.height as usize {
+            rows.push(Line::from(Span::styled(
+                "~",
+                Style::default().add_modifier(Modifier::DIM),
+            )));
+        }
+        rows.truncate(body.height as usize);
+        frame.render_widget(Paragraph::new(rows), body);
+
+        frame.render_widget(Paragraph::new(self.status_line(bottom)), status);
+    }
+
+    fn status_line(&self, bottom: usize) -> Line<'static> {
+        if let Some((kind, input)) = self.prompt.as_ref() {
+            let prefix = match kind {
+                PromptKind::SearchForward => "/",
+                PromptKind::SearchBackward => "?",
+                PromptKind::GotoLine => ":",
+            };
+            return Line::from(format!("{}{}", prefix, input));
+        }
+
+        let text = match self.message.as_ref() {
+            Some(message) => message.clone(),
+            None => {
+                let percent = if self.lines.is_empty() {
+                    100
+                } else {
+                    bottom * 100 / self.lines.len()
+                };
+                let end = if bottom >= self.lines.len() {
+                    " (END)"
+                } else {
+                    ""
+                };
+                format!(
+                    "{}  lines {}-{}/{}  {}%{}",
+                    self.title,
+                    (self.top + 1).min(self.lines.len()),
+                    bottom,
+                    self.lines.len(),
+                    percent,
+                    end
+                )
+            }
+        };
+        Line::from(Span::styled(
+            text,
+            Style::default().add_modifier(Modifier::REVERSED),
+        ))
+    }
+}
+
+fn expand_tabs(line: &str) -> String {
+    if !line.contains('\t') {
+        return line.to_string();
+    }
+    let mut out = String::with_capacity(line.len());
+    let mut column = 0;
+    let mut in_escape = false;
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let spaces = TAB_WIDTH - column % TAB_WIDTH;
+                out.push_str(&" ".repeat(spaces));
+                column += spaces;
+            }
+            '\x1b' => {
+                in_escape = true;
+                out.push(c);
+            }
+            _ if in_escape => {
+                if c.is_ascii_alphabetic() {
+                    in_escape = false;
+                }
+                out.push(c);
+            }
+            _ => {
+                column += c.width().unwrap_or(0);
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// Char ranges of every occurrence of the search pattern in `line`.
+fn find_matches(line: &str, search: &Search) -> Vec<(usize, usize)> {
+    let (haystack, needle) = if search.ignore_case {
+        (line.to_lowercase(), search.pattern.to_lowercase())
+    } else {
+        (line.to_string(), search.pattern.clone())
+    };
+    if needle.is_empty() || haystack.chars().count() != line.chars().count() {
+        return Vec::new();
+    }
+
+    let haystack: Vec<char> = haystack.chars().collect();
+    let needle: Vec<char> = needle.chars().collect();
+    let mut matches = Vec::new();
+    let mut idx = 0;
+    while idx + needle.len() <= haystack.len() {
+        if haystack[idx..idx + needle.len()] == needle[..] {
+            matches.push((idx, idx + needle.len()));
+            idx += needle.len();
+        } else {
+            idx += 1;
+        }
+    }
+    matches
+}
+
+/// Paints the characters inside `ranges` in the `match` style of the theme.
+fn highlight(runs: Vec<(Style, String)>, ranges: &[(usize, usize)]) -> Vec<(Style, String)> {
+    if ranges.is_empty() {
+        return runs;
+    }
+    let match_style = theme::current().style(Role::Match).to_ratatui();
+    let mut out: Vec<(Style, String)> = Vec::new();
+    let mut position = 0;
+    for (style, text) in runs {
+        for c in text.chars() {
+            let matched = ranges
+                .iter()
+                .any(|&(start, end)| start <= position && position < end);
+            let style = if matched {
+                style.patch(match_style)
+            } else {
+                style
+            };
+            match out.last_mut() {
+                Some((last, run)) if *last == style => run.push(c),
+                _ => out.push((style, c.to_string())),
+            }
+            position += 1;
+        }
+    }
+    out
+}
+
+/// The rows `wrap` splits a line of plain text into.
+fn row_count(line: &str, width: usize) -> usize {
+    let mut rows = 1;
+    let mut column = 0;
+    for c in line.chars() {
+        let c_width = c.width().unwrap_or(0);
+        if width > 0 && column + c_width > width {
+            rows += 1;
+            column = 0;
+        }
+        column += c_width;
+    }
+    rows
+}
+
+fn wrap(runs: Vec<(Style, String)>, width: usize) -> Vec<Line<'static>> {
+    let mut rows = Vec::new();
+    let mut row: Vec<Span> = Vec::new();
+    let mut column = 0;
+    for (style, text) in runs {
+        let mut chunk = String::new();
+        for c in text.chars() {
+            let c_width = c.width().unwrap_or(0);
+            if width > 0 && column + c_width > width {
+                if !chunk.is_empty() {
+                    row.push(Span::styled(std::mem::take(&mut chunk), style));
+                }
+                rows.push(Line::from(std::mem::take(&mut row)));
+                column = 0;
+            }
+            chunk.push(c);
+            column += c_width;
+        }
+        if !chunk.is_empty() {
+            row.push(Span::styled(chunk, style));
+        }
+    }
+    rows.push(Line::from(row));
+    rows
+}
+
+fn chop(runs: Vec<(Style, String)>, left: usize, width: usize) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut column = 0;
+    for (style, text) in runs {
+        let mut chunk = String::new();
+        for c in text.chars() {
+            let c_width = c.width().unwrap_or(0);
+            if column >= left && column + c_width <= left + width {
+                chunk.push(c);
+            }
+            column += c_width;
+        }
+        if !chunk.is_empty() {
+            spans.push(Span::styled(chunk, style));
+        }
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+
+    /// The rows of the screen after drawing `pager` on a `width` by `height` terminal.
+    fn screen(pager: &mut Pager, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| pager.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                let row: String = (0..width).map(|x| buffer.get(x, y).symbol()).collect();
+                row.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_end_shows_wrapped_last_lines() {
+        let text = format!("1\n2\n3\n4\n5\n{}end\n", "x".repeat(60));
+        let mut pager = Pager::new("t", &text);
+        screen(&mut pager, 30, 5);
+
+        pager.scroll_to_end();
+        let rows = screen(&mut pager, 30, 5);
+        assert_eq!(
+            rows[..4],
+            ["5", "x".repeat(30).as_str(), "x".repeat(30).as_str(), "end"]
+        );
+        assert!(rows[4].ends_with("(END)"), "{}", rows[4]);
+
+        pager.scroll_up(1);
+        let rows = screen(&mut pager, 30, 5);
+        assert_eq!(rows[0], "4");
+        assert!(!rows[4].contains("(END)"), "{}", rows[4]);
+    }
+
+    #[test]
+    fn a_last_line_taller_than_the_screen_is_reached() {
+        let text = format!("1\n2\n{}", "y".repeat(200));
+        let mut pager = Pager::new("t", &text);
+        screen(&mut pager, 30, 5);
+
+        pager.goto_line(3);
+        let rows = screen(&mut pager, 30, 5);
+        assert_eq!(rows[0], "y".repeat(30));
+        assert!(!rows[4].contains("(END)"), "{}", rows[4]);
+
+        pager.wrap = false;
+        pager.scroll_to_end();
+        let rows = screen(&mut pager, 30, 5);
+        assert_eq!(rows[..3], ["1", "2", "y".repeat(30).as_str()]);
+        assert!(rows[4].ends_with("(END)"), "{}", rows[4]);
+    }
+}
@@ -0,0 +1,300 @@
+//! Records passed between the structured commands of a pipeline.
+//!
+//! Commands such as `ls` and `todo list` print their records as one JSON
+//! object per line when the next stage reads records, so `where`, `select`,
+//! `sort-by` and friends can work on fields instead of text columns.
+
+use std::{cmp::Ordering, fmt, io::Read};
+
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::Value;
+
+use crate::cmd::{error::Error, output::Format, CMD};
+
+pub mod csv;
+pub mod render;
+
+/// The column holding values that were not objects, e.g. the numbers of `[1, 2]`.
+const VALUE_COLUMN: &str = "value";
+
+/// One row of a table. Unlike a JSON object it keeps its columns in the
+/// order they were produced.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    /// Wraps a value that is not an object.
+    fn scalar(value: Value) -> Self {
+        Self {
+            fields: vec![(VALUE_COLUMN.to_string(), value)],
+        }
+    }
+
+    /// Looks a column up; `a.b` reaches into nested objects unless a column
+    /// has that very name.
+    pub fn get(&self, column: &str) -> Option<&Value> {
+        if let Some((_, value)) = self.fields.iter().find(|(name, _)| name == column) {
+            return Some(value);
+        }
+        let mut path = column.split('.');
+        let first = path.next()?;
+        let mut value = &self.fields.iter().find(|(name, _)| name == first)?.1;
+        for key in path {
+            value = match value {
+                Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+                _ => value.get(key)?,
+            };
+        }
+        Some(value)
+    }
+
+    /// Sets a column, adding it at the end when it is new.
+    pub fn insert(&mut self, column: &str, value: Value) {
+        match self.fields.iter_mut().find(|(name, _)| name == column) {
+            Some((_, old)) => *old = value,
+            None => self.fields.push((column.to_string(), value)),
+        }
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|(name, _)| name.as_str())
+    }
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for (name, value) in self.fields.iter() {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Record {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = deserializer.deserialize_any(RowsVisitor { nested: true })?;
+        Ok(value.into_iter().next().unwrap_or_default())
+    }
+}
+
+/// Everything one JSON value of the input stands for: an object is one
+/// record, an array at the top level is a record per element and any other
+/// value a record with a single `value` column.
+struct Rows(Vec<Record>);
+
+impl<'de> Deserialize<'de> for Rows {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_any(RowsVisitor { nested: false })
+            .map(Rows)
+    }
+}
+
+/// `nested` is set for the elements of a top-level array, whose own arrays
+/// are kept as values instead of being split into more records.
+struct RowsVisitor {
+    nested: bool,
+}
+
+impl RowsVisitor {
+    fn scalar<E>(value: Value) -> Result<Vec<Record>, E> {
+        Ok(vec![Record::scalar(value)])
+    }
+}
+
+impl<'de> Visitor<'de> for RowsVisitor {
+    type Value = Vec<Record>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut record = Record::default();
+        while let Some((name, value)) = map.next_entry::<String, Value>()? {
+            record.insert(&name, value);
+        }
+        Ok(vec![record])
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        if self.nested {
+            let mut items = Vec::new();
+            while let Some(item) = seq.next_element::<Value>()? {
+                items.push(item);
+            }
+            return Self::scalar(Value::Array(items));
+        }
+        let mut records = Vec::new();
+        while let Some(record) = seq.next_element::<Record>()? {
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Self::scalar(Value::from(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Self::scalar(Value::from(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Self::scalar(Value::from(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Self::scalar(Value::from(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Self::scalar(Value::from(value))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Self::scalar(Value::Null)
+    }
+}
+
+/// The columns of all records, in the order they first appear.
+pub fn columns(records: &[Record]) -> Vec<&str> {
+    let mut columns = Vec::new();
+    for record in records {
+        for column in record.columns() {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+    }
+    columns
+}
+
+/// Parses NDJSON, a JSON array or any sequence of JSON values into records.
+pub fn parse(command: &str, text: &str) -> Result<Vec<Record>, Error> {
+    let mut records = Vec::new();
+    for rows in serde_json::Deserializer::from_str(text).into_iter::<Rows>() {
+        let Rows(rows) =
+            rows.map_err(|err| Error::failed(command, format!("invalid JSON input: {}", err)))?;
+        records.extend(rows);
+    }
+    Ok(records)
+}
+
+/// The whole output of the previous stage of the pipeline.
+pub fn read_text(command: &str, vars: &CMD) -> Result<String, Error> {
+    if !vars.has_piped_input() {
+        return Err(Error::usage(
+            command,
+            format!("expects input from a pipeline, e.g. 'ls | {}'", command),
+        ));
+    }
+    let mut text = String::new();
+    vars.stdin()
+        .read_to_string(&mut text)
+        .map_err(|err| Error::io(command, "standard input", err))?;
+    Ok(text)
+}
+
+/// Reads the records written by the previous stage of the pipeline.
+pub fn read(command: &str, vars: &CMD) -> Result<Vec<Record>, Error> {
+    parse(command, &read_text(command, vars)?)
+}
+
+/// Prints records as a table, or as JSON for a later stage or `--json`.
+pub fn emit(command: &str, format: Format, records: &[Record]) -> Result<(), Error> {
+    match format {
+        Format::Text => {
+            print!("{}", render::table(records));
+            Ok(())
+        }
+        _ => format.print(command, records),
+    }
+}
+
+/// Orders two cells of a column: numbers numerically, strings and booleans
+/// naturally, and missing values or values of different types last.
+pub fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => compare_values(a, b).unwrap_or_else(|| rank(a).cmp(&rank(b))),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Where a value of each type goes when types are mixed.
+fn rank(value: &Value) -> u8 {
+    match value {
+        Value::Number(_) => 0,
+        Value::String(_) => 1,
+        Value::Bool(_) => 2,
+        Value::Array(_) | Value::Object(_) => 3,
+        Value::Null => 4,
+    }
+}
+
+/// `None` when the values cannot be compared.
+pub fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+/// Reads `literal` as a value of the same type as `like`, so `size > 1kb`
+/// compares numbers and `mode == 0644` compares strings.
+pub fn literal_like(like: &Value, literal: &str) -> Option<Value> {
+    match like {
+        Value::Number(_) => parse_number(literal).map(Value::from),
+        Value::Bool(_) => literal.parse::<bool>().ok().map(Value::from),
+        Value::Null => (literal == "null").then_some(Value::Null),
+        _ => Some(Value::from(literal)),
+    }
+}
+
+/// A number, optionally followed by a size unit: `1.5`, `4k`, `1kb`, `2MiB`.
+/// Units are powers of 1024, as with `ls -h`.
+pub fn parse_number(text: &str) -> Option<f64> {
+    let lower = text.to_ascii_lowercase();
+    let digits_end = lower
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(lower.len());
+    let (digits, unit) = lower.split_at(digits_end);
+    let number: f64 = digits.parse().ok()?;
+    let unit = unit
+        .strip_suffix("ib")
+        .or_else(|| unit.strip_suffix('b'))
+        .unwrap_or(unit);
+    let power = match unit {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        _ => return None,
+    };
+    Some(number * 1024f64.powi(power))
+}
+
+/// How a cell is shown in a table.
+pub fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => format!("[{} items]", items.len()),
+        Value::Object(fields) => format!("{{{} fields}}", fields.len()),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use serde_json::Value;
+use unicode_width::UnicodeWidthStr;
+
+use super::{cell_text, columns, Record};
+use crate::theme::Role;
+
+/// Draws records with rounded borders, a `#` column with the row index and
+/// a column per field, in the order the fields first appear.
+///
+/// ```text
+/// ╭───┬──────┬──────╮
+/// │ # │ name │ size │
+/// ├───┼──────┼──────┤
+/// │ 0 │ a    │    3 │
+/// ╰───┴──────┴──────╯
+/// ```
+pub fn table(records: &[Record]) -> String {
+    if records.is_empty() {
+        return String::new();
+    }
+
+    let columns = columns(records);
+    let mut headers = vec!["#".to_string()];
+    headers.extend(columns.iter().map(|column| column.to_string()));
+    // Every cell with whether it is aligned to the right, like numbers are.
+    let rows: Vec<Vec<(String, bool)>> = records
+        .iter()
+        .enumerate()
+        .map(|(idx, record)| {
+            let mut row = vec![(idx.to_string(), true)];
+            row.extend(columns.iter().map(|column| match record.get(column) {
+                Some(value) => (
+                    cell_text(value).replace('\n', " "),
+                    matches!(value, Value::Number(_)),
+                ),
+                None => (String::new(), false),
+            }));
+            row
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|col| {
+            rows.iter()
+                .map(|row| row[col].0.width())
+                .chain([headers[col].width()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let lines: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+        format!("{}{}{}\n", left, lines.join(middle), right)
+    };
+
+    let mut out = border("╭", "┬", "╮");
+    out.push('│');
+    for (header, width) in headers.iter().zip(widths.iter()) {
+        let padding = " ".repeat(width - header.width());
+        let _ = write!(out, " {}{} │", Role::Header.paint(header), padding);
+    }
+    out.push('\n');
+    out.push_str(&border("├", "┼", "┤"));
+    for row in rows.iter() {
+        out.push('│');
+        for (col, ((text, right), width)) in row.iter().zip(widths.iter()).enumerate() {
+            let padding = " ".repeat(width - text.width());
+            let text = if col == 0 {
+                Role::Muted.paint(text).to_string()
+            } else {
+                text.to_string()
+            };
+            if *right {
+                let _ = write!(out, " {}{} │", padding, text);
+            } else {
+                let _ = write!(out, " {}{} │", text, padding);
+            }
+        }
+        out.push('\n');
+    }
+    out.push_str(&border("╰", "┴", "╯"));
+    out
+}
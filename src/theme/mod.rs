@@ -0,0 +1,510 @@
+//! Styles of the colored output, looked up by what is shown instead of being
+//! hard-coded as colors in every command.
+//!
+//! A theme file maps roles to styles, one per line:
+//!
+//! ```text
+//! # comments start with '#'
+//! dir = bold blue
+//! prompt-cwd = bold white on_green
+//! todo-done = strikethrough #5faf5f
+//! ```
+//!
+//! A style is any number of words: a foreground color (`red`,
+//! `bright_black`, `#rrggbb`), a background color with an `on_` prefix and
+//! the attributes `bold`, `dimmed`, `italic`, `underline`, `reversed` and
+//! `strikethrough`. Roles a file leaves out keep their style from `dark`.
+
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{OnceLock, RwLock, RwLockReadGuard},
+};
+
+use colored::{Color, ColoredString, Colorize};
+
+mod presets;
+
+pub use self::presets::PRESETS;
+
+/// Names a theme by preset name, file name or path, picked up at startup.
+const THEME_VAR: &str = "CLI_THEME";
+const FILE_EXTENSION: &str = "theme";
+
+/// What a piece of colored output shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Dir,
+    File,
+    Executable,
+    Symlink,
+    /// A symlink whose target is missing.
+    BrokenLink,
+    Error,
+    Warning,
+    Hint,
+    PromptTime,
+    PromptCwd,
+    TodoDone,
+    TodoPending,
+    /// Search matches, e.g. in `less`.
+    Match,
+    /// The directory printed by `pwd`.
+    Path,
+    /// The text printed by `echo`, plain in the presets.
+    Echo,
+    /// Lines only in the new file of a `diff`.
+    Added,
+    /// Lines only in the old file of a `diff`.
+    Removed,
+    /// The `---`/`+++` lines of a unified diff.
+    DiffHeader,
+    /// The `@@` lines of a unified diff.
+    DiffHunk,
+    /// Column headers of tables and `df`.
+    Header,
+    LineNumber,
+    /// Secondary text such as the row numbers of tables.
+    Muted,
+    /// Command names and options in manual pages.
+    Keyword,
+    /// Keywords of highlighted source, e.g. by `cat`.
+    SyntaxKeyword,
+    /// `true`, `None` and similar constants.
+    SyntaxLiteral,
+    SyntaxString,
+    SyntaxNumber,
+    SyntaxComment,
+    SyntaxType,
+    SyntaxFunction,
+    SyntaxMacro,
+    /// Keys of TOML and JSON.
+    SyntaxKey,
+    SyntaxVariable,
+    /// Markdown headings.
+    SyntaxHeading,
+    /// Markdown bold and italic text.
+    SyntaxEmphasis,
+    /// Markdown links.
+    SyntaxLink,
+    SyntaxPunctuation,
+}
+
+impl Role {
+    pub const ALL: [Role; 37] = [
+        Role::Dir,
+        Role::File,
+        Role::Executable,
+        Role::Symlink,
+        Role::BrokenLink,
+        Role::Error,
+        Role::Warning,
+        Role::Hint,
+        Role::PromptTime,
+        Role::PromptCwd,
+        Role::TodoDone,
+        Role::TodoPending,
+        Role::Match,
+        Role::Path,
+        Role::Echo,
+        Role::Added,
+        Role::Removed,
+        Role::DiffHeader,
+        Role::DiffHunk,
+        Role::Header,
+        Role::LineNumber,
+        Role::Muted,
+        Role::Keyword,
+        Role::SyntaxKeyword,
+        Role::SyntaxLiteral,
+        Role::SyntaxString,
+        Role::SyntaxNumber,
+        Role::SyntaxComment,
+        Role::SyntaxType,
+        Role::SyntaxFunction,
+        Role::SyntaxMacro,
+        Role::SyntaxKey,
+        Role::SyntaxVariable,
+        Role::SyntaxHeading,
+        Role::SyntaxEmphasis,
+        Role::SyntaxLink,
+        Role::SyntaxPunctuation,
+    ];
+
+    /// The name used in theme files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Dir => "dir",
+            Role::File => "file",
+            Role::Executable => "executable",
+            Role::Symlink => "symlink",
+            Role::BrokenLink => "broken-link",
+            Role::Error => "error",
+            Role::Warning => "warning",
+            Role::Hint => "hint",
+            Role::PromptTime => "prompt-time",
+            Role::PromptCwd => "prompt-cwd",
+            Role::TodoDone => "todo-done",
+            Role::TodoPending => "todo-pending",
+            Role::Match => "match",
+            Role::Path => "path",
+            Role::Echo => "echo",
+            Role::Added => "added",
+            Role::Removed => "removed",
+            Role::DiffHeader => "diff-header",
+            Role::DiffHunk => "diff-hunk",
+            Role::Header => "header",
+            Role::LineNumber => "line-number",
+            Role::Muted => "muted",
+            Role::Keyword => "keyword",
+            Role::SyntaxKeyword => "syntax-keyword",
+            Role::SyntaxLiteral => "syntax-literal",
+            Role::SyntaxString => "syntax-string",
+            Role::SyntaxNumber => "syntax-number",
+            Role::SyntaxComment => "syntax-comment",
+            Role::SyntaxType => "syntax-type",
+            Role::SyntaxFunction => "syntax-function",
+            Role::SyntaxMacro => "syntax-macro",
+            Role::SyntaxKey => "syntax-key",
+            Role::SyntaxVariable => "syntax-variable",
+            Role::SyntaxHeading => "syntax-heading",
+            Role::SyntaxEmphasis => "syntax-emphasis",
+            Role::SyntaxLink => "syntax-link",
+            Role::SyntaxPunctuation => "syntax-punctuation",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|role| role.name() == name)
+    }
+
+    /// `text` in the style the current theme gives this role.
+    pub fn paint(self, text: &str) -> ColoredString {
+        let style = current().style(self);
+        style.paint(text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    Bold,
+    Dimmed,
+    Italic,
+    Underline,
+    Reversed,
+    Strikethrough,
+}
+
+impl Attribute {
+    const ALL: [Attribute; 6] = [
+        Attribute::Bold,
+        Attribute::Dimmed,
+        Attribute::Italic,
+        Attribute::Underline,
+        Attribute::Reversed,
+        Attribute::Strikethrough,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Attribute::Bold => "bold",
+            Attribute::Dimmed => "dimmed",
+            Attribute::Italic => "italic",
+            Attribute::Underline => "underline",
+            Attribute::Reversed => "reversed",
+            Attribute::Strikethrough => "strikethrough",
+        }
+    }
+}
+
+/// Colors and attributes of one role.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub attributes: Vec<Attribute>,
+}
+
+fn parse_color(word: &str) -> Option<Color> {
+    if let Some(hex) = word.strip_prefix('#') {
+        let channel = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+        return match hex.len() {
+            6 => Some(Color::TrueColor {
+                r: channel(0)?,
+                g: channel(2)?,
+                b: channel(4)?,
+            }),
+            _ => None,
+        };
+    }
+    Color::from_str(&word.replace('_', " ")).ok()
+}
+
+fn color_name(color: Color) -> String {
+    match color {
+        Color::TrueColor { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Black => "black".to_string(),
+        Color::Red => "red".to_string(),
+        Color::Green => "green".to_string(),
+        Color::Yellow => "yellow".to_string(),
+        Color::Blue => "blue".to_string(),
+        Color::Magenta => "magenta".to_string(),
+        Color::Cyan => "cyan".to_string(),
+        Color::White => "white".to_string(),
+        Color::BrightBlack => "bright_black".to_string(),
+        Color::BrightRed => "bright_red".to_string(),
+        Color::BrightGreen => "bright_green".to_string(),
+        Color::BrightYellow => "bright_yellow".to_string(),
+        Color::BrightBlue => "bright_blue".to_string(),
+        Color::BrightMagenta => "bright_magenta".to_string(),
+        Color::BrightCyan => "bright_cyan".to_string(),
+        Color::BrightWhite => "bright_white".to_string(),
+    }
+}
+
+impl Style {
+    /// Parses the words after `=` in a theme file.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut style = Style::default();
+        for word in text.split_whitespace() {
+            let word = word.to_ascii_lowercase();
+            if let Some(attribute) = Attribute::ALL.into_iter().find(|a| a.name() == word) {
+                style.attributes.push(attribute);
+            } else if let Some(color) = word.strip_prefix("on_").and_then(parse_color) {
+                style.background = Some(color);
+            } else if let Some(color) = parse_color(&word) {
+                style.foreground = Some(color);
+            } else {
+                return Err(format!("unknown color or attribute '{}'", word));
+            }
+        }
+        Ok(style)
+    }
+
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let mut painted = ColoredString::from(text);
+        if let Some(color) = self.foreground {
+            painted = painted.color(color);
+        }
+        if let Some(color) = self.background {
+            painted = painted.on_color(color);
+        }
+        for attribute in self.attributes.iter() {
+            painted = match attribute {
+                Attribute::Bold => painted.bold(),
+                Attribute::Dimmed => painted.dimmed(),
+                Attribute::Italic => painted.italic(),
+                Attribute::Underline => painted.underline(),
+                Attribute::Reversed => painted.reversed(),
+                Attribute::Strikethrough => painted.strikethrough(),
+            };
+        }
+        painted
+    }
+
+    /// The same style for the full screen views drawn with ratatui.
+    pub fn to_ratatui(&self) -> ratatui::style::Style {
+        use ratatui::style::{Color as Tui, Modifier};
+
+        let convert = |color: Color| match color {
+            Color::Black => Tui::Black,
+            Color::Red => Tui::Red,
+            Color::Green => Tui::Green,
+            Color::Yellow => Tui::Yellow,
+            Color::Blue => Tui::Blue,
+            Color::Magenta => Tui::Magenta,
+            Color::Cyan => Tui::Cyan,
+            Color::White => Tui::Gray,
+            Color::BrightBlack => Tui::DarkGray,
+            Color::BrightRed => Tui::LightRed,
+            Color::BrightGreen => Tui::LightGreen,
+            Color::BrightYellow => Tui::LightYellow,
+            Color::BrightBlue => Tui::LightBlue,
+            Color::BrightMagenta => Tui::LightMagenta,
+            Color::BrightCyan => Tui::LightCyan,
+            Color::BrightWhite => Tui::White,
+            Color::TrueColor { r, g, b } => Tui::Rgb(r, g, b),
+        };
+        let mut style = ratatui::style::Style::default();
+        if let Some(color) = self.foreground {
+            style = style.fg(convert(color));
+        }
+        if let Some(color) = self.background {
+            style = style.bg(convert(color));
+        }
+        for attribute in self.attributes.iter() {
+            style = style.add_modifier(match attribute {
+                Attribute::Bold => Modifier::BOLD,
+                Attribute::Dimmed => Modifier::DIM,
+                Attribute::Italic => Modifier::ITALIC,
+                Attribute::Underline => Modifier::UNDERLINED,
+                Attribute::Reversed => Modifier::REVERSED,
+                Attribute::Strikethrough => Modifier::CROSSED_OUT,
+            });
+        }
+        style
+    }
+}
+
+impl fmt::Display for Style {
+    /// Writes the style back in the theme file syntax.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words: Vec<String> = self
+            .attributes
+            .iter()
+            .map(|attribute| attribute.name().to_string())
+            .collect();
+        if let Some(color) = self.foreground {
+            words.push(color_name(color));
+        }
+        if let Some(color) = self.background {
+            words.push(format!("on_{}", color_name(color)));
+        }
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// A style for every role.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    styles: HashMap<Role, Style>,
+}
+
+impl Theme {
+    /// Parses a theme file on top of `base`, so it only has to list the
+    /// roles it changes.
+    pub fn parse(name: &str, text: &str, base: Option<&Theme>) -> Result<Self, String> {
+        let mut styles = base.map(|base| base.styles.clone()).unwrap_or_default();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("{}: line {}: {}", name, idx + 1, message);
+            let (role, style) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected 'role = style'".to_string()))?;
+            let role = Role::from_name(role.trim())
+                .ok_or_else(|| error(format!("unknown role '{}'", role.trim())))?;
+            styles.insert(role, Style::parse(style).map_err(error)?);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            styles,
+        })
+    }
+
+    /// One of the built-in themes.
+    pub fn preset(name: &str) -> Option<Self> {
+        let (_, text) = PRESETS.iter().find(|(preset, _)| *preset == name)?;
+        let base = match name {
+            presets::DEFAULT => None,
+            _ => Some(Self::default()),
+        };
+        Some(Self::parse(name, text, base.as_ref()).expect("built-in themes are valid"))
+    }
+
+    /// Finds a theme by preset name, by name in the themes directory, or as
+    /// a path relative to `cwd`.
+    pub fn load(name: &str, cwd: &Path) -> Result<Self, String> {
+        if let Some(preset) = Self::preset(name) {
+            return Ok(preset);
+        }
+        let path = match themes_dir() {
+            Some(dir) if !name.contains('/') && dir.join(file_name(name)).is_file() => {
+                dir.join(file_name(name))
+            }
+            _ => cwd.join(name),
+        };
+        if !name.contains('/') && !path.exists() {
+            return Err(format!("unknown theme '{}'", name));
+        }
+        let text =
+            fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| name.to_string());
+        Self::parse(&name, &text, Some(&Self::default()))
+    }
+
+    pub fn style(&self, role: Role) -> Style {
+        self.styles.get(&role).cloned().unwrap_or_default()
+    }
+
+    /// The theme in the file syntax, every role included.
+    pub fn to_file(&self) -> String {
+        let mut out = format!("# {}\n", self.name);
+        for role in Role::ALL {
+            out.push_str(&format!("{} = {}\n", role.name(), self.style(role)));
+        }
+        out
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(presets::DEFAULT).expect("the default theme exists")
+    }
+}
+
+fn file_name(name: &str) -> String {
+    format!("{}.{}", name, FILE_EXTENSION)
+}
+
+/// `$XDG_CONFIG_HOME/cli-util/themes`, or `~/.config/cli-util/themes`.
+pub fn themes_dir() -> Option<PathBuf> {
+    let config_home = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config_home.join("cli-util").join("themes"))
+}
+
+/// Names of the themes in the themes directory.
+pub fn installed() -> Vec<String> {
+    let Some(entries) = themes_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == FILE_EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+fn slot() -> &'static RwLock<Theme> {
+    static CURRENT: OnceLock<RwLock<Theme>> = OnceLock::new();
+    CURRENT.get_or_init(|| RwLock::new(Theme::default()))
+}
+
+/// The theme every command paints with.
+pub fn current() -> RwLockReadGuard<'static, Theme> {
+    slot()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Switches the theme for everything printed from now on.
+pub fn set(theme: Theme) {
+    *slot()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = theme;
+}
+
+/// Applies the theme named by `CLI_THEME`, if any.
+pub fn load_from_env(cwd: &Path) -> Result<(), String> {
+    match env::var(THEME_VAR) {
+        Ok(name) if !name.is_empty() => {
+            set(Theme::load(&name, cwd)?);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,133 @@
+//! The built-in themes, written in the theme file syntax.
+
+/// The theme used unless another is chosen, and the base of all others.
+pub const DEFAULT: &str = "dark";
+
+const DARK: &str = "
+dir = cyan
+file = magenta
+executable = magenta
+symlink = yellow
+broken-link = red
+error = red
+warning = yellow
+hint = yellow
+prompt-time = black on_bright_yellow
+prompt-cwd = bold white on_green
+todo-done = strikethrough green
+todo-pending = red
+match = reversed
+path = cyan
+echo =
+added = green
+removed = red
+diff-header = bold
+diff-hunk = cyan
+header = bold green
+line-number = bright_black
+muted = dimmed
+keyword = bold
+syntax-keyword = magenta
+syntax-literal = bright_yellow
+syntax-string = green
+syntax-number = yellow
+syntax-comment = italic bright_black
+syntax-type = cyan
+syntax-function = blue
+syntax-macro = bright_cyan
+syntax-key = blue
+syntax-variable = bright_red
+syntax-heading = bold bright_blue
+syntax-emphasis = bold
+syntax-link = underline blue
+syntax-punctuation = bright_black
+";
+
+/// Darker colors that stay readable on a white background.
+const LIGHT: &str = "
+dir = bold blue
+file = black
+executable = bold green
+symlink = magenta
+broken-link = bold red
+error = red
+warning = #af5f00
+hint = blue
+prompt-time = white on_blue
+prompt-cwd = bold black on_bright_cyan
+todo-done = strikethrough bright_black
+todo-pending = bold black
+match = black on_bright_yellow
+path = blue
+echo =
+added = green
+removed = red
+diff-header = bold
+diff-hunk = magenta
+header = bold blue
+line-number = bright_black
+muted = bright_black
+keyword = bold
+syntax-keyword = magenta
+syntax-literal = #af5f00
+syntax-string = green
+syntax-number = #af5f00
+syntax-comment = italic bright_black
+syntax-type = blue
+syntax-function = #005faf
+syntax-macro = magenta
+syntax-key = blue
+syntax-variable = red
+syntax-heading = bold blue
+syntax-emphasis = bold
+syntax-link = underline blue
+syntax-punctuation = bright_black
+";
+
+/// Bright, bold colors and no dimmed text.
+const HIGH_CONTRAST: &str = "
+dir = bold bright_cyan
+file = bright_white
+executable = bold bright_green
+symlink = bold bright_yellow
+broken-link = bold bright_white on_red
+error = bold bright_red
+warning = bold bright_yellow
+hint = bold bright_white
+prompt-time = bold black on_bright_white
+prompt-cwd = bold black on_bright_yellow
+todo-done = bold bright_green
+todo-pending = bold bright_red
+match = bold black on_bright_yellow
+path = bold bright_cyan
+echo =
+added = bold bright_green
+removed = bold bright_red
+diff-header = bold bright_white
+diff-hunk = bold bright_cyan
+header = bold underline bright_white
+line-number = bright_white
+muted = bright_white
+keyword = bold bright_white
+syntax-keyword = bold bright_magenta
+syntax-literal = bold bright_yellow
+syntax-string = bright_green
+syntax-number = bright_yellow
+syntax-comment = italic bright_white
+syntax-type = bright_cyan
+syntax-function = bright_blue
+syntax-macro = bold bright_cyan
+syntax-key = bright_blue
+syntax-variable = bright_red
+syntax-heading = bold underline bright_white
+syntax-emphasis = bold
+syntax-link = underline bright_cyan
+syntax-punctuation = bright_white
+";
+
+/// Every preset with its name, as `theme -l` lists them.
+pub const PRESETS: [(&str, &str); 3] = [
+    (DEFAULT, DARK),
+    ("light", LIGHT),
+    ("high-contrast", HIGH_CONTRAST),
+];